@@ -35,6 +35,8 @@ def run():
     # create and run the Tk GUI
     weather_config = PyWeatherConfig(dirname=options.dir, logfile='weather.log', log_level=options.verbose)
     try:
+        for problem in weather_config.validate():
+            print('Configuration: %s' % problem, file=stderr)
         TkWeather(weather_config).execute()
     except SystemError as e:
         print('System error: %s' % e, file=stderr)
@@ -75,11 +75,13 @@ class AddHistory:
     def _get_history(self):
         try:
             history_client = self._weather_data.backend.get_history_client()
-            history_client.execute(location=self._location, date_range=self._date_range)
-            get_history = GetHistory(self._parent, self._location, history_client)
-            if not get_history.is_canceled:
+            handle = history_client.execute(location=self._location, date_range=self._date_range)
+            get_history = GetHistory(self._parent, self._location, history_client, handle)
+            if get_history.is_canceled:
+                history_client.cancel(handle)
+            else:
                 # the history client won't know there was a server error until you try and get the response
-                daily_histories = history_client.get()
+                daily_histories = history_client.get(handle)
                 self._weather_data.backend.add_histories(daily_histories)
                 history_count = len(daily_histories.histories)
                 _info(f'{history_count} histories were added to {self._location.name}.')
@@ -135,8 +137,9 @@ class DateSelector(Dialog):
 
 
 class GetHistory(Dialog):
-    def __init__(self, parent, location: PyLocation, history_client: PyHistoryClient):
+    def __init__(self, parent, location: PyLocation, history_client: PyHistoryClient, handle: int):
         self._history_client = history_client
+        self._handle = handle
         self._location = location
         self._callback_id: Optional[str] = None
         self._progress_step: Optional[tk.DoubleVar] = None
@@ -158,7 +161,7 @@ class GetHistory(Dialog):
         def callback():
             nonlocal direction
             # check if the client received the response
-            if self._history_client.poll():
+            if self._history_client.poll(self._handle):
                 self._is_canceled = False
                 self._callback_id = None
                 self.ok()
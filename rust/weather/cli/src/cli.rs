@@ -16,16 +16,26 @@ use toolslib::logs;
 use weather_lib::{
     admin_prelude::create_weather_admin,
     location_filter, location_filters,
-    prelude::{create_weather_data, LocationFilters, WeatherData},
+    prelude::{create_weather_data, LocationFilters, WeatherData, MEMORY_DIRNAME},
 };
 
 mod admin;
 use admin::Admin;
 
+mod config;
+use config::ConfigCmd;
+
 mod user;
 use user::User;
 
 mod reports;
+
+mod serve;
+use serve::ServeCmd;
+
+mod sql;
+use sql::SqlCmd;
+
 mod tui;
 
 /// The command line interface result.
@@ -96,8 +106,11 @@ pub fn command() -> Command {
         .args(CommandLineArgs::get())
         // the subcommands
         .subcommands(User::get_commands())
+        .subcommand(ServeCmd::get())
+        .subcommand(SqlCmd::get())
         .subcommand(TerminalUI::get())
         .subcommand(Admin::get())
+        .subcommand(ConfigCmd::get())
 }
 
 /// This is a mainline helper that prepares the runtime environment and runs the command.
@@ -159,6 +172,8 @@ fn run(mut args: ArgMatches) -> Result<()> {
     let command_args = CommandLineArgs::from(&args);
     match name.as_str() {
         Admin::NAME => run_admin(command_args, subcommand_args),
+        ConfigCmd::NAME => ConfigCmd::run(command_args, subcommand_args),
+        SqlCmd::NAME => SqlCmd::run(command_args, subcommand_args),
         _ => run_user(&name, command_args, subcommand_args),
     }
 }
@@ -171,8 +186,10 @@ fn run(mut args: ArgMatches) -> Result<()> {
 /// * `args` holds the arguments from the parsed command line.
 /// 
 fn run_admin(command_args: CommandLineArgs, args: ArgMatches) -> Result<()> {
+    let config_file = command_args.config_file();
+    let profile = command_args.profile();
     let weather_dir = command_args.weather_dir();
-    let weather_admin = create_weather_admin(weather_dir)?;
+    let weather_admin = create_weather_admin(config_file, profile, weather_dir)?;
     Admin::run(&weather_admin, args)
 }
 
@@ -186,10 +203,12 @@ fn run_admin(command_args: CommandLineArgs, args: ArgMatches) -> Result<()> {
 ///
 fn run_user(name: &str, command_args: CommandLineArgs, args: ArgMatches) -> Result<()> {
     let config_file = command_args.config_file();
+    let profile = command_args.profile();
     let weather_dir = command_args.weather_dir();
     let no_db = command_args.no_db();
-    let weather_data = create_weather_data(config_file, weather_dir, no_db)?;
+    let weather_data = create_weather_data(config_file, profile, weather_dir, no_db)?;
     match name {
+        ServeCmd::NAME => ServeCmd::run(weather_data, args),
         TerminalUI::NAME => TerminalUI::run_tui(weather_data, args),
         _ => User::run(&weather_data, name, args),
     }
@@ -275,6 +294,8 @@ pub struct CommandLineArgs<'a>(
 impl<'a> CommandLineArgs<'a> {
     /// The config file argument id.
     const CONFIG_FILE: &'static str = "CONFIG_FILE";
+    /// The configuration profile argument id.
+    const PROFILE: &'static str = "PROFILE";
     /// The weather directory argument id.
     const WEATHER_DIR: &'static str = "WEATHER_DIR";
     /// The log file argument id.
@@ -295,7 +316,12 @@ impl<'a> CommandLineArgs<'a> {
                 .value_name("FILE")
                 // .require_equals(true)
                 .value_parser(parse_filename)
-                .help("The configuration file pathname (DEFAULT weather.toml)."),
+                .help("The configuration file pathname (DEFAULT $WEATHER_CONFIG, weather.toml, $XDG_CONFIG_HOME/weather/weather.toml)."),
+            Arg::new(Self::PROFILE)
+                .long("profile")
+                .action(ArgAction::Set)
+                .value_name("NAME")
+                .help("The configuration profile name."),
             Arg::new(Self::WEATHER_DIR)
                 .short('d')
                 .long("directory")
@@ -303,7 +329,7 @@ impl<'a> CommandLineArgs<'a> {
                 .value_name("DIR")
                 // .require_equals(true)
                 .value_parser(Self::parse_weather_dir)
-                .help("The weather data directory pathname."),
+                .help("The weather data directory pathname (:memory: keeps weather data in memory)."),
             Arg::new(Self::FS)
                 .long("fs")
                 .action(ArgAction::SetTrue)
@@ -336,7 +362,7 @@ impl<'a> CommandLineArgs<'a> {
     /// * `dirname` is the weather directory command argument.
     fn parse_weather_dir(dirname: &str) -> std::result::Result<PathBuf, String> {
         let path = PathBuf::from(dirname);
-        if path.is_dir() {
+        if path.is_dir() || dirname == MEMORY_DIRNAME {
             Ok(path)
         } else if path.exists() {
             Err(format!("{} is not a directory.", dirname))
@@ -348,6 +374,10 @@ impl<'a> CommandLineArgs<'a> {
     pub fn config_file(&self) -> Option<PathBuf> {
         self.0.get_one::<PathBuf>(Self::CONFIG_FILE).map_or(Default::default(), |p| Some(p.clone()))
     }
+    /// Get the configuration profile argument.
+    pub fn profile(&self) -> Option<String> {
+        self.0.get_one::<String>(Self::PROFILE).cloned()
+    }
     /// Get the weather directory argument.
     pub fn weather_dir(&self) -> Option<PathBuf> {
         self.0.get_one::<PathBuf>(Self::WEATHER_DIR).map_or(Default::default(), |p| Some(p.clone()))
@@ -693,4 +723,36 @@ mod tests {
         assert_eq!(testcase[1].state, Some("state".into()));
         assert_eq!(testcase[1].name, Some("bar".into()));
     }
+
+    #[test]
+    fn memory_weather_data() {
+        use weather_lib::prelude::{DailyHistories, History, Location};
+        let weather_data = create_weather_data(None, None, Some(PathBuf::from(MEMORY_DIRNAME)), false).unwrap();
+        let location = Location {
+            city: "Tigard".to_string(),
+            state_id: "OR".to_string(),
+            state: "Oregon".to_string(),
+            name: "Tigard, OR".to_string(),
+            alias: "tigard".to_string(),
+            latitude: "45.4312".to_string(),
+            longitude: "-122.7717".to_string(),
+            tz: "America/Los_Angeles".to_string(),
+        };
+        weather_data.add_location(location.clone()).unwrap();
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 7, 4).unwrap();
+        let histories = vec![History { date, ..Default::default() }];
+        weather_data.add_histories(DailyHistories { location, histories }).unwrap();
+
+        // run the list history command against the in memory weather data
+        let report = std::env::temp_dir().join(format!("weather-cli-{}.json", std::process::id()));
+        let mut cmd = Command::new("test").no_binary_name(true).subcommands(User::get_commands());
+        let mut args = cmd.try_get_matches_from_mut(["lh", "--json", "-r", report.to_str().unwrap()]).unwrap();
+        let (name, args) = args.remove_subcommand().unwrap();
+        User::run(&weather_data, &name, args).unwrap();
+        let contents = std::fs::read_to_string(&report).unwrap();
+        std::fs::remove_file(&report).unwrap();
+        let testcase: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(testcase["history"][0]["location"], "Tigard, OR");
+        assert_eq!(testcase["history"][0]["dates"][0]["start"], "2024-07-04");
+    }
 }
@@ -3,21 +3,39 @@ use crate::cli::{self, Command};
 use clap::ArgMatches;
 use weather_lib::admin_prelude::WeatherAdmin;
 
+mod backup;
+use backup::BackupCmd;
+
 mod drop;
 use drop::DropCmd;
 
 mod init;
 use init::InitCmd;
 
+mod recover;
+use recover::RecoverCmd;
+
 mod reload;
 use reload::ReloadCmd;
 
+mod restore;
+use restore::RestoreCmd;
+
 mod show;
 use show::ShowCmd;
 
+mod sync;
+use sync::SyncCmd;
+
 mod us_cities;
 use us_cities::UsCitiesCmd;
 
+mod usage;
+use usage::UsageCmd;
+
+mod verify;
+use verify::VerifyCmd;
+
 #[derive(Debug)]
 pub struct Admin;
 impl Admin {
@@ -30,11 +48,17 @@ impl Admin {
             .subcommand_required(true)
             .arg_required_else_help(true)
             .allow_external_subcommands(false)
+            .subcommand(BackupCmd::get())
             .subcommand(InitCmd::get())
             .subcommand(DropCmd::get())
             .subcommand(ReloadCmd::get())
+            .subcommand(RecoverCmd::get())
+            .subcommand(RestoreCmd::get())
             .subcommand(ShowCmd::get())
+            .subcommand(SyncCmd::get())
             .subcommand(UsCitiesCmd::get())
+            .subcommand(UsageCmd::get())
+            .subcommand(VerifyCmd::get())
     }
     /// Executes the command.
     ///
@@ -45,11 +69,17 @@ impl Admin {
     pub fn run(weather_admin: &WeatherAdmin, mut args: ArgMatches) -> cli::Result<()> {
         let (name, cmd_args) = args.remove_subcommand().expect("There was no subcommand available to run");
         match (name.as_str(), cmd_args) {
+            (BackupCmd::NAME, cmd_args) => BackupCmd::run(weather_admin, cmd_args),
             (InitCmd::NAME, cmd_args) => InitCmd::run(weather_admin, cmd_args),
             (DropCmd::NAME, cmd_args) => DropCmd::run(weather_admin, cmd_args),
             (ShowCmd::NAME, cmd_args) => ShowCmd::run(weather_admin, cmd_args),
             (ReloadCmd::NAME, cmd_args) => ReloadCmd::run(weather_admin, cmd_args),
+            (RecoverCmd::NAME, cmd_args) => RecoverCmd::run(weather_admin, cmd_args),
+            (RestoreCmd::NAME, cmd_args) => RestoreCmd::run(weather_admin, cmd_args),
+            (SyncCmd::NAME, cmd_args) => SyncCmd::run(weather_admin, cmd_args),
             (UsCitiesCmd::NAME, cmd_args) => UsCitiesCmd::run(weather_admin, cmd_args),
+            (UsageCmd::NAME, cmd_args) => UsageCmd::run(weather_admin, cmd_args),
+            (VerifyCmd::NAME, cmd_args) => VerifyCmd::run(weather_admin, cmd_args),
             _ => unreachable!("Admin command should not be here..."),
         }
     }
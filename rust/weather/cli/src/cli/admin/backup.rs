@@ -0,0 +1,61 @@
+//! The weather data backup command.
+use crate::cli::{self, parse_filename};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::path::PathBuf;
+use toolslib::fmt::commafy;
+use weather_lib::admin_prelude::WeatherAdmin;
+
+#[derive(Debug)]
+pub struct BackupCmd;
+
+impl BackupCmd {
+    /// The backup sub-command name.
+    pub const NAME: &'static str = "backup";
+
+    /// The command argument id for the backup bundle.
+    const BUNDLE: &'static str = "BUNDLE";
+
+    /// The command argument id to include the database.
+    const DB: &'static str = "DB";
+
+    /// Get the backup sub-command definition.
+    ///
+    pub fn get() -> Command {
+        Command::new(Self::NAME)
+            .about("Backup the locations, archives, and optionally the database to a bundle.")
+            .arg(
+                Arg::new(Self::DB)
+                    .long("db")
+                    .action(ArgAction::SetTrue)
+                    .help("Include the database in the backup."),
+            )
+            .arg(
+                Arg::new(Self::BUNDLE)
+                    .value_name("FILE")
+                    .value_parser(parse_filename)
+                    .action(ArgAction::Set)
+                    .required(true)
+                    .help("The backup bundle that will be created or replaced."),
+            )
+    }
+
+    /// Run the backup sub-command.
+    ///
+    /// # Arguments
+    ///
+    /// * `admin_api` is the backend weather administration `API`.
+    /// * `args` holds the backup command arguments.
+    ///
+    pub fn run(admin_api: &WeatherAdmin, args: ArgMatches) -> cli::Result<()> {
+        let bundle = args.get_one::<PathBuf>(Self::BUNDLE).unwrap();
+        let backup_details = admin_api.backup(bundle, args.get_flag(Self::DB))?;
+        println!(
+            "Backed up {} locations{} to {} ({} bytes).",
+            commafy(backup_details.locations),
+            if backup_details.database { " and the database" } else { "" },
+            bundle.display(),
+            commafy(backup_details.size)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+//! The interrupted update recovery command.
+use crate::cli;
+use clap::{ArgMatches, Command};
+use toolslib::{
+    rptcols, rptrow,
+    text::{self, Report},
+};
+use weather_lib::admin_prelude::{Recovery, WeatherAdmin};
+
+#[derive(Debug)]
+pub struct RecoverCmd;
+
+impl RecoverCmd {
+    /// The recover sub-command name.
+    pub const NAME: &'static str = "recover";
+
+    /// Get the recover sub-command definition.
+    ///
+    pub fn get() -> Command {
+        Command::new(Self::NAME).about("Roll interrupted archive and locations updates forward or back.")
+    }
+
+    /// Run the recover sub-command.
+    ///
+    /// # Arguments
+    ///
+    /// * `admin_api` is the backend weather administration `API`.
+    /// * `_args` holds the recover command arguments.
+    ///
+    pub fn run(admin_api: &WeatherAdmin, _args: ArgMatches) -> cli::Result<()> {
+        let recoveries = admin_api.recover()?;
+        if recoveries.is_empty() {
+            println!("Nothing to recover.");
+        } else {
+            let mut writer = text::get_writer(&None, false)?;
+            text::write_strings(&mut writer, Self::report(&recoveries).into_iter())?;
+        }
+        Ok(())
+    }
+
+    /// Create the recovery report.
+    ///
+    /// # Arguments
+    ///
+    /// * `recoveries` are the files that were recovered.
+    ///
+    fn report(recoveries: &Vec<Recovery>) -> Report {
+        let mut report = Report::from(rptcols!(<, <));
+        report.header(rptrow!(^ "File", ^ "Recovery")).separator("-");
+        for recovery in recoveries {
+            report.text(rptrow!(&recovery.filename, recovery.action.to_string()));
+        }
+        report
+    }
+}
@@ -0,0 +1,73 @@
+//! The weather data restore command.
+use crate::cli::{self, parse_filename};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::path::PathBuf;
+use weather_lib::{
+    admin_prelude::WeatherAdmin,
+    prelude::{location_filter, LocationFilters},
+};
+
+#[derive(Debug)]
+pub struct RestoreCmd;
+
+impl RestoreCmd {
+    /// The restore sub-command name.
+    pub const NAME: &'static str = "restore";
+
+    /// The command argument id for the backup bundle.
+    const BUNDLE: &'static str = "BUNDLE";
+
+    /// The command argument id for which locations should be restored.
+    const CRITERIA: &'static str = "CRITERIA";
+
+    /// Get the restore sub-command definition.
+    ///
+    pub fn get() -> Command {
+        Command::new(Self::NAME)
+            .about("Restore weather data from a backup bundle.")
+            .arg(
+                Arg::new(Self::BUNDLE)
+                    .value_name("FILE")
+                    .value_parser(parse_filename)
+                    .action(ArgAction::Set)
+                    .required(true)
+                    .help("The backup bundle."),
+            )
+            .arg(
+                Arg::new(Self::CRITERIA)
+                    .value_name("LOCATION")
+                    .action(ArgAction::Append)
+                    .help("The locations that will be restored (supports wildcards, default is all)."),
+            )
+    }
+
+    /// Run the restore sub-command.
+    ///
+    /// # Arguments
+    ///
+    /// * `admin_api` is the backend weather administration `API`.
+    /// * `args` holds the restore command arguments.
+    ///
+    pub fn run(admin_api: &WeatherAdmin, args: ArgMatches) -> cli::Result<()> {
+        let bundle = args.get_one::<PathBuf>(Self::BUNDLE).unwrap();
+        let filters = match args.get_many::<String>(Self::CRITERIA) {
+            None => LocationFilters::default(),
+            Some(locations) => {
+                let filters =
+                    locations.into_iter().map(|location| location_filter!(name = location)).collect::<Vec<_>>();
+                LocationFilters::new(filters)
+            }
+        };
+        let restore_details = admin_api.restore(bundle, filters)?;
+        println!(
+            "Restored {}{} from {}.",
+            restore_details.locations.join(", "),
+            if restore_details.database { " and the database" } else { "" },
+            bundle.display()
+        );
+        if restore_details.stale_database {
+            log::warn!("The database was not restored, use 'admin reload' or 'admin init --load' to update it.");
+        }
+        Ok(())
+    }
+}
@@ -51,7 +51,7 @@ impl ShowCmd {
         let mut writer = text::get_writer(&None, false)?;
         let cmd_args = ShowCmd(args);
         if cmd_args.details() {
-            ShowCmd::component_details(&mut writer, &components)?;
+            ShowCmd::component_details(&mut writer, admin_api, &components)?;
         }
         if cmd_args.diff() {
             locations::audit(&mut writer, &components)?;
@@ -64,9 +64,15 @@ impl ShowCmd {
     /// # Arguments
     ///
     /// * `writer` is where the report will be written.
+    /// * `admin_api` is the backend weather administration `API`.
     /// * `components` contains the details about weather data.
-    fn component_details(writer: &mut impl Write, components: &Components) -> cli::Result<()> {
+    fn component_details(writer: &mut impl Write, admin_api: &WeatherAdmin, components: &Components) -> cli::Result<()> {
         let mut report = Report::from(rptcols!(<, >, >, >));
+        report.text(rptrow!(=format!("Profile: {}", admin_api.profile().unwrap_or("(none)"))));
+        report.text(rptrow!(=format!("Directory: {}", admin_api.directory().display())));
+        if let Some(db_details) = &components.db_details {
+            report.text(rptrow!(=format!("Database version: {}", db_details.version)));
+        }
         report.header(rptrow!(^ "Component Details", ^ "Size", ^ "Locations", ^ "Histories")).separator("-");
         if let Some(db_details) = &components.db_details {
             let size = mbufmt!(db_details.size);
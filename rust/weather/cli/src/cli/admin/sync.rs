@@ -0,0 +1,124 @@
+//! The synchronize database with archives command.
+use crate::cli;
+use chrono::NaiveDate;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use toolslib::{
+    fmt::commafy,
+    rptcols, rptrow,
+    text::{self, Report},
+};
+use weather_lib::{
+    admin_prelude::{SyncDetails, WeatherAdmin},
+    prelude::{location_filter, DateRanges, LocationFilters},
+};
+
+#[derive(Debug)]
+pub struct SyncCmd;
+
+impl SyncCmd {
+    /// The sync sub-command name.
+    pub const NAME: &'static str = "sync";
+
+    /// The command argument id for which locations should be synchronized.
+    const CRITERIA: &'static str = "CRITERIA";
+
+    /// The command argument id to report changes without making them.
+    const DRY_RUN: &'static str = "DRY_RUN";
+
+    /// Get the sync sub-command definition.
+    ///
+    pub fn get() -> Command {
+        Command::new(Self::NAME)
+            .about("Copy histories missing from the database or the archives.")
+            .arg(
+                Arg::new(Self::DRY_RUN)
+                    .long("dry-run")
+                    .action(ArgAction::SetTrue)
+                    .help("Show the histories that would be copied without copying them."),
+            )
+            .arg(
+                Arg::new(Self::CRITERIA)
+                    .value_name("LOCATION")
+                    .action(ArgAction::Append)
+                    .help("The locations that will be synchronized (supports wildcards, default is all)."),
+            )
+    }
+
+    /// Run the sync sub-command.
+    ///
+    /// # Arguments
+    ///
+    /// * `admin_api` is the backend weather administration `API`.
+    /// * `args` holds the sync command arguments.
+    ///
+    pub fn run(admin_api: &WeatherAdmin, args: ArgMatches) -> cli::Result<()> {
+        let filters = match args.get_many::<String>(Self::CRITERIA) {
+            None => LocationFilters::default(),
+            Some(locations) => {
+                let filters =
+                    locations.into_iter().map(|location| location_filter!(name = location)).collect::<Vec<_>>();
+                LocationFilters::new(filters)
+            }
+        };
+        let sync_details = admin_api.sync(filters, args.get_flag(Self::DRY_RUN))?;
+        let mut writer = text::get_writer(&None, false)?;
+        text::write_strings(&mut writer, Self::report(&sync_details).into_iter())?;
+        Ok(())
+    }
+
+    /// Create the sync report.
+    ///
+    /// # Arguments
+    ///
+    /// * `sync_details` are the changes found synchronizing the database and archives.
+    ///
+    fn report(sync_details: &SyncDetails) -> Report {
+        let mut report = Report::from(rptcols!(<, <, <));
+        if !sync_details.locations.is_empty() {
+            report.header(rptrow!(^ "Location", ^ "To Database", ^ "To Archive")).separator("-");
+            for location in &sync_details.locations {
+                let to_database = Self::date_ranges(&location.alias, &location.to_database);
+                let to_archive = Self::date_ranges(&location.alias, &location.to_archive);
+                let alias = match location.added_to {
+                    Some(added_to) => format!("{} (added to {})", location.alias, added_to),
+                    None => location.alias.clone(),
+                };
+                for row in 0..to_database.len().max(to_archive.len()).max(1) {
+                    let name = if row == 0 { alias.as_str() } else { "" };
+                    let to_database = to_database.get(row).map_or("", String::as_str);
+                    let to_archive = to_archive.get(row).map_or("", String::as_str);
+                    report.text(rptrow!(name, to_database, to_archive));
+                }
+            }
+        }
+        let to_database: usize = sync_details.locations.iter().map(|location| location.to_database.len()).sum();
+        let to_archive: usize = sync_details.locations.iter().map(|location| location.to_archive.len()).sum();
+        let summary = format!(
+            "{} {} histories to the database and {} histories to the archives for {} locations.",
+            if sync_details.dry_run { "Would copy" } else { "Copied" },
+            commafy(to_database),
+            commafy(to_archive),
+            commafy(sync_details.locations.len())
+        );
+        report.text(rptrow!(=summary));
+        report
+    }
+
+    /// Group history dates into consecutive date ranges.
+    ///
+    /// # Arguments
+    ///
+    /// * `alias` is the location alias name.
+    /// * `dates` are the history dates.
+    ///
+    fn date_ranges(alias: &str, dates: &[NaiveDate]) -> Vec<String> {
+        DateRanges::new(alias, dates.to_vec())
+            .date_ranges
+            .into_iter()
+            .map(|date_range| match date_range.is_one_day() {
+                true => date_range.start.to_string(),
+                false => format!("{} thru {}", date_range.start, date_range.end),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,51 @@
+//! The history client usage command.
+use crate::cli;
+use clap::{ArgMatches, Command};
+use toolslib::{
+    fmt::commafy,
+    rptcols, rptrow,
+    text::{self, Report},
+};
+use weather_lib::{admin_prelude::WeatherAdmin, prelude::QueryUsage};
+
+#[derive(Debug)]
+pub struct UsageCmd;
+
+impl UsageCmd {
+    /// The usage sub-command name.
+    pub const NAME: &'static str = "usage";
+
+    /// Get the usage sub-command definition.
+    ///
+    pub fn get() -> Command {
+        Command::new(Self::NAME).about("Show the weather history requests made today and what they cost.")
+    }
+
+    /// Run the usage sub-command.
+    ///
+    /// # Arguments
+    ///
+    /// * `admin_api` is the backend weather administration `API`.
+    /// * `_args` holds the usage command arguments.
+    ///
+    pub fn run(admin_api: &WeatherAdmin, _args: ArgMatches) -> cli::Result<()> {
+        let query_usage = admin_api.query_usage()?;
+        let mut writer = text::get_writer(&None, false)?;
+        text::write_strings(&mut writer, Self::report(&query_usage).into_iter())?;
+        Ok(())
+    }
+
+    /// Create the usage report.
+    ///
+    /// # Arguments
+    ///
+    /// * `query_usage` is the history client usage for today.
+    ///
+    fn report(query_usage: &QueryUsage) -> Report {
+        let mut report = Report::from(rptcols!(<, >, >));
+        report.header(rptrow!(^ "Date (UTC)", ^ "Requests", ^ "Records")).separator("-");
+        let date = toolslib::date_time::isodate(&query_usage.date);
+        report.text(rptrow!(date, commafy(query_usage.requests), commafy(query_usage.cost)));
+        report
+    }
+}
@@ -0,0 +1,74 @@
+//! The weather data integrity check command.
+use crate::cli::{self, err};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use toolslib::{
+    fmt::commafy,
+    rptcols, rptrow,
+    text::{self, Report},
+};
+use weather_lib::admin_prelude::{VerifyDetails, WeatherAdmin};
+
+#[derive(Debug)]
+pub struct VerifyCmd;
+
+impl VerifyCmd {
+    /// The verify sub-command name.
+    pub const NAME: &'static str = "verify";
+
+    /// The command argument id to quarantine history files with problems.
+    const QUARANTINE: &'static str = "QUARANTINE";
+
+    /// Get the verify sub-command definition.
+    ///
+    pub fn get() -> Command {
+        Command::new(Self::NAME).about("Check the integrity of the weather history archives.").arg(
+            Arg::new(Self::QUARANTINE)
+                .long("quarantine")
+                .action(ArgAction::SetTrue)
+                .help("Move history files with problems out of the archives."),
+        )
+    }
+
+    /// Run the verify sub-command.
+    ///
+    /// # Arguments
+    ///
+    /// * `admin_api` is the backend weather administration `API`.
+    /// * `args` holds the verify command arguments.
+    ///
+    pub fn run(admin_api: &WeatherAdmin, args: ArgMatches) -> cli::Result<()> {
+        let verify_details = admin_api.verify(args.get_flag(Self::QUARANTINE))?;
+        let mut writer = text::get_writer(&None, false)?;
+        text::write_strings(&mut writer, Self::report(&verify_details).into_iter())?;
+        match verify_details.problems.len() {
+            0 => Ok(()),
+            count => err!("{} problem(s) found.", commafy(count)),
+        }
+    }
+
+    /// Create the verify report.
+    ///
+    /// # Arguments
+    ///
+    /// * `verify_details` are the results of checking the weather data.
+    ///
+    fn report(verify_details: &VerifyDetails) -> Report {
+        let mut report = Report::from(rptcols!(<, <, <));
+        report.text(rptrow!(=format!(
+            "Checked {} histories in {} archives.",
+            commafy(verify_details.histories),
+            commafy(verify_details.archives)
+        )));
+        if verify_details.quarantined > 0 {
+            report.text(rptrow!(=format!("Quarantined {} histories.", commafy(verify_details.quarantined))));
+        }
+        if !verify_details.problems.is_empty() {
+            report.header(rptrow!(^ "Location", ^ "History", ^ "Problem")).separator("-");
+            for problem in &verify_details.problems {
+                let history = problem.history.as_deref().unwrap_or("archive");
+                report.text(rptrow!(&problem.alias, history, &problem.problem));
+            }
+        }
+        report
+    }
+}
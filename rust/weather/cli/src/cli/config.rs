@@ -0,0 +1,185 @@
+//! The weather configuration command.
+use crate::cli::{self, err, parse_filename, CommandLineArgs};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::{fs, path::PathBuf};
+use toolslib::{
+    rptcols, rptrow,
+    text::{self, Report},
+};
+use weather_lib::config_prelude::{
+    config_settings, find_config_file, starter_config, user_config_path, validate_config, ConfigSettings,
+    ConfigSource,
+};
+
+#[derive(Debug)]
+pub struct ConfigCmd;
+impl ConfigCmd {
+    /// The command name.
+    pub const NAME: &'static str = "config";
+    /// The show sub-command name.
+    const SHOW: &'static str = "show";
+    /// The init sub-command name.
+    const INIT: &'static str = "init";
+    /// The validate sub-command name.
+    const VALIDATE: &'static str = "validate";
+    /// The configuration file argument id.
+    const FILE: &'static str = "FILE";
+    /// The overwrite an existing file argument id.
+    const FORCE: &'static str = "FORCE";
+
+    /// Create the sub-command.
+    pub fn get() -> Command {
+        Command::new(Self::NAME)
+            .about("Show, create, or validate the weather configuration.")
+            .subcommand_required(true)
+            .arg_required_else_help(true)
+            .allow_external_subcommands(false)
+            .subcommand(
+                Command::new(Self::SHOW).about("Show the effective configuration and where each setting came from."),
+            )
+            .subcommand(
+                Command::new(Self::INIT)
+                    .about("Write a starter configuration file.")
+                    .arg(
+                        Arg::new(Self::FORCE)
+                            .long("force")
+                            .action(ArgAction::SetTrue)
+                            .help("Overwrite the file if it exists."),
+                    )
+                    .arg(
+                        Arg::new(Self::FILE)
+                            .action(ArgAction::Set)
+                            .value_parser(parse_filename)
+                            .help("The configuration file (DEFAULT the user configuration directory)."),
+                    ),
+            )
+            .subcommand(
+                Command::new(Self::VALIDATE).about("Validate a configuration file.").arg(
+                    Arg::new(Self::FILE)
+                        .action(ArgAction::Set)
+                        .value_parser(parse_filename)
+                        .help("The configuration file (DEFAULT the configuration file that would be used)."),
+                ),
+            )
+    }
+
+    /// Executes the command.
+    ///
+    /// # Arguments
+    ///
+    /// * `command_args` holds the common command line arguments.
+    /// * `args` contains the config command arguments.
+    ///
+    pub fn run(command_args: CommandLineArgs, mut args: ArgMatches) -> cli::Result<()> {
+        let (name, cmd_args) = args.remove_subcommand().expect("There was no subcommand available to run");
+        match name.as_str() {
+            Self::SHOW => Self::show(command_args.config_file(), command_args.profile()),
+            Self::INIT => Self::init(Self::file(&cmd_args), cmd_args.get_flag(Self::FORCE)),
+            Self::VALIDATE => {
+                Self::validate(Self::file(&cmd_args).or(command_args.config_file()), command_args.weather_dir())
+            }
+            _ => unreachable!("Config command should not be here..."),
+        }
+    }
+
+    /// Get the configuration file argument.
+    fn file(args: &ArgMatches) -> Option<PathBuf> {
+        args.get_one::<PathBuf>(Self::FILE).cloned()
+    }
+
+    /// Show the effective configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_file` is the configuration file pathname.
+    /// * `profile` is the name of the configuration profile.
+    ///
+    fn show(config_file: Option<PathBuf>, profile: Option<String>) -> cli::Result<()> {
+        let config_settings = config_settings(config_file, profile)?;
+        let mut writer = text::get_writer(&None, false)?;
+        text::write_strings(&mut writer, Self::report(&config_settings).into_iter())?;
+        Ok(())
+    }
+
+    /// Create the effective configuration report.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_settings` is the effective configuration.
+    ///
+    fn report(config_settings: &ConfigSettings) -> Report {
+        let mut report = Report::from(rptcols!(<, <, <));
+        let filename = match &config_settings.path {
+            Some(path) => path.display().to_string(),
+            None => "none (using defaults)".to_string(),
+        };
+        report.text(rptrow!(=format!("Configuration file: {}", filename)));
+        if let Some(profile) = &config_settings.profile {
+            report.text(rptrow!(=format!("Profile: {}", profile)));
+        }
+        report.header(rptrow!(^ "Setting", ^ "Value", ^ "Source")).separator("-");
+        for setting in &config_settings.settings {
+            let source = match &setting.source {
+                ConfigSource::Default => "default".to_string(),
+                ConfigSource::File => "file".to_string(),
+                ConfigSource::Profile(name) => format!("profile {}", name),
+                ConfigSource::Environment(name) => format!("${}", name),
+            };
+            report.text(rptrow!(&setting.name, &setting.value, source));
+        }
+        report
+    }
+
+    /// Write a starter configuration file.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_file` is the configuration file pathname.
+    /// * `force` allows an existing file to be overwritten.
+    ///
+    fn init(config_file: Option<PathBuf>, force: bool) -> cli::Result<()> {
+        let path = match config_file.or_else(user_config_path) {
+            Some(path) => path,
+            None => return err!("The user configuration directory could not be determined."),
+        };
+        if path.exists() && !force {
+            return err!("'{}' already exists, use --force to overwrite it.", path.display());
+        }
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            if let Err(error) = fs::create_dir_all(parent) {
+                return err!("Could not create '{}' ({}).", parent.display(), error);
+            }
+        }
+        match fs::write(&path, starter_config()) {
+            Ok(_) => {
+                println!("Created {}", path.display());
+                Ok(())
+            }
+            Err(error) => err!("Could not write '{}' ({}).", path.display(), error),
+        }
+    }
+
+    /// Validate a configuration file.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_file` is the configuration file pathname.
+    /// * `weather_dir` is the weather data directory used in place of the configured weather data.
+    ///
+    fn validate(config_file: Option<PathBuf>, weather_dir: Option<PathBuf>) -> cli::Result<()> {
+        let path = match find_config_file(config_file)? {
+            Some(path) => path,
+            None => return err!("A configuration file was not found."),
+        };
+        let problems = validate_config(&path, weather_dir.as_deref())?;
+        if problems.is_empty() {
+            println!("{} is valid.", path.display());
+            Ok(())
+        } else {
+            for problem in &problems {
+                eprintln!("{}", problem);
+            }
+            err!("{} has {} problem(s).", path.display(), problems.len())
+        }
+    }
+}
@@ -3,6 +3,8 @@ pub(crate) mod list_history;
 pub(crate) mod list_locations;
 pub(crate) mod list_summary;
 pub(crate) mod report_history;
+pub(crate) mod search_history;
+pub(crate) mod sql_query;
 pub(crate) mod list_states;
 
 /// Attempts to write a `CSV` record and captures any errors that may occur.
@@ -0,0 +1,133 @@
+//! Generates the weather data history search report.
+//!
+use super::{csv_to_string, csv_write_record, json_to_string, text_title_separator};
+use serde_json::{json, Value};
+use toolslib::{header, layout, report::ReportSheet};
+use weather_lib::prelude::DailyHistories;
+
+pub mod text {
+    //! The search history text based reporting implementation.
+    //!
+    use super::*;
+    use toolslib::date_time::fmt_date;
+
+    /// The metadata controlling the report appearance.
+    #[derive(Debug, Default)]
+    pub struct Report {
+        /// Controls if a separator row will be added between the report headers and report text.
+        title_separator: bool,
+    }
+    impl Report {
+        /// Adds a separator row between the report headers and report text.
+        ///
+        pub fn with_title_separator(mut self) -> Self {
+            self.title_separator = true;
+            self
+        }
+        /// Generates the search history text based report.
+        ///
+        /// # Arguments
+        ///
+        /// * `locations_histories` - The locations and histories that matched the search.
+        ///
+        pub fn generate(&self, locations_histories: Vec<DailyHistories>) -> ReportSheet {
+            let mut report = ReportSheet::new(vec![layout!(<), layout!(^), layout!(<)]);
+            report.add_row(vec![header!(^ "Location"), header!(^ "Date"), header!(^ "Description")]);
+            if self.title_separator {
+                report.add_row(text_title_separator!(report.columns()));
+            }
+            for daily_histories in locations_histories {
+                for (index, history) in daily_histories.histories.iter().enumerate() {
+                    let name = match index {
+                        0 => daily_histories.location.name.as_str(),
+                        _ => "",
+                    };
+                    report.add_row(vec![
+                        toolslib::text!(name),
+                        toolslib::text!(fmt_date(&history.date, "%b-%d-%Y")),
+                        toolslib::text!(history.description.as_deref().unwrap_or_default()),
+                    ]);
+                }
+            }
+            report
+        }
+    }
+}
+
+pub mod csv {
+    //! The search history CSV based reporting implementation.
+    //!
+    use super::*;
+    extern crate csv as csv_lib;
+
+    #[derive(Default, Debug)]
+    pub struct Report;
+    impl Report {
+        /// Generates the search history CSV based report.
+        ///
+        /// # Arguments
+        ///
+        /// * `locations_histories` - The locations and histories that matched the search.
+        ///
+        pub fn generate(self, locations_histories: Vec<DailyHistories>) -> String {
+            let mut writer = csv_lib::Writer::from_writer(vec![]);
+            csv_write_record!(writer, &["location", "date", "description"]);
+            for daily_histories in locations_histories {
+                for history in daily_histories.histories {
+                    let date = history.date.format("%Y-%m-%d").to_string();
+                    let description = history.description.unwrap_or_default();
+                    csv_write_record!(writer, &[&daily_histories.location.name, &date, &description]);
+                }
+            }
+            csv_to_string(writer)
+        }
+    }
+}
+
+pub mod json {
+    //! The search history JSON based reporting implementation.
+    //!
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct Report(
+        /// Controls if the report will be pretty printed or not.
+        bool,
+    );
+    impl Report {
+        /// Create a report instance and configure it to pretty print the `JSON` document.
+        ///
+        pub fn pretty_printed() -> Self {
+            Self(true)
+        }
+        /// Generates the search history JSON based report.
+        ///
+        /// # Arguments
+        ///
+        /// * `locations_histories` - The locations and histories that matched the search.
+        ///
+        pub fn generate(&self, locations_histories: Vec<DailyHistories>) -> String {
+            let location_array: Vec<Value> = locations_histories
+                .into_iter()
+                .map(|daily_histories| {
+                    let histories: Vec<Value> = daily_histories
+                        .histories
+                        .iter()
+                        .map(|history| {
+                            json!({
+                                "date": history.date.format("%Y-%m-%d").to_string(),
+                                "description": history.description,
+                            })
+                        })
+                        .collect();
+                    json!({
+                        "location": daily_histories.location.name,
+                        "alias": daily_histories.location.alias,
+                        "histories": histories,
+                    })
+                })
+                .collect();
+            json_to_string(json!({ "search": location_array }), self.0)
+        }
+    }
+}
@@ -0,0 +1,189 @@
+//! The SQL query reports.
+
+use serde_json::Value;
+use weather_lib::admin_prelude::SqlResults;
+
+/// Get the text of a query value, `NULL` values are empty.
+///
+/// # Arguments
+///
+/// * `value` is the query column value.
+///
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::default(),
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+pub mod text {
+    /// The text based SQL query report.
+    ///
+    use super::*;
+    use crate::cli::reports::text_title_separator;
+    use toolslib::{header, layout, report::ReportSheet, text};
+
+    #[derive(Default, Debug)]
+    pub struct Report {
+        title_separator: bool,
+    }
+    impl Report {
+        /// Controls if a separator row will separate report headers from the report text.
+        pub fn with_title_separator() -> Self {
+            Self { title_separator: true }
+        }
+
+        /// Generates the SQL query text based report. Columns with numbers are right aligned.
+        ///
+        /// # Arguments
+        ///
+        /// * `sql_results` are the query columns and rows.
+        ///
+        pub fn generate(&self, sql_results: SqlResults) -> ReportSheet {
+            let is_number = |index: usize| sql_results.rows.iter().any(|row| row[index].is_number());
+            let layouts = (0..sql_results.columns.len())
+                .map(|index| match is_number(index) {
+                    true => layout!(>),
+                    false => layout!(<),
+                })
+                .collect();
+            let mut report = ReportSheet::new(layouts);
+            report.add_row(sql_results.columns.iter().map(|column| header!(^ column)).collect());
+            if self.title_separator {
+                report.add_row(text_title_separator!(report.columns()));
+            }
+            for row in sql_results.rows {
+                report.add_row(row.iter().map(|value| text!(value_text(value))).collect());
+            }
+            report
+        }
+    }
+}
+
+pub mod csv {
+    /// The CSV based SQL query report.
+    ///
+    use super::*;
+    use crate::cli::reports::{csv_to_string, csv_write_record};
+
+    extern crate csv as csv_lib;
+
+    #[derive(Default, Debug)]
+    pub struct Report;
+    impl Report {
+        /// Generates the SQL query CSV based report.
+        ///
+        /// # Arguments
+        ///
+        /// * `sql_results` are the query columns and rows.
+        ///
+        pub fn generate(&self, sql_results: SqlResults) -> String {
+            let mut writer = csv_lib::Writer::from_writer(vec![]);
+            csv_write_record!(writer, &sql_results.columns);
+            for row in sql_results.rows {
+                csv_write_record!(writer, row.iter().map(value_text));
+            }
+            csv_to_string(writer)
+        }
+    }
+}
+
+pub mod json {
+    /// The JSON based SQL query report.
+    ///
+    use super::*;
+    use crate::cli::reports::json_to_string;
+    use serde_json::{json, Map};
+
+    #[derive(Default, Debug)]
+    pub struct Report(
+        /// Controls if the report will be pretty printed or not.
+        bool,
+    );
+    impl Report {
+        /// Create a report instance and configure it to pretty print the `JSON` document.
+        ///
+        pub fn pretty_printed() -> Self {
+            Self(true)
+        }
+
+        /// Generates the SQL query JSON based report. Each row is an object keyed by column name.
+        /// Repeated column names get a suffix (`date`, `date:1`) so no column is dropped.
+        ///
+        /// # Arguments
+        ///
+        /// * `sql_results` are the query columns and rows.
+        ///
+        pub fn generate(&self, sql_results: SqlResults) -> String {
+            let keys = unique_keys(&sql_results.columns);
+            let json_rows = sql_results
+                .rows
+                .into_iter()
+                .map(|row| Value::Object(keys.iter().cloned().zip(row).collect::<Map<_, _>>()))
+                .collect::<Vec<_>>();
+            let root = json!({ "rows": json_rows });
+            json_to_string(root, self.0)
+        }
+    }
+
+    /// Get the row object keys for the query columns, repeated names are made unique.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` are the query column names.
+    ///
+    fn unique_keys(columns: &[String]) -> Vec<String> {
+        let mut keys: Vec<String> = Vec::with_capacity(columns.len());
+        for column in columns {
+            let mut key = column.clone();
+            let mut suffix = 0;
+            while keys.contains(&key) || (key != *column && columns.contains(&key)) {
+                suffix += 1;
+                key = format!("{}:{}", column, suffix);
+            }
+            keys.push(key);
+        }
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sql_results() -> SqlResults {
+        SqlResults {
+            columns: vec!["alias".to_string(), "temp_high".to_string(), "description".to_string()],
+            rows: vec![
+                vec![json!("tigard"), json!(88.5), json!("Clear, sunny")],
+                vec![json!("tigard"), Value::Null, Value::Null],
+            ],
+        }
+    }
+
+    #[test]
+    fn reports() {
+        let testcase = csv::Report.generate(sql_results());
+        assert_eq!(testcase, "alias,temp_high,description\ntigard,88.5,\"Clear, sunny\"\ntigard,,\n");
+        let testcase: Value = serde_json::from_str(&json::Report::default().generate(sql_results())).unwrap();
+        assert_eq!(testcase["rows"][0], json!({"alias": "tigard", "temp_high": 88.5, "description": "Clear, sunny"}));
+        assert_eq!(testcase["rows"][1]["temp_high"], Value::Null);
+        let testcase: Vec<String> =
+            text::Report::default().generate(sql_results()).into_iter().map(|row| row.to_string()).collect();
+        assert_eq!(testcase.len(), 3);
+        assert!(testcase[0].starts_with("alias"));
+        assert!(testcase[1].contains("88.5"));
+    }
+
+    #[test]
+    fn repeated_columns() {
+        let sql_results = SqlResults {
+            columns: vec!["date".to_string(), "date".to_string(), "date:1".to_string()],
+            rows: vec![vec![json!("2024-07-04"), json!("2024-07-05"), json!(1)]],
+        };
+        let testcase: Value = serde_json::from_str(&json::Report::default().generate(sql_results)).unwrap();
+        assert_eq!(testcase["rows"][0], json!({"date": "2024-07-04", "date:2": "2024-07-05", "date:1": 1}));
+    }
+}
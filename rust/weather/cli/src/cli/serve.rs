@@ -0,0 +1,662 @@
+//! # The weather data `HTTP` server (`serve`).
+//!
+//! The server makes weather data available to tools that cannot link with the weather library.
+//! Responses are `JSON` documents and errors are returned as `{"error": "..."}` with a matching
+//! `HTTP` status. Request problems are `400` errors and bodies larger than [MAX_BODY_SIZE] are
+//! rejected with `413`. The endpoints are:
+//!
+//! * `GET /locations` gets locations, filtered by `name`, `city`, and `state`.
+//! * `GET /history/dates` gets the location history dates, filtered like locations.
+//! * `GET /history/summary` gets the location history summaries, filtered like locations.
+//! * `GET /history/daily?name=NAME&from=DATE&thru=DATE` gets the histories of a location. Use
+//!   `alias=ALIAS` instead of `name` to select the location by its exact alias.
+//! * `GET /history/search?query=WORDS` searches history descriptions, filtered like locations and
+//!   optionally by `from` and `thru` dates.
+//! * `GET /cities` searches the US Cities by `name`, `state`, and `zip`, up to `limit` cities.
+//! * `GET /states` gets the US City state names.
+//! * `POST /locations` adds the location in the request body.
+//! * `POST /history` adds the daily histories in the request body.
+//!
+//! The server is read-only unless `--read-write` is used. Writes then require the server token
+//! as an `Authorization: Bearer TOKEN` request header. The token is read from a file or the
+//! environment so it does not show up in the process list.
+//!
+//! Requests are handled one at a time on the calling thread. The server does not use `TLS`, put
+//! it behind a proxy that does if it will be used off the local machine.
+
+use crate::cli::{self, err, location_filter, location_filters, parse_filename, LocationFilters};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{env, fs, io::Read, path::PathBuf};
+use tiny_http::{Header, Response, Server};
+use url::Url;
+use weather_lib::{
+    config_prelude::ENV_TOKEN,
+    prelude::{CityFilter, DailyHistories, DateRange, Location, LocationFilter, WeatherData},
+};
+
+/// The largest request body that will be read.
+const MAX_BODY_SIZE: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug)]
+pub struct ServeCmd;
+impl ServeCmd {
+    /// The serve command name.
+    pub const NAME: &'static str = "serve";
+
+    /// The server address argument id.
+    const ADDRESS: &'static str = "ADDRESS";
+
+    /// The read-write mode argument id.
+    const READ_WRITE: &'static str = "READ_WRITE";
+
+    /// The token file argument id.
+    const TOKEN_FILE: &'static str = "TOKEN_FILE";
+
+    /// Get the serve command definition.
+    ///
+    pub fn get() -> Command {
+        Command::new(Self::NAME)
+            .about("Serve weather data as JSON over HTTP.")
+            .arg(
+                Arg::new(Self::ADDRESS)
+                    .long("address")
+                    .action(ArgAction::Set)
+                    .value_name("HOST:PORT")
+                    .require_equals(true)
+                    .default_value("127.0.0.1:8080")
+                    .help("The address the server listens on."),
+            )
+            .arg(
+                Arg::new(Self::READ_WRITE)
+                    .long("read-write")
+                    .action(ArgAction::SetTrue)
+                    .help(format!(
+                        "Allow locations and history to be added (requires --token-file or ${}).",
+                        ENV_TOKEN
+                    )),
+            )
+            .arg(
+                Arg::new(Self::TOKEN_FILE)
+                    .long("token-file")
+                    .action(ArgAction::Set)
+                    .value_name("FILE")
+                    .require_equals(true)
+                    .value_parser(parse_filename)
+                    .requires(Self::READ_WRITE)
+                    .help("The file containing the token that authorizes writes."),
+            )
+    }
+
+    /// Run the weather data server until the process is stopped.
+    ///
+    /// # Arguments
+    ///
+    /// * `weather_data` is the weather data API that will be served.
+    /// * `args` holds the serve command arguments.
+    ///
+    pub fn run(weather_data: WeatherData, args: ArgMatches) -> cli::Result<()> {
+        let token = match args.get_flag(Self::READ_WRITE) {
+            true => Some(Self::token(args.get_one::<PathBuf>(Self::TOKEN_FILE))?),
+            false => None,
+        };
+        let address = args.get_one::<String>(Self::ADDRESS).unwrap();
+        let server = match Server::http(address) {
+            Ok(server) => server,
+            Err(error) => err!("Serve could not listen on {} ({}).", address, error)?,
+        };
+        let mode = if token.is_some() { "read-write" } else { "read-only" };
+        println!("Serving weather data {} on http://{}.", mode, address);
+        serve(&server, &Api::new(weather_data, token));
+        Ok(())
+    }
+
+    /// Get the token that authorizes writes from the token file or environment.
+    ///
+    /// # Arguments
+    ///
+    /// * `token_file` is the file containing the token.
+    ///
+    fn token(token_file: Option<&PathBuf>) -> cli::Result<String> {
+        let token = match token_file {
+            Some(path) => match fs::read_to_string(path) {
+                Ok(token) => token,
+                Err(error) => err!("Serve could not read the token file {} ({}).", path.display(), error)?,
+            },
+            None => env::var(ENV_TOKEN).unwrap_or_default(),
+        };
+        match token.trim() {
+            "" => err!("Serve read-write mode requires a token from --token-file or ${}.", ENV_TOKEN),
+            token => Ok(token.to_string()),
+        }
+    }
+}
+
+/// Handle server requests until the server is stopped.
+///
+/// # Arguments
+///
+/// * `server` receives the requests.
+/// * `api` handles the requests.
+///
+fn serve(server: &Server, api: &Api) {
+    for mut request in server.incoming_requests() {
+        let body_length = request.body_length();
+        let (method, url) = (request.method().to_string(), request.url().to_string());
+        let authorization = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Authorization"))
+            .map(|header| header.value.to_string());
+        // the body is only read after the route and authorization have been checked
+        let body = || read_body(request.as_reader(), body_length);
+        let reply = api.handle(&method, &url, authorization.as_deref(), body);
+        log::info!("{} {} {}", request.method(), request.url(), reply.status);
+        let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+        let response =
+            Response::from_string(reply.body.to_string()).with_status_code(reply.status).with_header(content_type);
+        if let Err(error) = request.respond(response) {
+            log::warn!("Serve could not send the response ({}).", error);
+        }
+    }
+}
+
+/// Read the request body. An error reply is returned if the body is larger than [MAX_BODY_SIZE]
+/// or cannot be read.
+///
+/// # Arguments
+///
+/// * `reader` is the request body.
+/// * `body_length` is the body size from the request headers, if it was provided.
+///
+fn read_body(reader: impl Read, body_length: Option<usize>) -> Result<String, Reply> {
+    let too_large = || Reply::error(413, format!("the request body is larger than {} bytes", MAX_BODY_SIZE));
+    if body_length.is_some_and(|body_length| body_length as u64 > MAX_BODY_SIZE) {
+        return Err(too_large());
+    }
+    // read one byte more than allowed so a body without a length that is too large is not truncated
+    let mut body = String::new();
+    match reader.take(MAX_BODY_SIZE + 1).read_to_string(&mut body) {
+        Ok(size) if size as u64 > MAX_BODY_SIZE => Err(too_large()),
+        Ok(_) => Ok(body),
+        Err(error) => Err(Reply::error(400, format!("the request body could not be read ({})", error))),
+    }
+}
+
+/// The status and body of a response.
+#[derive(Debug)]
+struct Reply {
+    /// The `HTTP` status code.
+    status: u16,
+    /// The `JSON` response body.
+    body: Value,
+}
+impl Reply {
+    /// Create a successful reply.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` is the `HTTP` status code.
+    /// * `body` is what will be returned as `JSON`.
+    ///
+    fn ok(status: u16, body: impl Serialize) -> Self {
+        match serde_json::to_value(body) {
+            Ok(body) => Self { status, body },
+            Err(error) => Self::error(500, format!("the response could not be created ({})", error)),
+        }
+    }
+    /// Create a reply from the result of a weather data call. Errors are server errors, request
+    /// parameters are validated before the weather data is called so filter problems are client errors.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` is what the weather data call returned.
+    ///
+    fn from_result(result: weather_lib::Result<impl Serialize>) -> Self {
+        match result {
+            Ok(body) => Self::ok(200, body),
+            Err(error) => Self::error(500, error.to_string()),
+        }
+    }
+    /// Create an error reply.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` is the `HTTP` status code.
+    /// * `message` describes the error.
+    ///
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self { status, body: json!({ "error": message.into() }) }
+    }
+}
+
+/// The request parameters from the query string.
+struct Params(Vec<(String, String)>);
+impl Params {
+    /// Get the first value of a parameter.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` is the parameter name.
+    ///
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+    /// Get the location filters, which follow the location command line arguments. Each `name`
+    /// is a filter that includes the `city` and `state`.
+    fn location_filters(&self) -> LocationFilters {
+        let city = self.get("city");
+        let state = self.get("state");
+        let with_city_state = |mut filter: LocationFilter| {
+            if let Some(city) = city {
+                filter = filter.with_city(city);
+            }
+            if let Some(state) = state {
+                filter = filter.with_state(state);
+            }
+            filter
+        };
+        let names: Vec<&str> = self.0.iter().filter(|(key, _)| key == "name").map(|(_, name)| name.as_str()).collect();
+        match (names.is_empty(), city.is_some() || state.is_some()) {
+            (true, false) => LocationFilters::default(),
+            (true, true) => LocationFilters::new(vec![with_city_state(location_filter!())]),
+            (false, _) => {
+                let filters = names.into_iter().map(|name| with_city_state(location_filter!(name = name)));
+                LocationFilters::new(filters.collect())
+            }
+        }
+    }
+    /// Get a date parameter.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` is the parameter name.
+    ///
+    fn date(&self, name: &str) -> std::result::Result<Option<chrono::NaiveDate>, Reply> {
+        match self.get(name).map(toolslib::date_time::parse_date) {
+            None => Ok(None),
+            Some(Ok(date)) => Ok(Some(date)),
+            Some(Err(error)) => Err(Reply::error(400, format!("'{}' is not a valid date ({})", name, error))),
+        }
+    }
+}
+
+/// The weather data `API` served over `HTTP`.
+struct Api {
+    /// The weather data being served.
+    weather_data: WeatherData,
+    /// The token that authorizes writes, `None` when the server is read-only.
+    token: Option<String>,
+}
+impl Api {
+    /// Create the `API`.
+    ///
+    /// # Arguments
+    ///
+    /// * `weather_data` is the weather data being served.
+    /// * `token` authorizes writes, if `None` the server is read-only.
+    ///
+    fn new(weather_data: WeatherData, token: Option<String>) -> Self {
+        Self { weather_data, token }
+    }
+
+    /// Handle a request.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` is the request method.
+    /// * `url` is the request path and query string.
+    /// * `authorization` is the request authorization header.
+    /// * `body` reads the request body, it is only called for authorized writes.
+    ///
+    fn handle(
+        &self,
+        method: &str,
+        url: &str,
+        authorization: Option<&str>,
+        body: impl FnOnce() -> Result<String, Reply>,
+    ) -> Reply {
+        let url = match Url::parse("http://localhost").and_then(|base| base.join(url)) {
+            Ok(url) => url,
+            Err(error) => return Reply::error(400, format!("the request URL is not valid ({})", error)),
+        };
+        let params = Params(url.query_pairs().into_owned().collect());
+        let filters = || params.location_filters();
+        match (method, url.path()) {
+            ("GET", "/locations") => Reply::from_result(self.weather_data.get_locations(filters())),
+            ("GET", "/history/dates") => Reply::from_result(self.weather_data.get_history_dates(filters())),
+            ("GET", "/history/summary") => Reply::from_result(self.weather_data.get_history_summary(filters())),
+            ("GET", "/history/daily") => self.get_daily_history(&params),
+            ("GET", "/history/search") => self.search_histories(&params),
+            ("GET", "/cities") => self.search_cities(&params),
+            ("GET", "/states") => Reply::from_result(self.weather_data.get_states()),
+            ("POST", "/locations") => self.write(authorization, body, |body| self.add_location(body)),
+            ("POST", "/history") => self.write(authorization, body, |body| self.add_histories(body)),
+            (_, "/locations" | "/history" | "/history/dates" | "/history/summary" | "/history/daily")
+            | (_, "/history/search" | "/cities" | "/states") => {
+                Reply::error(405, format!("{} is not supported by {}", method, url.path()))
+            }
+            (_, path) => Reply::error(404, format!("{} was not found", path)),
+        }
+    }
+
+    /// Handle a write request. The request body is read only if the write is authorized.
+    ///
+    /// # Arguments
+    ///
+    /// * `authorization` is the request authorization header.
+    /// * `body` reads the request body.
+    /// * `write` handles the request body.
+    ///
+    fn write(
+        &self,
+        authorization: Option<&str>,
+        body: impl FnOnce() -> Result<String, Reply>,
+        write: impl FnOnce(&str) -> Reply,
+    ) -> Reply {
+        if let Some(reply) = self.authorize(authorization) {
+            return reply;
+        }
+        match body() {
+            Ok(body) => write(&body),
+            Err(reply) => reply,
+        }
+    }
+
+    /// Check if a write is authorized. An error reply is returned if it is not.
+    ///
+    /// # Arguments
+    ///
+    /// * `authorization` is the request authorization header.
+    ///
+    fn authorize(&self, authorization: Option<&str>) -> Option<Reply> {
+        let token = match &self.token {
+            Some(token) => token,
+            None => return Some(Reply::error(403, "the server is read-only")),
+        };
+        match authorization.and_then(|authorization| authorization.strip_prefix("Bearer ")) {
+            Some(bearer) if is_same(token, bearer.trim()) => None,
+            _ => Some(Reply::error(401, "a valid bearer token is required")),
+        }
+    }
+
+    /// Get the daily histories of a location.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` are the request parameters.
+    ///
+    fn get_daily_history(&self, params: &Params) -> Reply {
+        let (name, alias) = match (params.get("alias"), params.get("name")) {
+            (Some(alias), _) => (alias, true),
+            (None, Some(name)) => (name, false),
+            (None, None) => return Reply::error(400, "the location 'alias' or 'name' is required"),
+        };
+        let (from, thru) = match (params.date("from"), params.date("thru")) {
+            (Ok(Some(from)), Ok(thru)) => (from, thru.unwrap_or(from)),
+            (Ok(None), _) => return Reply::error(400, "the 'from' date is required"),
+            (Err(reply), _) | (_, Err(reply)) => return reply,
+        };
+        if from > thru {
+            return Reply::error(400, "the 'from' date must not be after the 'thru' date");
+        }
+        let filters = location_filters![location_filter!(name = name)];
+        let mut locations = match self.weather_data.get_locations(filters) {
+            Ok(locations) => locations,
+            Err(error) => return Reply::error(500, error.to_string()),
+        };
+        if alias {
+            locations.retain(|location| location.alias == name);
+        }
+        let location = match locations.len() {
+            1 => locations.pop().unwrap(),
+            0 => return Reply::error(404, format!("'{}' was not found", name)),
+            _ => return Reply::error(400, format!("'{}' matches more than one location", name)),
+        };
+        let history_range = DateRange::new(from, thru);
+        Reply::from_result(self.weather_data.get_location_histories(location, history_range))
+    }
+
+    /// Search the history descriptions of locations.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` are the request parameters.
+    ///
+    fn search_histories(&self, params: &Params) -> Reply {
+        let query = match params.get("query") {
+            Some(query) if query.chars().any(char::is_alphanumeric) => query,
+            Some(_) => return Reply::error(400, "the search 'query' does not contain any words"),
+            None => return Reply::error(400, "the search 'query' is required"),
+        };
+        let history_range = match (params.date("from"), params.date("thru")) {
+            (Ok(Some(from)), Ok(thru)) => Some((from, thru.unwrap_or(from))),
+            (Ok(None), Ok(None)) => None,
+            (Ok(None), Ok(Some(_))) => return Reply::error(400, "the 'from' date is required"),
+            (Err(reply), _) | (_, Err(reply)) => return reply,
+        };
+        if history_range.is_some_and(|(from, thru)| from > thru) {
+            return Reply::error(400, "the 'from' date must not be after the 'thru' date");
+        }
+        let history_range = history_range.map(|(from, thru)| DateRange::new(from, thru));
+        Reply::from_result(self.weather_data.search_histories(params.location_filters(), query, history_range))
+    }
+
+    /// Search the US Cities.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` are the request parameters.
+    ///
+    fn search_cities(&self, params: &Params) -> Reply {
+        let mut filter = CityFilter {
+            name: params.get("name").map(String::from),
+            state: params.get("state").map(String::from),
+            zip_code: params.get("zip").map(String::from),
+            ..Default::default()
+        };
+        if let Some(limit) = params.get("limit") {
+            match limit.parse() {
+                Ok(limit) => filter.limit = limit,
+                Err(_) => return Reply::error(400, format!("'{}' is not a valid limit", limit)),
+            }
+        }
+        Reply::from_result(self.weather_data.search_locations(filter))
+    }
+
+    /// Add the location in the request body.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` is the location `JSON` document.
+    ///
+    fn add_location(&self, body: &str) -> Reply {
+        let location: Location = match serde_json::from_str(body) {
+            Ok(location) => location,
+            Err(error) => return Reply::error(400, format!("the location is not valid ({})", error)),
+        };
+        match self.weather_data.add_location(location) {
+            Ok(_) => Reply::ok(201, json!({})),
+            Err(error) => Reply::error(400, error.to_string()),
+        }
+    }
+
+    /// Add the daily histories in the request body.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` is the daily histories `JSON` document.
+    ///
+    fn add_histories(&self, body: &str) -> Reply {
+        let daily_histories: DailyHistories = match serde_json::from_str(body) {
+            Ok(daily_histories) => daily_histories,
+            Err(error) => return Reply::error(400, format!("the daily histories are not valid ({})", error)),
+        };
+        match self.weather_data.add_histories(daily_histories) {
+            Ok(added) => Reply::ok(200, json!({ "added": added })),
+            Err(error) => Reply::error(400, error.to_string()),
+        }
+    }
+}
+
+/// Compare tokens in a time that does not depend on where they differ.
+///
+/// # Arguments
+///
+/// * `expected` is the server token.
+/// * `actual` is the request token.
+///
+fn is_same(expected: &str, actual: &str) -> bool {
+    expected.len() == actual.len()
+        && expected.bytes().zip(actual.bytes()).fold(0, |diff, (lhs, rhs)| diff | (lhs ^ rhs)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use weather_lib::prelude::{create_weather_data, MEMORY_DIRNAME};
+
+    fn api(token: Option<&str>) -> Api {
+        let weather_data = create_weather_data(None, None, Some(PathBuf::from(MEMORY_DIRNAME)), false).unwrap();
+        Api::new(weather_data, token.map(String::from))
+    }
+
+    fn body(text: &str) -> impl FnOnce() -> Result<String, Reply> + '_ {
+        move || Ok(text.to_string())
+    }
+
+    const LOCATION: &str = r#"{
+        "city": "Tigard", "state_id": "OR", "state": "Oregon", "name": "Tigard, OR", "alias": "tigard",
+        "latitude": "45.4312", "longitude": "-122.7717", "tz": "America/Los_Angeles"
+    }"#;
+
+    #[test]
+    fn read_only() {
+        let api = api(None);
+        let reply = api.handle("POST", "/locations", Some("Bearer secret"), body(LOCATION));
+        assert_eq!(reply.status, 403);
+        let reply = api.handle("GET", "/locations", None, body(""));
+        assert_eq!(reply.status, 200);
+        assert_eq!(reply.body, json!([]));
+        assert_eq!(api.handle("GET", "/nowhere", None, body("")).status, 404);
+        assert_eq!(api.handle("DELETE", "/locations", None, body("")).status, 405);
+        assert_eq!(api.handle("GET", "/history/daily?name=tigard", None, body("")).status, 400);
+        assert_eq!(api.handle("GET", "/history/daily?name=tigard&from=2024-07-04", None, body("")).status, 404);
+    }
+
+    #[test]
+    fn read_write() {
+        let api = api(Some("secret"));
+        assert_eq!(api.handle("POST", "/locations", None, body(LOCATION)).status, 401);
+        // an unauthorized body is not read
+        assert_eq!(api.handle("POST", "/history", None, || panic!("the body was read")).status, 401);
+        assert_eq!(api.handle("POST", "/locations", Some("Bearer guess!"), body(LOCATION)).status, 401);
+        assert_eq!(api.handle("POST", "/locations", Some("Bearer secret"), body("{}")).status, 400);
+        assert_eq!(api.handle("POST", "/locations", Some("Bearer secret"), body(LOCATION)).status, 201);
+
+        let daily_histories = format!(
+            r#"{{"location": {}, "histories": [
+                {{"date": "2024-07-04", "temperature_high": 88.5, "description": "Clear conditions."}},
+                {{"date": "2024-07-05"}}
+            ]}}"#,
+            LOCATION
+        );
+        let reply = api.handle("POST", "/history", Some("Bearer secret"), body(&daily_histories));
+        assert_eq!(reply.status, 200);
+        assert_eq!(reply.body, json!({ "added": 2 }));
+
+        let reply = api.handle("GET", "/locations?name=tig*&state=or", None, body(""));
+        assert_eq!(reply.body[0]["alias"], "tigard");
+        let reply = api.handle("GET", "/history/dates?city=Tigard", None, body(""));
+        assert_eq!(reply.body[0]["history_dates"], json!([{ "start": "2024-07-04", "end": "2024-07-05" }]));
+        let reply = api.handle("GET", "/history/summary", None, body(""));
+        assert_eq!(reply.body[0]["count"], 2);
+        let reply = api.handle("GET", "/history/daily?name=tigard&from=2024-07-04&thru=2024-07-31", None, body(""));
+        assert_eq!(reply.status, 200);
+        assert_eq!(reply.body["location"]["alias"], "tigard");
+        assert_eq!(reply.body["histories"][0]["temperature_high"], 88.5);
+        assert_eq!(reply.body["histories"][1]["date"], "2024-07-05");
+        let reply = api.handle("GET", "/history/daily?alias=tigard&from=2024-07-05", None, body(""));
+        assert_eq!(reply.body["histories"][0]["date"], "2024-07-05");
+        assert_eq!(api.handle("GET", "/history/daily?alias=tig*&from=2024-07-04", None, body("")).status, 404);
+        let reply = api.handle("GET", "/history/daily?name=tigard&from=2024-07-05&thru=2024-07-04", None, body(""));
+        assert_eq!(reply.status, 400);
+        assert_eq!(api.handle("GET", "/cities?name=Tigard&limit=x", None, body("")).status, 400);
+        assert_eq!(api.handle("GET", "/states", None, body("")).status, 500);
+        let reply = api.handle("GET", "/history/search?query=CLEAR&from=2024-07-01&thru=2024-07-31", None, body(""));
+        assert_eq!(reply.body[0]["histories"][0]["date"], "2024-07-04");
+        assert_eq!(api.handle("GET", "/history/search", None, body("")).status, 400);
+        assert_eq!(api.handle("GET", "/history/search?query=%20-%20", None, body("")).status, 400);
+        let portland = LOCATION.replace("Tigard", "Portland").replace("tigard", "portland");
+        assert_eq!(api.handle("POST", "/locations", Some("Bearer secret"), body(&portland)).status, 201);
+        assert_eq!(api.handle("GET", "/history/daily?name=*&from=2024-07-04", None, body("")).status, 400);
+        assert_eq!(api.handle("GET", "/history/search?query=clear&thru=2024-07-31", None, body("")).status, 400);
+    }
+
+    #[test]
+    fn remote_backend() {
+        use weather_lib::prelude::{location_filter, location_filters, DateRange};
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let address = server.server_addr().to_ip().unwrap();
+        let api = api(Some("secret"));
+        std::thread::spawn(move || serve(&server, &api));
+
+        // the client weather data uses the server
+        let dirname = env::temp_dir().join(format!("weather-serve-{}", std::process::id()));
+        fs::create_dir_all(&dirname).unwrap();
+        let token_file = dirname.join("token");
+        fs::write(&token_file, "secret\n").unwrap();
+        let config_file = dirname.join("weather.toml");
+        let config = format!("[weather-data]\nurl = \"http://{}\"\ntoken-file = {:?}\n", address, token_file);
+        fs::write(&config_file, config).unwrap();
+        let weather_data = create_weather_data(Some(config_file), None, None, false).unwrap();
+        fs::remove_dir_all(&dirname).unwrap();
+
+        let location: Location = serde_json::from_str(LOCATION).unwrap();
+        weather_data.add_location(location.clone()).unwrap();
+        assert!(weather_data.add_location(location.clone()).is_err());
+        let daily_histories = format!(
+            r#"{{"location": {}, "histories": [
+                {{"date": "2024-07-04", "description": "Clear conditions."}},
+                {{"date": "2024-07-05", "description": "Light rain."}}
+            ]}}"#,
+            LOCATION
+        );
+        assert_eq!(weather_data.add_histories(serde_json::from_str(&daily_histories).unwrap()).unwrap(), 2);
+
+        let testcase = weather_data.get_locations(location_filters![location_filter!(city = "tig*")]).unwrap();
+        assert_eq!(testcase[0].alias, "tigard");
+        assert!(weather_data.get_locations(location_filters![location_filter!(name = "x*")]).unwrap().is_empty());
+        let testcase = weather_data.get_history_dates(LocationFilters::default()).unwrap();
+        assert_eq!(testcase[0].history_dates.len(), 1);
+        assert_eq!(weather_data.get_history_summary(LocationFilters::default()).unwrap()[0].count, 2);
+        let date = |day| chrono::NaiveDate::from_ymd_opt(2024, 7, day).unwrap();
+        let testcase =
+            weather_data.get_daily_history(location_filter!(state = "or"), DateRange::new(date(5), date(31))).unwrap();
+        assert_eq!(testcase.histories.len(), 1);
+        assert_eq!(testcase.histories[0].description.as_deref(), Some("Light rain."));
+        let testcase = weather_data.search_histories(LocationFilters::default(), "clear", None).unwrap();
+        assert_eq!(testcase[0].histories[0].date, date(4));
+        assert!(weather_data.search_histories(LocationFilters::default(), "", None).is_err());
+        assert!(weather_data.get_states().is_err());
+    }
+
+    #[test]
+    fn body_size() {
+        assert_eq!(read_body("{}".as_bytes(), Some(2)).unwrap(), "{}");
+        assert_eq!(read_body("{}".as_bytes(), None).unwrap(), "{}");
+        let too_large = MAX_BODY_SIZE as usize + 1;
+        assert_eq!(read_body("{}".as_bytes(), Some(too_large)).unwrap_err().status, 413);
+        let body = std::io::repeat(b' ').take(MAX_BODY_SIZE + 1);
+        assert_eq!(read_body(body, None).unwrap_err().status, 413);
+        assert_eq!(read_body([0xff_u8, 0xfe].as_slice(), None).unwrap_err().status, 400);
+    }
+
+    #[test]
+    fn tokens() {
+        assert!(is_same("secret", "secret"));
+        assert!(!is_same("secret", "secreT"));
+        assert!(!is_same("secret", "secret!"));
+    }
+}
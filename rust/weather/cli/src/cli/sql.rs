@@ -0,0 +1,78 @@
+//! # The weather database query command (`sql`).
+//!
+//! The command runs a single read-only `SQL` statement against the weather database and writes the
+//! results as a text, `CSV`, or `JSON` report. The database is opened read-only and statements that
+//! would change it are rejected. Two temporary views save remembering how the tables are joined.
+//!
+//! * `daily` has the location `alias`, history `date`, and the history columns.
+//! * `summary` has the location `alias`, `name`, `histories` count, `first_date`, and `last_date`.
+//!
+
+use crate::cli::{self, err, get_writer, reports::sql_query as reports, CommandLineArgs, ReportArgs};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use weather_lib::admin_prelude::create_weather_admin;
+
+#[derive(Debug)]
+pub struct SqlCmd;
+impl SqlCmd {
+    /// The sql command name.
+    pub const NAME: &'static str = "sql";
+
+    /// The query argument id.
+    const QUERY: &'static str = "QUERY";
+
+    /// Get the sql command definition.
+    ///
+    pub fn get() -> Command {
+        Command::new(Self::NAME)
+            .about("Run a read-only SQL query against the weather database.")
+            .arg(
+                Arg::new(Self::QUERY)
+                    .action(ArgAction::Set)
+                    .value_name("QUERY")
+                    .required(true)
+                    .help("The query, the daily and summary views join locations with their history."),
+            )
+            .args(ReportArgs::get())
+            .group(ReportArgs::arg_group())
+    }
+
+    /// Executes the sql command.
+    ///
+    /// # Arguments
+    ///
+    /// * `command_args` holds the common command line arguments.
+    /// * `args` contains the sql command arguments.
+    ///
+    pub fn run(command_args: CommandLineArgs, args: ArgMatches) -> cli::Result<()> {
+        let weather_admin =
+            create_weather_admin(command_args.config_file(), command_args.profile(), command_args.weather_dir())?;
+        let sql_results = weather_admin.sql(args.get_one::<String>(Self::QUERY).unwrap())?;
+        let report_args = ReportArgs::new(&args);
+        if sql_results.rows.is_empty() && report_args.text() {
+            println!("The query did not return any rows.");
+            return Ok(());
+        }
+        let mut writer = get_writer(&report_args)?;
+        let report = if report_args.csv() {
+            reports::csv::Report.generate(sql_results)
+        } else if report_args.json() {
+            let report = match report_args.pretty() {
+                true => reports::json::Report::pretty_printed(),
+                false => reports::json::Report::default(),
+            };
+            report.generate(sql_results)
+        } else {
+            reports::text::Report::with_title_separator()
+                .generate(sql_results)
+                .into_iter()
+                .map(|row| row.to_string().trim_end().to_string())
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+        if let Err(error) = writer.write_all(report.as_bytes()) {
+            err!("SQL error writing the report: {:?}", error)?;
+        }
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use crate::cli;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::Frame;
 use std::{ops::ControlFlow, rc::Rc};
+use toolslib::fmt::commafy;
 use termui_lib::prelude::{
     break_event, log_key_pressed, log_render, Application, ApplicationResult, Console, DialogResult, DialogWindow,
     MenuDialog, MenuItem, Menubar, MessageStyle, TabDialog, TabWindow,
@@ -32,6 +33,8 @@ mod app {
     const SUMMARY_WIN_ID: &'static str = "SUMMARY";
     /// The history information window identifier.
     const HISTORY_WIN_ID: &'static str = "HISTORY";
+    /// The history client usage identifier.
+    const USAGE_ID: &'static str = "USAGE";
     /// The main menu new action identifier.
     const NEW_ID: &'static str = "NEW";
     /// The submenu search locations_win identifier.
@@ -92,6 +95,7 @@ mod app {
                         MenuItem::new(LOCATIONS_WIN_ID, "Location", 'L').with_char_select(),
                         MenuItem::new(SUMMARY_WIN_ID, "Summary", 'S').with_char_select(),
                         MenuItem::new(HISTORY_WIN_ID, "Histories", 'H').with_char_select(),
+                        MenuItem::new(USAGE_ID, "Usage", 'U').with_char_select(),
                     ]),
                     MenuItem::new(EXIT_ID, "Exit", 'x'),
                 ]),
@@ -147,6 +151,27 @@ mod app {
             }
         }
 
+        /// Show the history client requests made today and what they cost.
+        ///
+        fn show_usage(&mut self) {
+            match self.weather_data.get_query_usage() {
+                Ok(query_usage) => {
+                    let records = match query_usage.budget {
+                        Some(budget) => format!("{} of {}", commafy(query_usage.cost), commafy(budget)),
+                        None => commafy(query_usage.cost),
+                    };
+                    let message = format!(
+                        "{} requests used {} records on {} (UTC).",
+                        commafy(query_usage.requests),
+                        records,
+                        toolslib::date_time::isodate(&query_usage.date)
+                    );
+                    self.dialog.set_message(MessageStyle::Normal, message);
+                }
+                Err(error) => self.dialog.set_message(MessageStyle::Error, error),
+            }
+        }
+
         /// Give the [menu dialog](Self::dialog) a chance to consume the event.
         /// [ControlFlow::Continue] will be returned if the event is not consumed.
         ///
@@ -191,6 +216,7 @@ mod app {
                                 LOCATIONS_WIN_ID => self.show_locations(),
                                 SUMMARY_WIN_ID => self.show_summary(),
                                 HISTORY_WIN_ID => self.show_histories(),
+                                USAGE_ID => self.show_usage(),
                                 EXIT_ID => {
                                     break_event!(ApplicationResult::Exit)?;
                                 }
@@ -12,7 +12,10 @@ use termui_lib::prelude::{
     ControlGroup, ControlResult, DateEditor, DialogResult, DialogWindow, EditControl, EditField, EditFieldGroup, Label,
     MessageStyle, ProgressDialog,
 };
-use weather_lib::prelude::{DateRange, HistoryClient, Location, WeatherData};
+use weather_lib::{
+    location_filter, location_filters,
+    prelude::{DateRange, HistoryBatch, HistoryClient, HistoryHandle, Location, WeatherData},
+};
 
 /// The dialog that manages adding weather data history to a location.
 ///
@@ -23,6 +26,12 @@ pub struct AddHistory {
     history_criteria: RefCell<ButtonDialog<HistoryCriteria>>,
     /// The dialog that actually fetches and adds history.
     history_progress: RefCell<Option<ProgressDialog>>,
+    /// The weather history requests that remain to be downloaded.
+    history_batch: RefCell<Option<HistoryBatch>>,
+    /// The weather history requests that are being downloaded.
+    active_requests: RefCell<Vec<HistoryHandle>>,
+    /// The count of histories downloaded and added.
+    history_counts: RefCell<(usize, usize)>,
     /// The history client used to download weather data.
     history_client: Box<dyn HistoryClient>,
     /// The weather data history API that will be used.
@@ -35,6 +44,8 @@ impl std::fmt::Debug for AddHistory {
             .field("Location", &self.location)
             .field("history_criteria", &self.history_criteria)
             .field("download_progress", &self.history_progress)
+            .field("history_batch", &self.history_batch)
+            .field("active_requests", &self.active_requests)
             .finish()
     }
 }
@@ -52,6 +63,9 @@ impl AddHistory {
             location: location.clone(),
             history_criteria: RefCell::new(ButtonDialog::new(buttons, HistoryCriteria::new())),
             history_progress: RefCell::default(),
+            history_batch: RefCell::default(),
+            active_requests: RefCell::default(),
+            history_counts: RefCell::default(),
             history_client: weather_data.get_history_client()?,
             weather_data,
         })
@@ -66,7 +80,13 @@ impl AddHistory {
     pub fn key_pressed(&mut self, key_event: KeyEvent) -> ControlFlow<DialogResult> {
         let history_progress = &mut *self.history_progress.borrow_mut();
         match history_progress {
-            Some(progress) => progress.key_pressed(key_event)?,
+            Some(progress) => {
+                if let ControlFlow::Break(DialogResult::Cancel) = progress.key_pressed(key_event) {
+                    history_progress.take();
+                    self.cancel_active();
+                }
+                break_event!(DialogResult::Continue)?;
+            }
             None => {
                 log_key_pressed!("AddHistory");
                 let history_criteria = &mut *self.history_criteria.borrow_mut();
@@ -90,13 +110,32 @@ impl AddHistory {
                                     history_criteria.set_message(MessageStyle::Error, parse_error);
                                 }
                                 Ok(date_range) => {
-                                    let description = format!("Downloading weather history for {}", self.location.name);
-                                    history_progress.replace(ProgressDialog::new(description));
-                                    if let Err(error) = self.history_client.execute(&self.location, &date_range) {
-                                        // this will only happen if the add history state is messed up
-                                        debug_assert!(false, "{}\n{:?}", error, self)
+                                    let filters = location_filters![location_filter!(name = &self.location.alias)];
+                                    let chunk_days = HistoryBatch::DEFAULT_CHUNK_DAYS;
+                                    // the name filter also matches other locations, only this one is wanted
+                                    let alias = &self.location.alias;
+                                    let history_batch = self.weather_data.get_history_dates(filters).map(|mut dates| {
+                                        dates.retain(|history_dates| history_dates.location.alias == *alias);
+                                        HistoryBatch::new(dates, &date_range, chunk_days)
+                                    });
+                                    match history_batch {
+                                        Err(error) => history_criteria.set_message(MessageStyle::Error, error),
+                                        Ok(history_batch) if history_batch.is_empty() => {
+                                            history_criteria.win_mut().set_active(false);
+                                            let message = "Weather history is already available for those dates.";
+                                            history_criteria.set_message(MessageStyle::Normal, message);
+                                        }
+                                        Ok(history_batch) => {
+                                            let description =
+                                                format!("Downloading weather history for {}", self.location.name);
+                                            self.history_batch.replace(Some(history_batch));
+                                            self.history_counts.replace((0, 0));
+                                            let mut progress = ProgressDialog::new(description).with_cancel();
+                                            self.execute_available(&mut progress);
+                                            history_progress.replace(progress);
+                                            break_event!(DialogResult::Poll(Some(20)))?;
+                                        }
                                     }
-                                    break_event!(DialogResult::Poll(Some(20)))?;
                                 }
                             }
                         }
@@ -109,6 +148,93 @@ impl AddHistory {
         }
         ControlFlow::Continue(())
     }
+    /// Start weather history requests in the batch until the history client concurrency limit is
+    /// reached returning `false` if there are no more active requests.
+    ///
+    /// # Arguments
+    ///
+    /// - `progress` is the dialog showing the batch progress.
+    ///
+    fn execute_available(&self, progress: &mut ProgressDialog) -> bool {
+        let mut history_batch = self.history_batch.borrow_mut();
+        let Some(batch) = history_batch.as_mut() else {
+            return false;
+        };
+        let mut active_requests = self.active_requests.borrow_mut();
+        while self.history_client.available() > 0 {
+            let Some(chunk) = batch.next() else {
+                break;
+            };
+            match self.history_client.execute(&chunk.location, &chunk.date_range) {
+                Ok(handle) => active_requests.push(handle),
+                // this will only happen if the add history state is messed up
+                Err(error) => debug_assert!(false, "{}\n{:?}", error, self),
+            }
+        }
+        let (completed, total) = (batch.completed() - active_requests.len(), batch.total());
+        progress.set_status(format!("Requests {} of {} complete, {} active", completed, total, active_requests.len()));
+        !active_requests.is_empty()
+    }
+    /// Cancel the weather history requests that are active and drop the requests remaining in the batch.
+    /// Histories from requests that finished before being cancelled have already been added.
+    ///
+    fn cancel_active(&self) {
+        for handle in self.active_requests.borrow_mut().drain(..) {
+            if let Err(error) = self.history_client.cancel(handle) {
+                log::error!("{}", error);
+            }
+        }
+        self.history_batch.take();
+        let dialog = &mut *self.history_criteria.borrow_mut();
+        dialog.win_mut().set_active(false);
+        let (download_count, add_count) = *self.history_counts.borrow();
+        dialog.set_message(
+            MessageStyle::Normal,
+            format!("Download cancelled, histories downloaded {}, added {}.", download_count, add_count),
+        );
+    }
+    /// Get the weather history of requests that have finished and add it to weather data.
+    ///
+    fn add_finished(&self) -> weather_lib::Result<()> {
+        let mut active_requests = self.active_requests.borrow_mut();
+        let mut index = 0;
+        while index < active_requests.len() {
+            if !self.history_client.poll(&active_requests[index])? {
+                index += 1;
+                continue;
+            }
+            let daily_histories = self.history_client.get(active_requests.swap_remove(index))?;
+            let download_count = daily_histories.histories.len();
+            let add_count = self.weather_data.add_histories(daily_histories)?;
+            let mut counts = self.history_counts.borrow_mut();
+            counts.0 += download_count;
+            counts.1 += add_count;
+        }
+        Ok(())
+    }
+    /// Wait for the requests that are still active and add their weather history. The requests may have
+    /// already been charged so their histories are kept when the batch stops because of an error.
+    ///
+    fn add_active(&self) {
+        for handle in self.active_requests.borrow_mut().drain(..) {
+            let daily_histories = match self.history_client.get(handle) {
+                Ok(daily_histories) => daily_histories,
+                Err(error) => {
+                    log::error!("{}", error);
+                    continue;
+                }
+            };
+            let download_count = daily_histories.histories.len();
+            match self.weather_data.add_histories(daily_histories) {
+                Ok(add_count) => {
+                    let mut counts = self.history_counts.borrow_mut();
+                    counts.0 += download_count;
+                    counts.1 += add_count;
+                }
+                Err(error) => log::error!("{}", error),
+            }
+        }
+    }
     /// Draw the dialog on the terminal screen and optionally return the current cursor position.
     ///
     /// # Arguments
@@ -122,28 +248,37 @@ impl AddHistory {
         if self.history_progress.borrow().is_some() {
             // remove the current position since the progress dialog is running
             position.take();
-            match self.history_client.poll().unwrap() {
+            let finished = self.active_requests.borrow().iter().any(|handle| self.history_client.poll(handle).unwrap());
+            match finished {
                 false => {
                     self.history_progress.borrow().as_ref().unwrap().render(area, buffer);
                 }
                 true => {
+                    // each request is added as it completes so an interrupted batch can be resumed
+                    let result = self.add_finished();
+                    if result.is_ok() {
+                        let mut history_progress = self.history_progress.borrow_mut();
+                        if self.execute_available(history_progress.as_mut().unwrap()) {
+                            history_progress.as_ref().unwrap().render(area, buffer);
+                            return position;
+                        }
+                    }
                     self.history_progress.take();
+                    self.history_batch.take();
+                    if result.is_err() {
+                        self.add_active();
+                    }
                     let dialog = &mut *self.history_criteria.borrow_mut();
                     // mark the window as complete and set the message about what happened
                     dialog.win_mut().set_active(false);
-                    match self.history_client.get() {
+                    match result {
                         Err(error) => dialog.set_message(MessageStyle::Error, error),
-                        Ok(daily_histories) => {
-                            let download_count = daily_histories.histories.len();
-                            match self.weather_data.add_histories(daily_histories) {
-                                Err(error) => dialog.set_message(MessageStyle::Error, error),
-                                Ok(add_count) => {
-                                    dialog.set_message(
-                                        MessageStyle::Normal,
-                                        format!("Histories downloaded {}, added {}.", download_count, add_count),
-                                    );
-                                }
-                            }
+                        Ok(_) => {
+                            let (download_count, add_count) = *self.history_counts.borrow();
+                            dialog.set_message(
+                                MessageStyle::Normal,
+                                format!("Histories downloaded {}, added {}.", download_count, add_count),
+                            );
                         }
                     }
                 }
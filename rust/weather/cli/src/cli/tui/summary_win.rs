@@ -115,3 +115,37 @@ impl DialogWindow for SummaryWindow {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use weather_lib::prelude::{create_weather_data, DailyHistories, History, Location, MEMORY_DIRNAME};
+
+    #[test]
+    fn summary() {
+        let weather_data = create_weather_data(None, None, Some(PathBuf::from(MEMORY_DIRNAME)), false).unwrap();
+        let location = Location {
+            city: "Tigard".to_string(),
+            state_id: "OR".to_string(),
+            state: "Oregon".to_string(),
+            name: "Tigard, OR".to_string(),
+            alias: "tigard".to_string(),
+            latitude: "45.4312".to_string(),
+            longitude: "-122.7717".to_string(),
+            tz: "America/Los_Angeles".to_string(),
+        };
+        weather_data.add_location(location.clone()).unwrap();
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 7, 4).unwrap();
+        let histories = vec![History { date, ..Default::default() }];
+        weather_data.add_histories(DailyHistories { location, histories }).unwrap();
+
+        // the summary tab shows the in memory weather data
+        let testcase = SummaryWindow::new(Rc::new(weather_data)).unwrap();
+        let area = Rect::new(0, 0, 80, 5);
+        let mut buffer = Buffer::empty(area);
+        testcase.render(area, &mut buffer);
+        let text: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(text.contains("Tigard, OR"));
+    }
+}
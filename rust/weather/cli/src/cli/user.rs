@@ -2,13 +2,16 @@
 use crate::cli;
 use chrono::NaiveDate;
 use clap::{ArgMatches, Command};
-use weather_lib::prelude::WeatherData;
+use weather_lib::prelude::{DateRange, WeatherData};
 
 mod add_history;
+mod batch_history;
+mod export;
 mod list_history;
 mod list_locations;
 mod list_summary;
 mod report_history;
+mod search_history;
 mod query_cities;
 mod query_states;
 
@@ -22,7 +25,10 @@ impl User {
             list_history::command(),
             list_summary::command(),
             report_history::command(),
+            search_history::command(),
             add_history::command(),
+            batch_history::command(),
+            export::command(),
             query_cities::command(),
             query_states::command(),
         ]
@@ -40,7 +46,10 @@ impl User {
             list_history::COMMAND_NAME => list_history::execute(weather_data, args),
             list_summary::COMMAND_NAME => list_summary::execute(weather_data, args),
             report_history::COMMAND_NAME => report_history::execute(weather_data, args),
+            search_history::COMMAND_NAME => search_history::execute(weather_data, args),
             add_history::COMMAND_NAME => add_history::execute(weather_data, args),
+            batch_history::COMMAND_NAME => batch_history::execute(weather_data, args),
+            export::COMMAND_NAME => export::execute(weather_data, args),
             query_cities::COMMAND_NAME => query_cities::execute(weather_data, args),
             query_states::COMMAND_NAME => query_states::execute(weather_data, args),
             _ => unreachable!("User command should not be here..."),
@@ -72,6 +81,30 @@ fn date_parser(date: &str) -> Result<NaiveDate, String> {
     }
 }
 
+/// Get the history dates from the optional from and thru date arguments. A missing from or thru
+/// date leaves that end of the range open.
+///
+/// # Arguments
+///
+/// * `args` contains the command arguments.
+/// * `from_id` is the from date argument id.
+/// * `thru_id` is the thru date argument id.
+///
+fn history_range(args: &ArgMatches, from_id: &str, thru_id: &str) -> cli::Result<Option<DateRange>> {
+    let from = args.get_one::<NaiveDate>(from_id).copied();
+    let thru = args.get_one::<NaiveDate>(thru_id).copied();
+    match (from, thru) {
+        (None, None) => Ok(None),
+        (Some(from), Some(thru)) if from > thru => cli::err!("The from date must not be after the thru date."),
+        (from, thru) => {
+            // open ended dates need to be years the database will compare as dates
+            let from = from.unwrap_or(NaiveDate::from_ymd_opt(1, 1, 1).unwrap());
+            let thru = thru.unwrap_or(NaiveDate::from_ymd_opt(9999, 12, 31).unwrap());
+            Ok(Some(DateRange::new(from, thru)))
+        }
+    }
+}
+
 /// Trim trailing whitespace from the string.
 ///
 macro_rules! trim_row_end {
@@ -6,8 +6,9 @@ use chrono::NaiveDate;
 use clap::{Arg, ArgAction, ArgMatches, Command};
 use std::{
     io::{stdout, Write},
+    sync::atomic::{AtomicBool, Ordering},
     thread::sleep,
-    time::{Duration, SystemTime},
+    time::Duration,
 };
 use weather_lib::{
     location_filter, location_filters,
@@ -26,6 +27,9 @@ const FROM: &'static str = "FROM";
 /// The history thru date argument id.
 const THRU: &'static str = "THRU";
 
+/// Set when the command has been interrupted by Ctrl-C.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
 /// Create a new instance of the add history command arguments.
 ///
 pub fn command() -> Command {
@@ -83,6 +87,7 @@ pub fn execute(weather_data: &WeatherData, args: ArgMatches) -> cli::Result<()>
                 match weather_data.get_history_client() {
                     Err(error) => err!("Failed to get history client: {:?}", error),
                     Ok(client) => {
+                        catch_interrupt();
                         let daily_histories = get_histories(&client, location, date_range)?;
                         let histories_found = daily_histories.histories.len();
                         let histories_added = weather_data.add_histories(daily_histories)?;
@@ -103,33 +108,50 @@ pub fn execute(weather_data: &WeatherData, args: ArgMatches) -> cli::Result<()>
 /// - `location` is the historical weather data owner.
 /// - `date_range` are the dates being asked for.
 ///
-fn get_histories(
+pub(super) fn get_histories(
     client: &Box<dyn HistoryClient>,
     location: Location,
     date_range: DateRange,
 ) -> cli::Result<DailyHistories> {
-    client.execute(&location, &date_range)?;
-    let timeout = SystemTime::now() + Duration::new(30, 0);
+    // the HTTP client configuration controls request timeouts and retries
+    let handle = client.execute(&location, &date_range)?;
     let pause = Duration::from_millis(10);
     let mut loop_cnt = 0usize;
     // this loop could use some tender love
     loop {
-        if SystemTime::now() > timeout {
-            err!("Client history timed out")?;
-        }
         if (loop_cnt % 20) == 0 {
             write!(stdout().lock(), ".").unwrap();
             stdout().flush().unwrap();
         }
         loop_cnt += 1;
-        if client.poll()? {
+        if is_interrupted() {
+            client.cancel(handle)?;
+            println!();
+            err!("The history request was cancelled.")?;
+        }
+        if client.poll(&handle)? {
             break;
         }
         sleep(pause);
     }
     // poll() breaks the loop so this will not hang the commandline
-    match client.get() {
+    match client.get(handle) {
         Ok(daily_histories) => Ok(daily_histories),
         Err(error) => err!("{error}"),
     }
 }
+
+/// Catch Ctrl-C so history requests can be cancelled instead of the process being killed while
+/// histories are being added.
+///
+pub(super) fn catch_interrupt() {
+    if let Err(error) = ctrlc::set_handler(|| INTERRUPTED.store(true, Ordering::Relaxed)) {
+        log::warn!("Ctrl-C will not cancel history requests ({}).", error);
+    }
+}
+
+/// Query if the command has been interrupted by Ctrl-C.
+///
+pub(super) fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
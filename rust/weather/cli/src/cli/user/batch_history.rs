@@ -0,0 +1,201 @@
+//! The batch add weather data history command.
+//!
+//! Weather history is requested for all the locations that match the location filters. The date
+//! range is split into chunks and each chunk is added to weather data as soon as it is received.
+//! Dates that already have history are skipped, so if the command is interrupted running it
+//! again will continue where it left off. Several chunks are requested at the same time, up to the
+//! history client concurrency limit. Ctrl-C cancels the active requests.
+//!
+
+use super::{
+    add_history::{catch_interrupt, is_interrupted},
+    date_parser,
+};
+use crate::cli::{self, err, LocationFilterArgs};
+use chrono::NaiveDate;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::{thread::sleep, time::Duration};
+use weather_lib::prelude::{DateRange, HistoryBatch, HistoryChunk, HistoryClient, HistoryHandle, WeatherData};
+
+/// The batch add weather data history command name.
+pub const COMMAND_NAME: &str = "bh";
+
+/// The history from date argument id.
+const FROM: &str = "BATCH_HISTORY_FROM";
+
+/// The history thru date argument id.
+const THRU: &str = "BATCH_HISTORY_THRU";
+
+/// The number of days in a history request argument id.
+const DAYS: &str = "BATCH_HISTORY_DAYS";
+
+/// Create a new instance of the batch add history command arguments.
+///
+pub fn command() -> Command {
+    Command::new(COMMAND_NAME)
+        .about("Add weather history to locations in batches.")
+        .arg(
+            Arg::new(FROM)
+                .long("from")
+                .action(ArgAction::Set)
+                .required(true)
+                .require_equals(true)
+                .value_parser(date_parser)
+                .value_name("DATE")
+                .help("The weather history starting date."),
+        )
+        .arg(
+            Arg::new(THRU)
+                .long("thru")
+                .action(ArgAction::Set)
+                .require_equals(true)
+                .value_parser(date_parser)
+                .value_name("DATE")
+                .help("The weather history ending date (default FROM)."),
+        )
+        .arg(
+            Arg::new(DAYS)
+                .long("days")
+                .action(ArgAction::Set)
+                .require_equals(true)
+                .value_parser(days_parser)
+                .value_name("DAYS")
+                .help("The maximum number of days in a weather history request (default 31)."),
+        )
+        .args(LocationFilterArgs::get())
+        .arg_required_else_help(true)
+}
+
+/// Executes the batch add history command.
+///
+/// # Arguments
+///
+/// * `weather_data` is the weather library API used by the command.
+/// * `args` contains the batch add history command arguments.
+///
+pub fn execute(weather_data: &WeatherData, args: ArgMatches) -> cli::Result<()> {
+    let from = args.get_one::<NaiveDate>(FROM).unwrap();
+    let thru = args.get_one::<NaiveDate>(THRU).unwrap_or(from);
+    if thru < from {
+        err!("The thru date {thru} cannot be before the from date {from}.")?;
+    }
+    let date_range = DateRange::new(*from, *thru);
+    let chunk_days = args.get_one::<usize>(DAYS).map_or(HistoryBatch::DEFAULT_CHUNK_DAYS, |days| *days);
+    let filters = LocationFilterArgs::new(&args).as_location_filters();
+    let batch = weather_data.get_history_batch(filters, &date_range, chunk_days)?;
+    if batch.is_empty() {
+        println!("Weather history is already available for the locations.");
+        return Ok(());
+    }
+    let client = match weather_data.get_history_client() {
+        Ok(client) => client,
+        Err(error) => err!("Failed to get history client: {:?}", error)?,
+    };
+    catch_interrupt();
+    let total = batch.total();
+    let mut batch = batch.peekable();
+    let mut active: Vec<(HistoryHandle, HistoryChunk)> = vec![];
+    let (mut completed, mut histories_found, mut histories_added) = (0usize, 0usize, 0usize);
+    let pause = Duration::from_millis(10);
+    let stopped = |completed: usize, reason: String| -> cli::Result<()> {
+        err!("{} of {} requests completed, run the command again to resume ({}).", completed, total, reason)
+    };
+    let result = 'batch: loop {
+        if batch.peek().is_none() && active.is_empty() {
+            break Ok(());
+        }
+        if is_interrupted() {
+            for (handle, _) in active.drain(..) {
+                client.cancel(handle)?;
+            }
+            break stopped(completed, "cancelled".to_string());
+        }
+        // keep the history client busy
+        while client.available() > 0 {
+            let Some(chunk) = batch.next() else {
+                break;
+            };
+            match client.execute(&chunk.location, &chunk.date_range) {
+                Ok(handle) => active.push((handle, chunk)),
+                Err(error) => break 'batch stopped(completed, error.to_string()),
+            }
+        }
+        let mut finished = vec![];
+        for (index, (handle, _)) in active.iter().enumerate() {
+            match client.poll(handle) {
+                Ok(true) => finished.push(index),
+                Ok(false) => (),
+                Err(error) => break 'batch Err(error.into()),
+            }
+        }
+        if finished.is_empty() {
+            sleep(pause);
+            continue;
+        }
+        for index in finished.into_iter().rev() {
+            let (handle, chunk) = active.swap_remove(index);
+            let daily_histories = match client.get(handle) {
+                Ok(daily_histories) => daily_histories,
+                Err(error) => break 'batch stopped(completed, error.to_string()),
+            };
+            completed += 1;
+            let found = daily_histories.histories.len();
+            let added = match weather_data.add_histories(daily_histories) {
+                Ok(added) => added,
+                Err(error) => break 'batch Err(error.into()),
+            };
+            let (start, end) = chunk.date_range.as_iso8601();
+            println!("[{}/{}] {} {} thru {} {} received, {} added.", completed, total, chunk.location.alias, start, end, found, added);
+            histories_found += found;
+            histories_added += added;
+        }
+    };
+    if result.is_err() && !active.is_empty() {
+        // the active requests may have been charged so add their histories before giving up
+        let added = add_active(weather_data, client.as_ref(), active);
+        println!("{} histories added from the requests that were active.", added);
+    }
+    result?;
+    println!("\n{} histories received, {} histories added.", histories_found, histories_added);
+    Ok(())
+}
+
+/// Wait for the active requests and add their histories. Problems are logged since an error is
+/// already being reported.
+///
+/// # Arguments
+///
+/// * `weather_data` is the weather library API used by the command.
+/// * `client` is the history client that executed the requests.
+/// * `active` are the requests that have not finished.
+///
+fn add_active(
+    weather_data: &WeatherData,
+    client: &dyn HistoryClient,
+    active: Vec<(HistoryHandle, HistoryChunk)>,
+) -> usize {
+    let mut histories_added = 0;
+    for (handle, _) in active {
+        match client.get(handle).and_then(|daily_histories| weather_data.add_histories(daily_histories)) {
+            Ok(added) => histories_added += added,
+            Err(error) => log::warn!("{}", error),
+        }
+    }
+    histories_added
+}
+
+/// Parse the number of days in a history request argument.
+///
+/// # Arguments
+///
+/// * `days_arg` is the command line argument.
+///
+fn days_parser(days_arg: &str) -> Result<usize, String> {
+    match days_arg.parse::<usize>() {
+        Ok(days) => match days > 0 {
+            true => Ok(days),
+            _ => Err("days must be greater than 0".to_string()),
+        },
+        Err(_) => Err("days needs to be an unsigned integer.".to_string()),
+    }
+}
@@ -0,0 +1,123 @@
+//! # The implementation for export (`export`).
+//!
+//! The export command writes location histories to newline delimited `JSON` or `Apache Parquet`
+//! files that can be loaded by other tools. Each row carries the location metadata along with the
+//! typed history values.
+//!
+//! Histories are written to a single file unless `--split` is used. In that case the output is a
+//! directory and a file is written for each location, named by its alias.
+//!
+use super::{date_parser, history_range};
+use crate::cli::{self, LocationFilterArgs};
+use clap::{builder::PossibleValue, value_parser, Arg, ArgAction, ArgMatches, Command, ValueEnum};
+use std::path::PathBuf;
+use toolslib::fmt::commafy;
+use weather_lib::prelude::{ExportFormat, WeatherData};
+
+/// The export command name.
+pub const COMMAND_NAME: &'static str = "export";
+
+/// The export format argument id.
+const FORMAT: &'static str = "FORMAT";
+
+/// The export output argument id.
+const OUTPUT: &'static str = "OUTPUT";
+
+/// The split export argument id.
+const SPLIT: &'static str = "SPLIT";
+
+/// The history from date argument id.
+const FROM: &'static str = "FROM";
+
+/// The history thru date argument id.
+const THRU: &'static str = "THRU";
+
+/// The command line export format.
+#[derive(Debug, Clone, Copy)]
+struct Format(ExportFormat);
+impl ValueEnum for Format {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Format(ExportFormat::Ndjson), Format(ExportFormat::Parquet)]
+    }
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.0.extension()))
+    }
+}
+
+/// Create the export command.
+pub fn command() -> Command {
+    Command::new(COMMAND_NAME)
+        .about("Export weather history by location to NDJSON or Parquet.")
+        .arg(
+            Arg::new(FORMAT)
+                .short('f')
+                .long("format")
+                .action(ArgAction::Set)
+                .value_name("FORMAT")
+                .value_parser(value_parser!(Format))
+                .default_value("ndjson")
+                .help("The export file format."),
+        )
+        .arg(
+            Arg::new(OUTPUT)
+                .short('o')
+                .long("output")
+                .action(ArgAction::Set)
+                .value_name("PATH")
+                .value_parser(value_parser!(PathBuf))
+                .required(true)
+                .help("The export file, or the directory when location files are split."),
+        )
+        .arg(
+            Arg::new(SPLIT)
+                .long("split")
+                .action(ArgAction::SetTrue)
+                .help("Write a file for each location (default one combined file)."),
+        )
+        .arg(
+            Arg::new(FROM)
+                .long("from")
+                .action(ArgAction::Set)
+                .value_name("DATE")
+                .require_equals(true)
+                .value_parser(date_parser)
+                .help("Export history starting on this date."),
+        )
+        .arg(
+            Arg::new(THRU)
+                .long("thru")
+                .action(ArgAction::Set)
+                .value_name("DATE")
+                .require_equals(true)
+                .value_parser(date_parser)
+                .help("Export history ending on this date."),
+        )
+        .args(LocationFilterArgs::get())
+}
+
+/// Executes the export command.
+///
+/// # Arguments
+///
+/// * `weather_data` is the weather library API used by the command.
+/// * `args` contains the export command arguments.
+///
+pub fn execute(weather_data: &WeatherData, args: ArgMatches) -> cli::Result<()> {
+    let format = args.get_one::<Format>(FORMAT).unwrap().0;
+    let output = args.get_one::<PathBuf>(OUTPUT).unwrap();
+    let history_range = history_range(&args, FROM, THRU)?;
+    let filters = LocationFilterArgs::new(&args).as_location_filters();
+    let details = weather_data.export_histories(filters, history_range, format, output, args.get_flag(SPLIT))?;
+    match details.files.is_empty() {
+        true => println!("There was no weather history to export."),
+        false => println!(
+            "Exported {} histories for {} locations to {} {} file{}.",
+            commafy(details.histories),
+            commafy(details.locations),
+            details.files.len(),
+            format,
+            if details.files.len() == 1 { "" } else { "s" }
+        ),
+    }
+    Ok(())
+}
@@ -0,0 +1,97 @@
+//! # The implementation for search history (`sh`).
+//!
+//! The search history command lists the days whose weather description matches a query, such as
+//! `thunderstorms` or `"freezing fog"`. When a weather history database is being used the query
+//! supports the `SQLite FTS5` syntax, otherwise a description must contain all the query words.
+//!
+//! The command allows locations to be filtered and the search restricted to a range of dates.
+//!
+use super::{date_parser, history_range, trim_row_end};
+use crate::cli::{self, err, get_writer, reports::search_history as reports, LocationFilterArgs, ReportArgs};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use weather_lib::prelude::WeatherData;
+
+/// The search history command name.
+pub const COMMAND_NAME: &str = "sh";
+
+/// The search query argument id.
+const QUERY: &str = "QUERY";
+
+/// The history from date argument id.
+const FROM: &str = "FROM";
+
+/// The history thru date argument id.
+const THRU: &str = "THRU";
+
+/// Create the search history command.
+pub fn command() -> Command {
+    Command::new(COMMAND_NAME)
+        .about("Search the weather history descriptions by location.")
+        .arg(
+            Arg::new(QUERY)
+                .action(ArgAction::Set)
+                .required(true)
+                .value_name("QUERY")
+                .help("The words to search for (eg: thunderstorms, \"freezing fog\")."),
+        )
+        .arg(
+            Arg::new(FROM)
+                .long("from")
+                .action(ArgAction::Set)
+                .value_name("DATE")
+                .require_equals(true)
+                .value_parser(date_parser)
+                .help("Search history starting on this date."),
+        )
+        .arg(
+            Arg::new(THRU)
+                .long("thru")
+                .action(ArgAction::Set)
+                .value_name("DATE")
+                .require_equals(true)
+                .value_parser(date_parser)
+                .help("Search history ending on this date."),
+        )
+        .args(ReportArgs::get())
+        .group(ReportArgs::arg_group())
+        .args(LocationFilterArgs::get())
+}
+
+/// Executes the search history command.
+///
+/// # Arguments
+///
+/// * `weather_data` is the weather library API used by the command.
+/// * `args` contains the search history command arguments.
+///
+pub fn execute(weather_data: &WeatherData, args: ArgMatches) -> cli::Result<()> {
+    let query = args.get_one::<String>(QUERY).unwrap();
+    let history_range = history_range(&args, FROM, THRU)?;
+    let filters = LocationFilterArgs::new(&args).as_location_filters();
+    let histories = weather_data.search_histories(filters, query, history_range)?;
+    let report_args = ReportArgs::new(&args);
+    let mut writer = get_writer(&report_args)?;
+    let report = if report_args.csv() {
+        reports::csv::Report.generate(histories)
+    } else if report_args.json() {
+        let report = match report_args.pretty() {
+            true => reports::json::Report::pretty_printed(),
+            false => reports::json::Report::default(),
+        };
+        report.generate(histories)
+    } else if histories.is_empty() {
+        format!("No weather history matches '{}'.", query)
+    } else {
+        reports::text::Report::default()
+            .with_title_separator()
+            .generate(histories)
+            .into_iter()
+            .map(|row| trim_row_end!(row.to_string()))
+            .collect::<Vec<String>>()
+            .join("\n")
+    };
+    match writer.write_all(report.as_bytes()) {
+        Ok(_) => Ok(()),
+        Err(error) => err!("Search history error writing the report: {:?}", error),
+    }
+}
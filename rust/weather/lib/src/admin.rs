@@ -16,6 +16,8 @@ pub struct Components {
 pub struct DbDetails {
     /// The size of the database.
     pub size: usize,
+    /// The database schema version, `0` if the database was created before versions were tracked.
+    pub version: u32,
     /// The location weather history information.
     pub location_details: Vec<LocationDetails>,
 }
@@ -45,3 +47,128 @@ pub struct UsCityDetails {
     pub db_size: usize,
     pub state_info: Vec<(String, usize)>,
 }
+
+/// How an interrupted weather data file update was recovered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecoveryAction {
+    /// The update was complete and replaced the file.
+    RolledForward,
+    /// The update was not complete and was discarded.
+    RolledBack,
+    /// The file was not valid and was restored from its backup.
+    Restored,
+}
+impl std::fmt::Display for RecoveryAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecoveryAction::RolledForward => write!(f, "rolled forward"),
+            RecoveryAction::RolledBack => write!(f, "rolled back"),
+            RecoveryAction::Restored => write!(f, "restored from backup"),
+        }
+    }
+}
+
+/// A weather data file whose interrupted update was recovered.
+#[derive(Debug)]
+pub struct Recovery {
+    /// The name of the file in the weather data directory.
+    pub filename: String,
+    /// What was done to recover the file.
+    pub action: RecoveryAction,
+}
+
+/// A problem found verifying the weather data.
+#[derive(Debug)]
+pub struct VerifyProblem {
+    /// The location alias name.
+    pub alias: String,
+    /// The history file or date with the problem, `None` if it is the archive.
+    pub history: Option<String>,
+    /// What is wrong.
+    pub problem: String,
+}
+
+/// The results of verifying the weather data.
+#[derive(Debug, Default)]
+pub struct VerifyDetails {
+    /// The count of location archives that were checked.
+    pub archives: usize,
+    /// The count of weather history files that were checked.
+    pub histories: usize,
+    /// The problems that were found.
+    pub problems: Vec<VerifyProblem>,
+    /// The count of weather history files moved to quarantine.
+    pub quarantined: usize,
+}
+
+/// The results of backing up the weather data.
+#[derive(Debug, Default)]
+pub struct BackupDetails {
+    /// The count of locations in the backup.
+    pub locations: usize,
+    /// The count of files in the backup.
+    pub files: usize,
+    /// Indicates the database is in the backup.
+    pub database: bool,
+    /// The size of the backup bundle.
+    pub size: u64,
+}
+
+/// The results of restoring weather data from a backup.
+#[derive(Debug, Default)]
+pub struct RestoreDetails {
+    /// The alias names of the locations that were restored.
+    pub locations: Vec<String>,
+    /// Indicates the database was restored.
+    pub database: bool,
+    /// Indicates the database exists but was not restored so it needs to be reloaded.
+    pub stale_database: bool,
+}
+
+/// Where a location was added when the database and archives were synchronized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyncTarget {
+    /// The location was added to the database.
+    Database,
+    /// The location was added to the locations document and archives.
+    Archives,
+}
+impl std::fmt::Display for SyncTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncTarget::Database => write!(f, "database"),
+            SyncTarget::Archives => write!(f, "archives"),
+        }
+    }
+}
+
+/// The changes made to a location synchronizing the database and archives.
+#[derive(Debug)]
+pub struct SyncLocation {
+    /// The location alias name.
+    pub alias: String,
+    /// Where the location was added if it was missing.
+    pub added_to: Option<SyncTarget>,
+    /// The dates of histories copied from the archive to the database.
+    pub to_database: Vec<chrono::NaiveDate>,
+    /// The dates of histories copied from the database to the archive.
+    pub to_archive: Vec<chrono::NaiveDate>,
+}
+
+/// The results of synchronizing the database and archives.
+#[derive(Debug, Default)]
+pub struct SyncDetails {
+    /// Indicates the changes were found but not made.
+    pub dry_run: bool,
+    /// The locations that were changed.
+    pub locations: Vec<SyncLocation>,
+}
+
+/// The results of a read-only weather database query.
+#[derive(Debug, Default)]
+pub struct SqlResults {
+    /// The result column names.
+    pub columns: Vec<String>,
+    /// The result rows, values are `JSON` null, number, or string.
+    pub rows: Vec<Vec<serde_json::Value>>,
+}
@@ -2,30 +2,54 @@
 
 mod db;
 mod filesys;
+mod memory;
+mod remote;
 
-pub use config::Config;
+pub use config::{
+    config_settings, find_config_file, starter_config, user_config_path, validate_config, Config, ENV_CONFIG,
+    ENV_TOKEN,
+};
 pub mod admin;
 mod config;
 
 use crate::prelude::{
     DailyHistories, DateRange, HistoryDates, HistorySummaries, Location, LocationFilters, State, CityFilter,
 };
+use crate::history_client::UsageLedger;
 use std::path::PathBuf;
 
-/// Get the backend implementation of weather data.
+/// The weather data directory name that keeps weather data in memory instead of a directory.
+pub const MEMORY_DIRNAME: &str = ":memory:";
+
+/// Get the backend implementation of weather data. When the weather data directory is
+/// [MEMORY_DIRNAME] the weather data is kept in memory. When the configuration has a weather data
+/// server URL the server is used, unless the weather data directory name is overridden.
 ///
 /// # Arguments
 ///
 /// * `config_file` is the weather data configuration filename.
+/// * `profile` is the name of the configuration profile to use.
 /// * `dirname` is the weather data directory name override.
 /// * `no_db` forces the filesys backend to be used.
 ///
-pub fn create(config_file: Option<PathBuf>, dirname: Option<PathBuf>, no_db: bool) -> crate::Result<Box<dyn Backend>> {
-    let mut config = Config::new(config_file)?;
+pub fn create(
+    config_file: Option<PathBuf>,
+    profile: Option<String>,
+    dirname: Option<PathBuf>,
+    no_db: bool,
+) -> crate::Result<Box<dyn Backend>> {
+    let mut config = Config::new(config_file, profile)?;
     if let Some(path) = dirname {
-        config.weather_data.directory = path.display().to_string();
+        config.set_dirname(&path);
+    }
+    if config.weather_data.url.is_some() {
+        return remote::create_remote_backend(config);
+    }
+    if config.weather_data.directory == MEMORY_DIRNAME {
+        return memory::create_memory_backend(config);
     }
     let weather_dir = filesys::WeatherDir::try_from(&config)?;
+    filesys::recover_and_log(&weather_dir)?;
     if no_db {
         filesys::create_filesys_backend(config)
     } else if db::is_available(&weather_dir) {
@@ -42,6 +66,12 @@ pub(crate) trait Backend: Send {
     ///
     fn get_config(&self) -> &Config;
 
+    /// Get the ledger of history client query costs.
+    ///
+    fn get_usage_ledger(&self) -> UsageLedger {
+        UsageLedger::new(&self.get_config().weather_data.directory)
+    }
+
     /// Add weather data history to a location.
     ///
     /// # Arguments
@@ -63,6 +93,15 @@ pub(crate) trait Backend: Send {
     // todo: change this to allow multiple locations or change to the location alias
     fn get_daily_histories(&self, filters: LocationFilters, history_range: DateRange) -> crate::Result<DailyHistories>;
 
+    /// Get daily weather history for a location selected by its alias.
+    ///
+    /// # Arguments
+    ///
+    /// - `location` is the location whose history is returned.
+    /// - `history_range` covers the history dates returned.
+    ///
+    fn get_location_histories(&self, location: Location, history_range: DateRange) -> crate::Result<DailyHistories>;
+
     /// Get the history dates for locations.
     ///
     /// # Arguments
@@ -79,6 +118,21 @@ pub(crate) trait Backend: Send {
     ///
     fn get_history_summaries(&self, filters: LocationFilters) -> crate::Result<Vec<HistorySummaries>>;
 
+    /// Search the weather history descriptions of locations.
+    ///
+    /// # Arguments
+    ///
+    /// - `filters` identifies the locations.
+    /// - `query` selects the history descriptions that match.
+    /// - `history_range` restricts the history dates searched, if `None` all history is searched.
+    ///
+    fn search_histories(
+        &self,
+        filters: LocationFilters,
+        query: &str,
+        history_range: Option<DateRange>,
+    ) -> crate::Result<Vec<DailyHistories>>;
+
     /// Get the weather location metadata.
     ///
     /// # Arguments
@@ -109,18 +163,19 @@ pub(crate) trait Backend: Send {
 }
 
 #[cfg(test)]
-mod testlib {
+pub(crate) mod testlib {
     //! A library for common utilities used by the backend.
 
+    use crate::entities::Location;
     use rand::Rng;
     use std::{env, fmt, fs, path};
 
     /// Used to create a temporary weather directory and delete it as part of the function exit.
     #[derive(Debug)]
-    pub(in crate::backend) struct TestFixture(path::PathBuf);
+    pub(crate) struct TestFixture(path::PathBuf);
     impl TestFixture {
         /// Creates a test weather directory or panics if a unique directory cannot be created.
-        pub(in crate::backend) fn create() -> Self {
+        pub(crate) fn create() -> Self {
             let tmpdir = env::temp_dir();
             let mut weather_dir: Option<path::PathBuf> = None;
             // try to create a test directory 10 times
@@ -146,7 +201,7 @@ mod testlib {
                 None => panic!("Tried 10 times to get a unique test directory name and failed..."),
             }
         }
-        pub(in crate::backend) fn copy_resources(&self, source: &path::PathBuf) {
+        pub(crate) fn copy_resources(&self, source: &path::PathBuf) {
             if source.is_file() {
                 let target = self.0.join(source.file_name().unwrap().to_str().unwrap());
                 if let Err(err) = fs::copy(source, &target) {
@@ -183,7 +238,7 @@ mod testlib {
         }
     }
 
-    pub(in crate::backend) fn generate_random_string(len: usize) -> String {
+    pub(crate) fn generate_random_string(len: usize) -> String {
         let mut rand = rand::rng();
         const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmonopqrstuvwxyz0123456789";
         let random_string = (0..len)
@@ -196,7 +251,27 @@ mod testlib {
         random_string
     }
 
-    pub(in crate::backend) fn test_resources() -> path::PathBuf {
+    pub(crate) fn test_resources() -> path::PathBuf {
         std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources").join("tests")
     }
+
+    /// Create an Oregon location for tests.
+    ///
+    /// # Arguments
+    ///
+    /// * `city` is the location city, the location name is the city and state.
+    /// * `alias` is the location alias.
+    ///
+    pub(crate) fn location(city: &str, alias: &str) -> Location {
+        Location {
+            city: city.to_string(),
+            state_id: "OR".to_string(),
+            state: "Oregon".to_string(),
+            name: format!("{}, OR", city),
+            alias: alias.to_string(),
+            latitude: "45.4312".to_string(),
+            longitude: "-122.7717".to_string(),
+            tz: "America/Los_Angeles".to_string(),
+        }
+    }
 }
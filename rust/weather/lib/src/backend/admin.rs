@@ -1,20 +1,36 @@
 //! The administration commands are scoped to this module.
-use super::{db, filesys};
+use super::{db, filesys, filesys::LockMode, Config};
 use crate::{
-    admin_prelude::{Components, UsCityDetails},
-    entities::LocationFilters,
+    admin_prelude::{
+        BackupDetails, Components, Recovery, RestoreDetails, SqlResults, SyncDetails, UsCityDetails, VerifyDetails,
+    },
+    entities::{LocationFilters, QueryUsage},
+    history_client::UsageLedger,
+};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+    time::Duration,
 };
-use std::path::PathBuf;
 use toolslib::{fmt::commafy, stopwatch::StopWatch};
 
 /// Create an instance of the weather data administration `API`.
 ///
 /// # Arguments
 ///
+/// * `config_file` is the weather data configuration filename.
+/// * `profile` is the name of the configuration profile to use.
 /// * `dirname` is the weather data directory pathname.
-pub fn create_weather_admin(dirname: Option<PathBuf>) -> crate::Result<WeatherAdmin> {
-    let dirname = dirname.map_or(Default::default(), |pb| pb.as_path().display().to_string());
-    WeatherAdmin::new(dirname.as_str())
+pub fn create_weather_admin(
+    config_file: Option<PathBuf>,
+    profile: Option<String>,
+    dirname: Option<PathBuf>,
+) -> crate::Result<WeatherAdmin> {
+    let config = Config::new(config_file, profile)?;
+    let dirname = dirname.map_or(config.weather_data.directory, |pb| pb.as_path().display().to_string());
+    let lock_timeout = Duration::from_secs(config.weather_data.lock_timeout);
+    WeatherAdmin::new(dirname.as_str(), lock_timeout, config.profile)
 }
 
 /// The weather data administration `API`.
@@ -22,6 +38,8 @@ pub fn create_weather_admin(dirname: Option<PathBuf>) -> crate::Result<WeatherAd
 pub struct WeatherAdmin(
     /// The weather data directory.
     filesys::WeatherDir,
+    /// The configuration profile name.
+    Option<String>,
 );
 impl WeatherAdmin {
     /// Create an instance of the weather data administration `API`.
@@ -29,8 +47,20 @@ impl WeatherAdmin {
     /// # Arguments
     ///
     /// * `dirname` is the weather data directory pathname.
-    fn new(dirname: &str) -> crate::Result<Self> {
-        Ok(WeatherAdmin(filesys::create_weather_dir(dirname)?))
+    /// * `lock_timeout` is how long to wait for the weather data directory lock.
+    /// * `profile` is the configuration profile name.
+    fn new(dirname: &str, lock_timeout: Duration, profile: Option<String>) -> crate::Result<Self> {
+        Ok(WeatherAdmin(filesys::create_weather_dir(dirname, lock_timeout)?, profile))
+    }
+
+    /// Get the name of the configuration profile being used.
+    pub fn profile(&self) -> Option<&str> {
+        self.1.as_deref()
+    }
+
+    /// Get the weather data directory.
+    pub fn directory(&self) -> &std::path::Path {
+        self.0.path()
     }
 
     /// Initialize the weather database using the supplied database configuration.
@@ -61,6 +91,73 @@ impl WeatherAdmin {
         Ok(Components { db_details, fs_details })
     }
 
+    /// Roll interrupted archive and locations file updates forward or back.
+    pub fn recover(&self) -> crate::Result<Vec<Recovery>> {
+        filesys::recover(&self.0)
+    }
+
+    /// Copy the histories that are missing from the database or the location archives.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` identifies the locations that will be synchronized.
+    /// * `dry_run` when `true` reports what is missing without changing anything.
+    pub fn sync(&self, filters: LocationFilters, dry_run: bool) -> crate::Result<SyncDetails> {
+        let locations = db::admin::sync(&self.0, filters, dry_run)?;
+        Ok(SyncDetails { dry_run, locations })
+    }
+
+    /// Backup the weather data to a bundle.
+    ///
+    /// # Arguments
+    ///
+    /// * `bundle` is the backup bundle that will be created or replaced.
+    /// * `database` when `true` includes the database in the backup.
+    pub fn backup(&self, bundle: &Path, database: bool) -> crate::Result<BackupDetails> {
+        // hold the lock so the database and archives are the same snapshot
+        let _lock = self.0.lock(LockMode::Shared)?;
+        if !database {
+            return filesys::backup(&self.0, bundle, None);
+        }
+        let snapshot = env::temp_dir().join(format!("weather_data-{}.db", process::id()));
+        let _ = fs::remove_file(&snapshot);
+        let backup_result = db::admin::snapshot(&self.0, &snapshot).and_then(|db_name| {
+            let database = db_name.map(|db_name| (db_name, snapshot.as_path()));
+            filesys::backup(&self.0, bundle, database)
+        });
+        let _ = fs::remove_file(&snapshot);
+        backup_result
+    }
+
+    /// Restore the weather data from a backup bundle.
+    ///
+    /// # Arguments
+    ///
+    /// * `bundle` is the backup bundle.
+    /// * `filters` selects the locations that will be restored, all locations are restored if it is empty.
+    pub fn restore(&self, bundle: &Path, filters: LocationFilters) -> crate::Result<RestoreDetails> {
+        let mut restore_details = filesys::restore(&self.0, bundle, filters)?;
+        restore_details.stale_database = !restore_details.database && db::admin::db_details(&self.0)?.is_some();
+        Ok(restore_details)
+    }
+
+    /// Check the integrity of the location archives and compare them with the database if it
+    /// exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `quarantine` when `true` moves history files with problems out of the archives and removes
+    ///   them from the database.
+    pub fn verify(&self, quarantine: bool) -> crate::Result<VerifyDetails> {
+        let weather_dir = &self.0;
+        // quarantined histories are removed from the database so it continues to match the archives
+        let mut verify_details = filesys::admin::verify(weather_dir, quarantine, |alias, dates| {
+            db::admin::remove_histories(weather_dir, alias, dates)
+        })?;
+        verify_details.problems.extend(db::admin::verify(&self.0)?);
+        Ok(verify_details)
+    }
+
     /// Reload history for locations.
     ///
     /// # Arguments
@@ -72,6 +169,23 @@ impl WeatherAdmin {
         Ok(locations.len())
     }
 
+    /// Run a read-only `SQL` query against the weather database. The `daily` view joins the location
+    /// alias and history date with the history columns and the `summary` view has the history count
+    /// and date range of each location.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` is the query, statements that change the database are not allowed.
+    pub fn sql(&self, sql: &str) -> crate::Result<SqlResults> {
+        db::admin::sql(&self.0, sql)
+    }
+
+    /// Get the history client requests made today and what they cost. The daily budget is part of
+    /// the weather data configuration so it is not available.
+    pub fn query_usage(&self) -> crate::Result<QueryUsage> {
+        UsageLedger::new(self.0.path()).usage(UsageLedger::today())
+    }
+
     /// Load the US Cities database.
     ///
     /// # Arguments
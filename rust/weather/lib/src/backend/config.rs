@@ -2,6 +2,7 @@
 use crate::{Error, Result};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     env,
     path::{Path, PathBuf},
 };
@@ -14,16 +15,32 @@ macro_rules! err {
 pub(self) use err;
 
 const DEFAULT_FILENAME: &'static str = "weather.toml";
+/// The environment variable that names the configuration file.
+pub const ENV_CONFIG: &str = "WEATHER_CONFIG";
+pub use weather_data::ENV_TOKEN;
 
 #[derive(Debug)]
 pub struct Config {
     pub weather_data: weather_data::Properties,
     pub visual_crossing: visual_crossing::Properties,
     pub us_cities: us_cities::Properties,
+    pub http: http::Properties,
+    /// The name of the profile that was selected.
+    pub profile: Option<String>,
 }
 impl Config {
-    pub fn new(optional_path: Option<PathBuf>) -> Result<Config> {
-        config_file::load(optional_path)
+    pub fn new(optional_path: Option<PathBuf>, profile: Option<String>) -> Result<Config> {
+        config_file::load(optional_path, profile)
+    }
+    /// Use a weather data directory in place of the configured directory or weather data server.
+    ///
+    /// # Arguments
+    ///
+    /// * `dirname` is the weather data directory name.
+    ///
+    pub fn set_dirname(&mut self, dirname: &Path) {
+        self.weather_data.directory = dirname.display().to_string();
+        self.weather_data.url = None;
     }
 }
 impl TryFrom<&str> for Config {
@@ -34,39 +51,204 @@ impl TryFrom<&str> for Config {
     }
 }
 
+pub use config_file::{find as find_config_file, user_config_path};
+pub use settings::{config_settings, starter_config, validate_config};
+
 mod config_file {
     //! The configuration file manager.
     use super::*;
+    use reqwest::Url;
     use std::{fs::File, io::prelude::*};
     use toml;
 
-    /// Try to get the configuration from the file pathname. If it was not provided
-    /// try the default filename. If the default filename does not exist use defaults.
-    pub fn load(optional_path: Option<PathBuf>) -> Result<Config> {
-        match optional_path {
-            Some(path) => match (path.exists(), path.is_file()) {
-                (true, true) => Ok(Config::from(load_path(&path)?)),
+    /// Try to get the configuration from the file pathname. If it was not provided search for the configuration
+    /// file in the standard locations. If a configuration file is not found use defaults.
+    ///
+    /// # Arguments
+    ///
+    /// * `optional_path` is the configuration file pathname.
+    /// * `profile` is the name of the configuration profile to use.
+    ///
+    pub fn load(optional_path: Option<PathBuf>, profile: Option<String>) -> Result<Config> {
+        let document = match find(optional_path)? {
+            Some(path) => {
+                log::info!("Loading configuration from {}", path.display());
+                load_path(&path)?
+            }
+            None => {
+                log::info!("Did not find a configuration file, using defaults");
+                ConfigDocument::default()
+            }
+        };
+        let mut config = Config::from(document.select_profile(profile.as_deref())?);
+        config.profile = profile;
+        Ok(config)
+    }
+
+    /// Find the configuration file. The file pathname is used if provided, otherwise the file named by
+    /// [ENV_CONFIG], the default filename in the current directory, and the default filename in the
+    /// user configuration directory are searched in that order.
+    ///
+    /// # Arguments
+    ///
+    /// * `optional_path` is the configuration file pathname.
+    ///
+    pub fn find(optional_path: Option<PathBuf>) -> Result<Option<PathBuf>> {
+        let env_config = env::var_os(ENV_CONFIG).filter(|name| !name.is_empty()).map(PathBuf::from);
+        search(optional_path, env_config, user_config_path())
+    }
+
+    /// Search for the configuration file using the environment values [find] reads.
+    ///
+    /// # Arguments
+    ///
+    /// * `optional_path` is the configuration file pathname.
+    /// * `env_config` is the value of [ENV_CONFIG].
+    /// * `user_config` is the configuration file pathname in the user configuration directory.
+    ///
+    fn search(
+        optional_path: Option<PathBuf>,
+        env_config: Option<PathBuf>,
+        user_config: Option<PathBuf>,
+    ) -> Result<Option<PathBuf>> {
+        if let Some(path) = optional_path {
+            return match (path.exists(), path.is_file()) {
+                (true, true) => Ok(Some(path)),
                 (true, false) => err!("Configuration name is not a file."),
                 _ => err!("Configuration name not found."),
+            };
+        }
+        if let Some(path) = env_config {
+            return match path.is_file() {
+                true => Ok(Some(path)),
+                false => err!(format!("{} file '{}' not found.", ENV_CONFIG, path.display())),
+            };
+        }
+        let path = PathBuf::from(DEFAULT_FILENAME);
+        match (path.exists(), path.is_file()) {
+            (true, true) => return Ok(Some(path)),
+            (true, false) => return err!(format!("{} is not a file.", DEFAULT_FILENAME)),
+            _ => (),
+        }
+        Ok(user_config.filter(|path| path.is_file()))
+    }
+
+    /// Get the configuration file pathname in the user configuration directory. The directory is
+    /// `$XDG_CONFIG_HOME` or `$HOME/.config` if it is not defined.
+    pub fn user_config_path() -> Option<PathBuf> {
+        let xdg_config_home = env::var_os("XDG_CONFIG_HOME").filter(|name| !name.is_empty());
+        let home = env::var_os("HOME").filter(|name| !name.is_empty());
+        user_config_in(xdg_config_home.map(PathBuf::from), home.map(PathBuf::from))
+    }
+
+    /// Get the configuration file pathname from the user configuration directory environment values.
+    ///
+    /// # Arguments
+    ///
+    /// * `xdg_config_home` is the value of `$XDG_CONFIG_HOME`.
+    /// * `home` is the value of `$HOME`.
+    ///
+    fn user_config_in(xdg_config_home: Option<PathBuf>, home: Option<PathBuf>) -> Option<PathBuf> {
+        let config_home = match xdg_config_home {
+            Some(dirname) => dirname,
+            None => home?.join(".config"),
+        };
+        Some(config_home.join("weather").join(DEFAULT_FILENAME))
+    }
+
+    /// The structure that holds the weather configuration document.
+    #[derive(Debug, Default, Clone, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct ConfigDocument {
+        #[serde(rename = "weather-data")]
+        pub weather_data: Option<weather_data::Document>,
+        #[serde(rename = "visual-crossing")]
+        pub visual_crossing: Option<visual_crossing::Document>,
+        #[serde(rename = "us-cities")]
+        pub us_cities: Option<us_cities::Document>,
+        pub http: Option<http::Document>,
+        /// The named configuration profiles.
+        pub profiles: Option<BTreeMap<String, ProfileDocument>>,
+    }
+    impl ConfigDocument {
+        /// Get the configuration document with the settings of a profile applied. Profile settings
+        /// replace the corresponding settings in the document.
+        ///
+        /// # Arguments
+        ///
+        /// * `profile` is the name of the profile, if `None` only the profiles are removed.
+        ///
+        pub fn select_profile(mut self, profile: Option<&str>) -> Result<ConfigDocument> {
+            let mut profiles = self.profiles.take().unwrap_or_default();
+            let Some(name) = profile else {
+                return Ok(self);
+            };
+            match profiles.remove(name) {
+                Some(profile) => Ok(ConfigDocument {
+                    weather_data: merge(profile.weather_data, self.weather_data, weather_data::Document::merge),
+                    visual_crossing: merge(
+                        profile.visual_crossing,
+                        self.visual_crossing,
+                        visual_crossing::Document::merge,
+                    ),
+                    us_cities: merge(profile.us_cities, self.us_cities, us_cities::Document::merge),
+                    http: self.http,
+                    profiles: None,
+                }),
+                None if profiles.is_empty() => err!(format!("Profile '{}' was not found.", name)),
+                None => {
+                    let names: Vec<&str> = profiles.keys().map(|name| name.as_str()).collect();
+                    err!(format!("Profile '{}' was not found, available profiles are {}.", name, names.join(", ")))
+                }
             }
-            None => {
-                // try loading the default filename
-                let path = PathBuf::from(DEFAULT_FILENAME);
-                match (path.exists(), path.is_file()) {
-                    (true, true) => Ok(Config::from(load_path(&path)?)),
-                    (true, false) => err!(format!("{} is not a file.", DEFAULT_FILENAME)),
-                    _ => {
-                        log::info!("Did not find a configuration file, using defaults");
-                        Ok(Config::from(ConfigDocument::default()))
+        }
+
+        /// Get the configuration document with relative weather data directory, token file, and API key
+        /// file names resolved against the directory of the configuration file instead of the current
+        /// directory.
+        ///
+        /// # Arguments
+        ///
+        /// * `config_dir` is the directory that contains the configuration file.
+        ///
+        pub fn relative_to(mut self, config_dir: &Path) -> ConfigDocument {
+            let resolve = |pathname: Option<&mut String>| {
+                if let Some(pathname) = pathname {
+                    if pathname != crate::backend::MEMORY_DIRNAME && Path::new(pathname).is_relative() {
+                        *pathname = config_dir.join(&pathname).display().to_string();
                     }
                 }
+            };
+            let resolve_tables = |weather_data: Option<&mut weather_data::Document>,
+                                  visual_crossing: Option<&mut visual_crossing::Document>| {
+                if let Some(weather_data) = weather_data {
+                    resolve(weather_data.directory.as_mut());
+                    resolve(weather_data.token_file.as_mut());
+                }
+                resolve(visual_crossing.and_then(|table| table.api_key_file.as_mut()));
+            };
+            resolve_tables(self.weather_data.as_mut(), self.visual_crossing.as_mut());
+            for profile in self.profiles.iter_mut().flat_map(|profiles| profiles.values_mut()) {
+                resolve_tables(profile.weather_data.as_mut(), profile.visual_crossing.as_mut());
             }
+            self
         }
     }
 
-    /// The structure that holds the weather configuration document.
-    #[derive(Debug, Default, Serialize, Deserialize)]
-    pub struct ConfigDocument {
+    /// Get the directory that contains a configuration file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` is the configuration file pathname.
+    ///
+    pub fn config_dir(path: &Path) -> &Path {
+        path.parent().unwrap_or(Path::new(""))
+    }
+
+    /// The settings of a named configuration profile.
+    #[derive(Debug, Default, Clone, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct ProfileDocument {
         #[serde(rename = "weather-data")]
         pub weather_data: Option<weather_data::Document>,
         #[serde(rename = "visual-crossing")]
@@ -74,6 +256,22 @@ mod config_file {
         #[serde(rename = "us-cities")]
         pub us_cities: Option<us_cities::Document>,
     }
+
+    /// Merge a profile table with the configuration table.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` is the profile table.
+    /// * `base` is the configuration table.
+    /// * `merge_fn` merges the profile table settings into the configuration table.
+    ///
+    fn merge<T>(profile: Option<T>, base: Option<T>, merge_fn: fn(T, T) -> T) -> Option<T> {
+        match (profile, base) {
+            (Some(profile), Some(base)) => Some(merge_fn(profile, base)),
+            (profile, base) => profile.or(base),
+        }
+    }
+
     impl From<ConfigDocument> for Config {
         /// Create the configuration from the configuration document instance.
         fn from(config_document: ConfigDocument) -> Self {
@@ -81,17 +279,39 @@ mod config_file {
                 weather_data: weather_data::Properties::from(config_document.weather_data),
                 visual_crossing: visual_crossing::Properties::from(config_document.visual_crossing),
                 us_cities: us_cities::Properties::from(config_document.us_cities),
+                http: http::Properties::from(config_document.http),
+                profile: None,
+            }
+        }
+    }
+
+    impl From<&Config> for ConfigDocument {
+        /// Create the configuration document from the configuration.
+        fn from(config: &Config) -> Self {
+            ConfigDocument {
+                weather_data: Some(weather_data::Document::from(&config.weather_data)),
+                visual_crossing: Some(visual_crossing::Document::from(&config.visual_crossing)),
+                us_cities: Some(us_cities::Document::from(&config.us_cities)),
+                http: Some(http::Document::from(&config.http)),
+                profiles: None,
             }
         }
     }
 
-    /// Attempts to load the configuration from a file.
-    fn load_path(path: &Path) -> Result<ConfigDocument> {
+    /// Attempts to load the configuration from a file. Relative file names in the configuration are
+    /// resolved against the directory of the file.
+    pub fn load_path(path: &Path) -> Result<ConfigDocument> {
+        let contents = read_path(path)?;
+        Ok(parse(&contents, &path.display().to_string())?.relative_to(config_dir(path)))
+    }
+
+    /// Attempts to read the contents of a configuration file.
+    pub fn read_path(path: &Path) -> Result<String> {
         match File::open(path) {
             Ok(mut file) => {
                 let mut contents = String::new();
                 match file.read_to_string(&mut contents) {
-                    Ok(_) => load_str(&contents),
+                    Ok(_) => Ok(contents),
                     Err(err) => {
                         err!(format!("Could not read '{}' contents ({})", path.display(), err))
                     }
@@ -105,9 +325,129 @@ mod config_file {
 
     /// Attempts to load the configuration from a string.
     pub fn load_str(config: &str) -> Result<ConfigDocument> {
-        match toml::from_str::<ConfigDocument>(config) {
-            Ok(config) => Ok(config),
-            Err(err) => Err(Error::from(format!("Could not load the configuration ({}).", err))),
+        parse(config, "configuration")
+    }
+
+    /// Parse the configuration text. Unknown tables or keys and settings that are not valid URLs are
+    /// reported with the line and column they were found.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` is the configuration text.
+    /// * `source` is the name used to report where an error was found.
+    ///
+    pub fn parse(contents: &str, source: &str) -> Result<ConfigDocument> {
+        let locator = Locator::new(source, contents);
+        let document = match toml::from_str::<ConfigDocument>(contents) {
+            Ok(document) => document,
+            Err(error) => {
+                let location = locator.position(error.span().map(|span| span.start));
+                return err!(format!("{}: {}", location, error.message().trim()));
+            }
+        };
+        let problems = url_problems(&document, &locator);
+        match problems.is_empty() {
+            true => Ok(document),
+            false => err!(problems.join("\n")),
+        }
+    }
+
+    /// Check the settings that must be URLs.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` is the configuration document.
+    /// * `locator` finds where settings are in the configuration text.
+    ///
+    fn url_problems(document: &ConfigDocument, locator: &Locator) -> Vec<String> {
+        let mut urls: Vec<(Vec<&str>, &str)> = vec![];
+        if let Some(url) = document.weather_data.as_ref().and_then(|table| table.url.as_ref()) {
+            urls.push((vec!["weather-data", "url"], url));
+        }
+        if let Some(endpoint) = document.visual_crossing.as_ref().and_then(|table| table.endpoint.as_ref()) {
+            urls.push((vec!["visual-crossing", "endpoint"], endpoint));
+        }
+        if let Some(proxy) = document.http.as_ref().and_then(|table| table.proxy.as_ref()) {
+            urls.push((vec!["http", "proxy"], proxy));
+        }
+        for (name, profile) in document.profiles.iter().flatten() {
+            if let Some(url) = profile.weather_data.as_ref().and_then(|table| table.url.as_ref()) {
+                urls.push((vec!["profiles", name, "weather-data", "url"], url));
+            }
+            if let Some(endpoint) = profile.visual_crossing.as_ref().and_then(|table| table.endpoint.as_ref()) {
+                urls.push((vec!["profiles", name, "visual-crossing", "endpoint"], endpoint));
+            }
+        }
+        urls.into_iter()
+            .filter_map(|(keys, url)| match Url::parse(url) {
+                Ok(_) => None,
+                Err(error) => {
+                    Some(format!("{}: {} '{}' is not a valid URL ({}).", locator.locate(&keys), keys.join("."), url, error))
+                }
+            })
+            .collect()
+    }
+
+    /// Finds where settings are in the configuration text.
+    pub struct Locator<'c> {
+        /// The name of the configuration source.
+        source: &'c str,
+        /// The configuration text.
+        contents: &'c str,
+        /// The configuration text that retains where settings are found.
+        document: Option<toml_edit::ImDocument<&'c str>>,
+    }
+    impl<'c> Locator<'c> {
+        /// Create the configuration setting locator.
+        ///
+        /// # Arguments
+        ///
+        /// * `source` is the name of the configuration source.
+        /// * `contents` is the configuration text.
+        ///
+        pub fn new(source: &'c str, contents: &'c str) -> Self {
+            Self { source, contents, document: toml_edit::ImDocument::parse(contents).ok() }
+        }
+        /// Get where a setting is found as `source:line:column`. If the setting is not found only
+        /// the source name is returned.
+        ///
+        /// # Arguments
+        ///
+        /// * `keys` are the table names and key of the setting.
+        ///
+        pub fn locate(&self, keys: &[&str]) -> String {
+            self.position(self.find(keys))
+        }
+        /// Get the offset of a setting in the configuration text.
+        ///
+        /// # Arguments
+        ///
+        /// * `keys` are the table names and key of the setting.
+        ///
+        pub fn find(&self, keys: &[&str]) -> Option<usize> {
+            let (key, tables) = keys.split_last()?;
+            let mut table = self.document.as_ref()?.as_table();
+            for name in tables {
+                table = table.get(name)?.as_table()?;
+            }
+            table.key(key)?.span().map(|span| span.start)
+        }
+        /// Get the `source:line:column` of an offset in the configuration text.
+        ///
+        /// # Arguments
+        ///
+        /// * `offset` is the position in the configuration text.
+        ///
+        pub fn position(&self, offset: Option<usize>) -> String {
+            match offset.filter(|offset| *offset <= self.contents.len()) {
+                Some(offset) => {
+                    let preceding = &self.contents[..offset];
+                    let line = preceding.matches('\n').count() + 1;
+                    let column = preceding.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+                    format!("{}:{}:{}", self.source, line, column)
+                }
+                None => self.source.to_string(),
+            }
         }
     }
 
@@ -131,19 +471,56 @@ mod config_file {
             let config = r#"
                 [weather-data]
                 directory = "directory/name"
+                lock-timeout = 30
+                url = "http://localhost:8080"
+                token-file = "weather.token"
 
                 [visual-crossing]
                 endpoint = "http://end/point"
                 api-key = "api-key"
+                api-key-file = "api.key"
+                api-key-command = "pass weather/api-key"
+                cache = false
+                replay = true
+                concurrency = 8
+                daily-budget = 1000
+                elements = ["datetime", "tempmax"]
 
                 [us-cities]
                 filename = "filename.csv"
+
+                [http]
+                connect-timeout = 5
+                timeout = 30
+                proxy = "http://proxy:3128"
+                user-agent = "user-agent"
+                retries = 2
+                backoff = 100
+                max-backoff = 1000
                 "#;
             let testcase = load_str(config).unwrap();
             assert_eq!(as_ref!(testcase.weather_data).directory, some!("directory/name"));
+            assert_eq!(as_ref!(testcase.weather_data).lock_timeout, Some(30));
+            assert_eq!(as_ref!(testcase.weather_data).url, some!("http://localhost:8080"));
+            assert_eq!(as_ref!(testcase.weather_data).token_file, some!("weather.token"));
             assert_eq!(as_ref!(testcase.visual_crossing).endpoint, some!("http://end/point"));
             assert_eq!(as_ref!(testcase.visual_crossing).api_key, some!("api-key"));
+            assert_eq!(as_ref!(testcase.visual_crossing).api_key_file, some!("api.key"));
+            assert_eq!(as_ref!(testcase.visual_crossing).api_key_command, some!("pass weather/api-key"));
+            assert_eq!(as_ref!(testcase.visual_crossing).cache, Some(false));
+            assert_eq!(as_ref!(testcase.visual_crossing).replay, Some(true));
+            assert_eq!(as_ref!(testcase.visual_crossing).concurrency, Some(8));
+            assert_eq!(as_ref!(testcase.visual_crossing).daily_budget, Some(1000));
+            let elements = vec!["datetime".to_string(), "tempmax".to_string()];
+            assert_eq!(as_ref!(testcase.visual_crossing).elements, Some(elements));
             assert_eq!(as_ref!(testcase.us_cities).filename, some!("filename.csv"));
+            assert_eq!(as_ref!(testcase.http).connect_timeout, Some(5));
+            assert_eq!(as_ref!(testcase.http).timeout, Some(30));
+            assert_eq!(as_ref!(testcase.http).proxy, some!("http://proxy:3128"));
+            assert_eq!(as_ref!(testcase.http).user_agent, some!("user-agent"));
+            assert_eq!(as_ref!(testcase.http).retries, Some(2));
+            assert_eq!(as_ref!(testcase.http).backoff, Some(100));
+            assert_eq!(as_ref!(testcase.http).max_backoff, Some(1000));
             let config = r#"
                 [weather-data]
                 directory = "directory/name"
@@ -171,6 +548,75 @@ mod config_file {
             assert!(testcase.us_cities.is_some());
         }
 
+        #[test]
+        fn find_file() {
+            use crate::backend::testlib::TestFixture;
+            let fixture = TestFixture::create();
+            let user_config = PathBuf::from(&fixture).join("weather").join(DEFAULT_FILENAME);
+            std::fs::create_dir(user_config.parent().unwrap()).unwrap();
+            std::fs::write(&user_config, "").unwrap();
+            let env_config = PathBuf::from(&fixture).join("env.toml");
+            std::fs::write(&env_config, "").unwrap();
+            assert_eq!(user_config_in(Some(PathBuf::from(&fixture)), None), Some(user_config.clone()));
+            let home_config = PathBuf::from("home").join(".config").join("weather").join(DEFAULT_FILENAME);
+            assert_eq!(user_config_in(None, Some(PathBuf::from("home"))), Some(home_config));
+            assert!(user_config_in(None, None).is_none());
+            assert_eq!(search(None, None, Some(user_config.clone())).unwrap(), Some(user_config.clone()));
+            assert_eq!(search(None, Some(env_config.clone()), Some(user_config.clone())).unwrap(), Some(env_config));
+            let path = PathBuf::from(&fixture).join("missing.toml");
+            assert!(search(Some(path.clone()), None, None).is_err());
+            assert!(search(None, Some(path.clone()), Some(user_config.clone())).is_err());
+            assert_eq!(search(None, None, Some(path.clone())).unwrap(), None);
+            assert_eq!(search(Some(user_config.clone()), Some(path), None).unwrap(), Some(user_config));
+        }
+
+        #[test]
+        fn profiles() {
+            let config = r#"
+                [weather-data]
+                directory = "weather_data"
+
+                [visual-crossing]
+                api-key = "api-key"
+                concurrency = 2
+
+                [profiles.demo.weather-data]
+                directory = "demo_data"
+
+                [profiles.demo.visual-crossing]
+                api-key-file = "demo.key"
+                replay = true
+
+                [profiles.sample.us-cities]
+                filename = "sample.csv"
+                "#;
+            let testcase = Config::from(load_str(config).unwrap().select_profile(Some("demo")).unwrap());
+            assert_eq!(testcase.weather_data.directory, "demo_data");
+            assert_eq!(testcase.visual_crossing.api_key, visual_crossing::ApiKey::File(PathBuf::from("demo.key")));
+            assert!(testcase.visual_crossing.replay);
+            assert_eq!(testcase.visual_crossing.concurrency, 2);
+            let testcase = Config::from(load_str(config).unwrap().select_profile(None).unwrap());
+            assert_eq!(testcase.weather_data.directory, "weather_data");
+            assert_eq!(testcase.visual_crossing.api_key, visual_crossing::ApiKey::Value("api-key".to_string()));
+            assert!(!testcase.visual_crossing.replay);
+            let testcase = load_str(config).unwrap().select_profile(Some("missing"));
+            assert!(testcase.unwrap_err().to_string().contains("demo, sample"));
+        }
+
+        #[test]
+        fn strict() {
+            let testcase = parse("[visual-crossing]\napikey = \"key\"\n", "test.toml").unwrap_err().to_string();
+            assert!(testcase.contains("test.toml:2:1: unknown field `apikey`"), "{}", testcase);
+            let testcase = parse("[visual-crossing]\n\n[weather]\n", "test.toml").unwrap_err().to_string();
+            assert!(testcase.contains("test.toml:3:"), "{}", testcase);
+            let testcase = parse("[http]\nproxy = \"proxy\"\n", "test.toml").unwrap_err().to_string();
+            assert!(testcase.contains("test.toml:2:1: http.proxy 'proxy' is not a valid URL"), "{}", testcase);
+            let config = "[profiles.demo.visual-crossing]\n  endpoint = \"end-point\"\n";
+            let testcase = parse(config, "test.toml").unwrap_err().to_string();
+            assert!(testcase.contains("test.toml:2:3: profiles.demo.visual-crossing.endpoint"), "{}", testcase);
+            assert!(parse("[http]\nproxy = \"http://proxy:3128\"\n", "test.toml").is_ok());
+        }
+
         #[test]
         fn env() {
             // isolate env setting here to avoid threaded test failures
@@ -180,9 +626,21 @@ mod config_file {
             let testcase = Config::from(ConfigDocument::default());
             log::debug!("{:#?}", testcase);
             assert_eq!(testcase.weather_data.directory, weather_data::DEFAULT_DIRNAME);
+            assert_eq!(testcase.weather_data.lock_timeout, weather_data::DEFAULT_LOCK_TIMEOUT);
             assert_eq!(testcase.visual_crossing.endpoint, visual_crossing::DEFAULT_URI);
-            assert_eq!(testcase.visual_crossing.api_key, visual_crossing::DEFAULT_KEY);
+            assert_eq!(
+                testcase.visual_crossing.api_key,
+                visual_crossing::ApiKey::Value(visual_crossing::DEFAULT_KEY.to_string())
+            );
+            assert!(testcase.visual_crossing.cache);
+            assert!(!testcase.visual_crossing.replay);
+            assert_eq!(testcase.visual_crossing.concurrency, visual_crossing::DEFAULT_CONCURRENCY);
+            assert!(testcase.visual_crossing.daily_budget.is_none());
+            assert_eq!(testcase.visual_crossing.elements, visual_crossing::DEFAULT_ELEMENTS);
             assert_eq!(testcase.us_cities.filename, us_cities::DEFAULT_FILENAME);
+            assert_eq!(testcase.http.timeout, http::DEFAULT_TIMEOUT);
+            assert_eq!(testcase.http.retries, http::DEFAULT_RETRIES);
+            assert!(testcase.http.proxy.is_none());
             //
             env::remove_var(weather_data::ENV_DIRNAME);
             env::remove_var(visual_crossing::ENV_KEY);
@@ -196,7 +654,7 @@ mod config_file {
             let testcase = Config::from(ConfigDocument::default());
             assert_eq!(testcase.weather_data.directory, dirname);
             assert_eq!(testcase.visual_crossing.endpoint, visual_crossing::DEFAULT_URI);
-            assert_eq!(testcase.visual_crossing.api_key, key);
+            assert_eq!(testcase.visual_crossing.api_key, visual_crossing::ApiKey::Value(key.to_string()));
             assert_eq!(testcase.us_cities.filename, filename);
             env::remove_var(weather_data::ENV_DIRNAME);
             env::remove_var(visual_crossing::ENV_KEY);
@@ -205,16 +663,493 @@ mod config_file {
     }
 }
 
+mod settings {
+    //! Reports the effective configuration and creates or validates configuration files.
+    use super::{config_file::*, *};
+    use crate::config::{ConfigSetting, ConfigSettings, ConfigSource};
+
+    /// The value shown in place of the API key.
+    const REDACTED: &str = "********";
+
+    /// Get the effective configuration settings and where each of them came from.
+    ///
+    /// # Arguments
+    ///
+    /// * `optional_path` is the configuration file pathname.
+    /// * `profile` is the name of the configuration profile to use.
+    ///
+    pub fn config_settings(optional_path: Option<PathBuf>, profile: Option<String>) -> Result<ConfigSettings> {
+        let path = find(optional_path)?;
+        let document = match &path {
+            Some(path) => load_path(path)?,
+            None => ConfigDocument::default(),
+        };
+        Ok(ConfigSettings { settings: settings(document, profile.as_deref())?, path, profile })
+    }
+
+    /// Get the configuration settings for a configuration document.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` is the configuration file document.
+    /// * `profile` is the name of the configuration profile to use.
+    ///
+    fn settings(mut document: ConfigDocument, profile: Option<&str>) -> Result<Vec<ConfigSetting>> {
+        let profile_values = match (profile, &document.profiles) {
+            (Some(name), Some(profiles)) => match profiles.get(name) {
+                Some(profile) => flatten(&ConfigDocument {
+                    weather_data: profile.weather_data.clone(),
+                    visual_crossing: profile.visual_crossing.clone(),
+                    us_cities: profile.us_cities.clone(),
+                    ..Default::default()
+                })?,
+                None => vec![],
+            },
+            _ => vec![],
+        };
+        let config = Config::from(document.clone().select_profile(profile)?);
+        document.profiles = None;
+        let file_values = flatten(&document)?;
+        let contains = |values: &Vec<(String, String)>, name: &str| values.iter().any(|(key, _)| key == name);
+        let settings = flatten(&ConfigDocument::from(&config))?
+            .into_iter()
+            .map(|(name, value)| {
+                let source = if contains(&profile_values, &name) {
+                    ConfigSource::Profile(profile.unwrap_or_default().to_string())
+                } else if contains(&file_values, &name) {
+                    ConfigSource::File
+                } else {
+                    match env_name(&name).filter(|env_name| env::var_os(env_name).is_some()) {
+                        Some(env_name) => ConfigSource::Environment(env_name.to_string()),
+                        None => ConfigSource::Default,
+                    }
+                };
+                let placeholder = format!("\"{}\"", visual_crossing::DEFAULT_KEY);
+                let value = match name == "visual-crossing.api-key" && value != placeholder {
+                    true => REDACTED.to_string(),
+                    false => value,
+                };
+                ConfigSetting { name, value, source }
+            })
+            .collect();
+        Ok(settings)
+    }
+
+    /// Get the environment variable that can supply a setting.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` is the setting name.
+    ///
+    fn env_name(name: &str) -> Option<&'static str> {
+        match name {
+            "weather-data.directory" => Some(weather_data::ENV_DIRNAME),
+            "visual-crossing.api-key" => Some(visual_crossing::ENV_KEY),
+            "us-cities.filename" => Some(us_cities::ENV_FILENAME),
+            _ => None,
+        }
+    }
+
+    /// Get the document settings as `table.key` names and their `TOML` values.
+    fn flatten(document: &ConfigDocument) -> Result<Vec<(String, String)>> {
+        let table = match toml::Table::try_from(document) {
+            Ok(table) => table,
+            Err(error) => return err!(format!("Could not serialize the configuration ({}).", error)),
+        };
+        let mut values = vec![];
+        for (table_name, value) in table {
+            if let toml::Value::Table(table) = value {
+                for (key, value) in table {
+                    values.push((format!("{}.{}", table_name, key), value.to_string()));
+                }
+            }
+        }
+        Ok(values)
+    }
+
+    /// Get the contents of a starter configuration file. The settings are commented out and show their
+    /// default values.
+    pub fn starter_config() -> String {
+        let elements: Vec<String> =
+            visual_crossing::DEFAULT_ELEMENTS.iter().map(|element| format!("\"{}\"", element)).collect();
+        format!(
+            r#"# The weather data configuration file.
+
+[weather-data]
+# The weather data directory, {env_dirname} overrides the default. Use ":memory:" to keep
+# weather data in memory.
+# directory = "{dirname}"
+# The seconds to wait for another program using the weather data directory.
+# lock-timeout = {lock_timeout}
+# The URL of a weather data server, it is used instead of the weather data directory.
+# url = "http://localhost:8080"
+# The file containing the server token that authorizes writes, {env_token} overrides it.
+# token-file = "/path/to/weather-serve.token"
+
+[visual-crossing]
+# The timeline API end-point.
+# endpoint = "{endpoint}"
+# The API key, {env_key} overrides the default. Prefer a key file that is not world-readable
+# or a command that writes the key to standard output. A relative key file is found in the
+# directory of this file.
+# api-key = "{key}"
+# api-key-file = "/path/to/visual-crossing.key"
+# api-key-command = "pass show visual-crossing"
+# Save the raw response of history requests.
+# cache = true
+# Get weather history from the saved responses.
+# replay = false
+# The maximum number of active history requests.
+# concurrency = {concurrency}
+# The maximum number of records charged in a day.
+# daily-budget = 1000
+# The timeline elements to request.
+# elements = [{elements}]
+
+[us-cities]
+# The US cities data filename, {env_filename} overrides the default.
+# filename = "{filename}"
+
+[http]
+# The seconds to wait for a connection.
+# connect-timeout = {connect_timeout}
+# The seconds to wait for a request to complete.
+# timeout = {timeout}
+# The proxy URL.
+# proxy = "http://proxy:3128"
+# The number of times a request will be retried.
+# retries = {retries}
+# The milliseconds to wait before the first retry.
+# backoff = {backoff}
+# The maximum milliseconds to wait before a retry. A Retry-After from the server is honored
+# up to the request timeout.
+# max-backoff = {max_backoff}
+
+# Named profiles have their own weather-data, visual-crossing, and us-cities settings that
+# replace the settings above. Select a profile with the --profile command line option.
+# [profiles.demo.weather-data]
+# directory = "demo_data"
+# [profiles.demo.visual-crossing]
+# replay = true
+"#,
+            env_dirname = weather_data::ENV_DIRNAME,
+            dirname = weather_data::DEFAULT_DIRNAME,
+            lock_timeout = weather_data::DEFAULT_LOCK_TIMEOUT,
+            env_token = weather_data::ENV_TOKEN,
+            endpoint = visual_crossing::DEFAULT_URI,
+            env_key = visual_crossing::ENV_KEY,
+            key = visual_crossing::DEFAULT_KEY,
+            concurrency = visual_crossing::DEFAULT_CONCURRENCY,
+            elements = elements.join(", "),
+            env_filename = us_cities::ENV_FILENAME,
+            filename = us_cities::DEFAULT_FILENAME,
+            connect_timeout = http::DEFAULT_CONNECT_TIMEOUT,
+            timeout = http::DEFAULT_TIMEOUT,
+            retries = http::DEFAULT_RETRIES,
+            backoff = http::DEFAULT_BACKOFF,
+            max_backoff = http::DEFAULT_MAX_BACKOFF,
+        )
+    }
+
+    /// Validate a configuration file. An error is returned if the file cannot be loaded otherwise
+    /// the problems found with the configuration and each of its profiles are returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` is the configuration file pathname.
+    /// * `dirname` is the weather data directory used in place of the configured weather data.
+    ///
+    pub fn validate_config(path: &Path, dirname: Option<&Path>) -> Result<Vec<String>> {
+        let contents = read_path(path)?;
+        let source = path.display().to_string();
+        validate(&contents, &source, config_dir(path), dirname)
+    }
+
+    /// Validate the configuration text.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` is the configuration text.
+    /// * `source` is the name used to report where problems were found.
+    /// * `config_dir` is the directory relative file names are resolved against.
+    /// * `dirname` is the weather data directory used in place of the configured weather data.
+    ///
+    fn validate(contents: &str, source: &str, config_dir: &Path, dirname: Option<&Path>) -> Result<Vec<String>> {
+        let unresolved = parse(contents, source)?;
+        let document = unresolved.clone().relative_to(config_dir);
+        // relative names only resolve differently when the configuration is outside the current directory
+        let current_dir = env::current_dir().ok().and_then(|dirname| dirname.canonicalize().ok());
+        let same_dir = config_dir.as_os_str().is_empty() || config_dir.canonicalize().ok() == current_dir;
+        let unresolved = if same_dir { None } else { Some(unresolved) };
+        let locator = Locator::new(source, contents);
+        let config = |document: &ConfigDocument, profile: Option<&str>| -> Result<Config> {
+            let mut config = Config::from(document.clone().select_profile(profile)?);
+            if let Some(dirname) = dirname {
+                config.set_dirname(dirname);
+            }
+            Ok(config)
+        };
+        let mut problems = vec![];
+        let names: Vec<String> = document.profiles.iter().flat_map(|profiles| profiles.keys().cloned()).collect();
+        for profile in std::iter::once(None).chain(names.iter().map(|name| Some(name.as_str()))) {
+            let resolved = config(&document, profile)?;
+            problems.extend(self::problems(&resolved, &locator, profile));
+            if let Some(unresolved) = &unresolved {
+                problems.extend(self::moved(&resolved, &config(unresolved, profile)?, &locator, profile));
+            }
+        }
+        Ok(problems)
+    }
+
+    /// Get the problems found with a configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` is the configuration that will be checked.
+    /// * `locator` finds where settings are in the configuration text.
+    /// * `profile` is the name of the profile used to create the configuration.
+    ///
+    fn problems(config: &Config, locator: &Locator, profile: Option<&str>) -> Vec<String> {
+        let locate = |table: &str, keys: &[&str]| self::locate(locator, profile, table, keys);
+        let mut problems = vec![];
+        let directory = &config.weather_data.directory;
+        if config.weather_data.is_local() && !Path::new(directory).is_dir() {
+            let location = locate("weather-data", &["directory"]);
+            problems.push(format!("{}: weather-data.directory '{}' does not exist.", location, directory));
+        }
+        if let Some(token_file) = &config.weather_data.token_file {
+            if !Path::new(token_file).is_file() {
+                let location = locate("weather-data", &["token-file"]);
+                problems.push(format!("{}: weather-data.token-file '{}' does not exist.", location, token_file));
+            }
+        }
+        if !config.visual_crossing.replay {
+            if let Err(error) = config.visual_crossing.api_key.resolve() {
+                let location = locate("visual-crossing", &["api-key-file", "api-key-command", "api-key"]);
+                problems.push(format!("{}: {}", location, error));
+            }
+        }
+        if config.visual_crossing.elements.iter().all(|element| element.trim().is_empty()) {
+            let location = locate("visual-crossing", &["elements"]);
+            problems.push(format!("{}: visual-crossing.elements is empty.", location));
+        }
+        problems
+    }
+
+    /// Get the warnings for relative names that were found in the current directory before they were
+    /// resolved against the directory of the configuration file.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` is the configuration with names resolved against the configuration file directory.
+    /// * `unresolved` is the configuration with names resolved against the current directory.
+    /// * `locator` finds where settings are in the configuration text.
+    /// * `profile` is the name of the profile used to create the configuration.
+    ///
+    fn moved(config: &Config, unresolved: &Config, locator: &Locator, profile: Option<&str>) -> Vec<String> {
+        let mut warnings = vec![];
+        let mut warn = |table: &str, key: &str, unresolved: String, resolved: String| {
+            if unresolved != resolved {
+                warnings.push(format!(
+                    "{}: {}.{} '{}' is found in the configuration file directory as '{}', not the current directory.",
+                    locate(locator, profile, table, &[key]),
+                    table,
+                    key,
+                    unresolved,
+                    resolved
+                ));
+            }
+        };
+        warn(
+            "weather-data",
+            "directory",
+            unresolved.weather_data.directory.clone(),
+            config.weather_data.directory.clone(),
+        );
+        let token_files = (&unresolved.weather_data.token_file, &config.weather_data.token_file);
+        if let (Some(unresolved), Some(resolved)) = token_files {
+            warn("weather-data", "token-file", unresolved.clone(), resolved.clone());
+        }
+        if let (visual_crossing::ApiKey::File(unresolved), visual_crossing::ApiKey::File(resolved)) =
+            (&unresolved.visual_crossing.api_key, &config.visual_crossing.api_key)
+        {
+            let (unresolved, resolved) = (unresolved.display().to_string(), resolved.display().to_string());
+            warn("visual-crossing", "api-key-file", unresolved, resolved);
+        }
+        warnings
+    }
+
+    /// Get the position of a setting in the configuration text, preferring profile settings over the
+    /// top level settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `locator` finds where settings are in the configuration text.
+    /// * `profile` is the name of the profile used to create the configuration.
+    /// * `table` is the configuration table name.
+    /// * `keys` are the setting names that are searched for in order.
+    ///
+    fn locate(locator: &Locator, profile: Option<&str>, table: &str, keys: &[&str]) -> String {
+        let profile_keys = |name| keys.iter().map(move |key| vec!["profiles", name, table, key]);
+        let table_keys = keys.iter().map(|key| vec![table, key]);
+        let offset = profile
+            .into_iter()
+            .flat_map(profile_keys)
+            .chain(table_keys)
+            .find_map(|keys| locator.find(&keys));
+        match profile {
+            Some(name) => format!("{}: [{}]", locator.position(offset), name),
+            None => locator.position(offset),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn sources() {
+            let document = load_str(
+                r#"
+                [visual-crossing]
+                api-key = "secret"
+                concurrency = 2
+
+                [profiles.demo.visual-crossing]
+                replay = true
+                "#,
+            )
+            .unwrap();
+            let testcase = settings(document.clone(), Some("demo")).unwrap();
+            let setting = |name: &str| testcase.iter().find(|setting| setting.name == name).unwrap();
+            assert_eq!(setting("visual-crossing.replay").value, "true");
+            assert_eq!(setting("visual-crossing.replay").source, ConfigSource::Profile("demo".to_string()));
+            assert_eq!(setting("visual-crossing.concurrency").source, ConfigSource::File);
+            let testcase = settings(document, None).unwrap();
+            let setting = |name: &str| testcase.iter().find(|setting| setting.name == name).unwrap();
+            assert_eq!(setting("visual-crossing.api-key").value, REDACTED);
+            assert_eq!(setting("visual-crossing.api-key").source, ConfigSource::File);
+            assert_eq!(setting("visual-crossing.concurrency").value, "2");
+            assert_eq!(setting("visual-crossing.concurrency").source, ConfigSource::File);
+            assert_eq!(setting("http.retries").value, http::DEFAULT_RETRIES.to_string());
+            assert_eq!(setting("http.retries").source, ConfigSource::Default);
+        }
+
+        #[test]
+        fn starter() {
+            let testcase = Config::try_from(starter_config().as_str()).unwrap();
+            assert_eq!(testcase.visual_crossing.endpoint, visual_crossing::DEFAULT_URI);
+            assert_eq!(testcase.http.retries, http::DEFAULT_RETRIES);
+        }
+
+        #[test]
+        fn validate() {
+            let fixture = crate::backend::testlib::TestFixture::create();
+            let contents = format!(
+                r#"[weather-data]
+directory = "{}"
+
+[visual-crossing]
+api-key = "API_KEY"
+elements = []
+
+[profiles.demo.weather-data]
+directory = "missing"
+
+[profiles.demo.visual-crossing]
+api-key = "secret"
+"#,
+                fixture
+            );
+            let testcase = super::validate(&contents, "test.toml", Path::new(""), None).unwrap();
+            assert_eq!(testcase.len(), 4);
+            assert!(testcase[0].starts_with("test.toml:5:1: "));
+            assert!(testcase[1].starts_with("test.toml:6:1: "));
+            assert!(testcase[2].starts_with("test.toml:9:1: [demo]"));
+            assert!(testcase[3].starts_with("test.toml:6:1: [demo]"));
+            let contents = format!(
+                r#"[weather-data]
+directory = "{}"
+
+[visual-crossing]
+api-key = "secret"
+"#,
+                fixture
+            );
+            assert!(super::validate(&contents, "test.toml", Path::new(""), None).unwrap().is_empty());
+            let contents = r#"[weather-data]
+directory = "missing"
+url = "http://localhost:8080"
+token-file = "missing.token"
+
+[visual-crossing]
+api-key = "secret"
+"#;
+            let testcase = super::validate(contents, "test.toml", Path::new(""), None).unwrap();
+            assert_eq!(testcase.len(), 1);
+            assert!(testcase[0].starts_with("test.toml:4:1: "), "{}", testcase[0]);
+            let contents = "[weather-data]\nurl = \"localhost\"\n";
+            let testcase = super::validate(contents, "test.toml", Path::new(""), None).unwrap_err();
+            assert!(testcase.to_string().contains("weather-data.url 'localhost' is not a valid URL"));
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn relative_paths() {
+            use std::os::unix::fs::PermissionsExt;
+            let fixture = crate::backend::testlib::TestFixture::create();
+            let weather_dir = PathBuf::from(&fixture).join("weather_data");
+            std::fs::create_dir(&weather_dir).unwrap();
+            let key_path = PathBuf::from(&fixture).join("api.key");
+            std::fs::write(&key_path, "secret\n").unwrap();
+            std::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o600)).unwrap();
+            let config_path = PathBuf::from(&fixture).join("weather.toml");
+            let contents = r#"[weather-data]
+directory = "weather_data"
+
+[visual-crossing]
+api-key-file = "api.key"
+"#;
+            std::fs::write(&config_path, contents).unwrap();
+            // the files are found next to the configuration file not in the current directory
+            let testcase = validate_config(&config_path, None).unwrap();
+            assert_eq!(testcase.len(), 2);
+            assert!(testcase[0].contains("weather-data.directory 'weather_data' is found in"), "{}", testcase[0]);
+            assert!(testcase[1].contains("visual-crossing.api-key-file 'api.key' is found in"), "{}", testcase[1]);
+            let testcase = Config::new(Some(config_path.clone()), None).unwrap();
+            assert_eq!(testcase.weather_data.directory, weather_dir.display().to_string());
+            assert_eq!(testcase.visual_crossing.api_key, visual_crossing::ApiKey::File(key_path));
+            assert_eq!(testcase.visual_crossing.api_key.resolve().unwrap(), "secret");
+            // the weather data directory override is validated instead of the configured directory
+            let testcase = validate_config(&config_path, Some(&weather_dir)).unwrap();
+            assert_eq!(testcase.len(), 1);
+            assert!(testcase[0].contains("api-key-file"), "{}", testcase[0]);
+            let testcase = validate_config(&config_path, Some(&weather_dir.join("missing"))).unwrap();
+            assert_eq!(testcase.len(), 2);
+            assert!(testcase[0].contains("missing' does not exist"), "{}", testcase[0]);
+        }
+    }
+}
+
 mod weather_data {
     //! The weather data configuration table.
     use super::*;
 
     pub const ENV_DIRNAME: &'static str = "WEATHER_DATA";
     pub const DEFAULT_DIRNAME: &'static str = "weather_data";
+    pub const DEFAULT_LOCK_TIMEOUT: u64 = 10;
+    /// The environment variable that can hold the weather data server token.
+    pub const ENV_TOKEN: &'static str = "WEATHER_SERVE_TOKEN";
 
     #[derive(Debug)]
     pub struct Properties {
         pub directory: String,
+        /// The seconds to wait for the weather data directory lock.
+        pub lock_timeout: u64,
+        /// The weather data server URL, when it is set the directory is not used.
+        pub url: Option<String>,
+        /// The file containing the weather data server token.
+        pub token_file: Option<String>,
     }
     impl From<Option<Document>> for Properties {
         /// Convert the document into the configuration table.
@@ -222,17 +1157,75 @@ mod weather_data {
             match value {
                 Some(dict) => {
                     let directory = dict.directory.unwrap_or_else(default_dirname);
-                    Properties { directory }
+                    let lock_timeout = dict.lock_timeout.unwrap_or(DEFAULT_LOCK_TIMEOUT);
+                    Properties { directory, lock_timeout, url: dict.url, token_file: dict.token_file }
                 }
-                None => Properties { directory: default_dirname() },
+                None => Properties {
+                    directory: default_dirname(),
+                    lock_timeout: DEFAULT_LOCK_TIMEOUT,
+                    url: None,
+                    token_file: None,
+                },
+            }
+        }
+    }
+    impl Properties {
+        /// Get the weather data server token from the token file or [ENV_TOKEN]. `None` is returned
+        /// if there is no token.
+        pub fn token(&self) -> Result<Option<String>> {
+            let token = match &self.token_file {
+                Some(path) => match std::fs::read_to_string(path) {
+                    Ok(token) => token,
+                    Err(error) => return err!(format!("Could not read token file '{}' ({}).", path, error)),
+                },
+                None => env::var(ENV_TOKEN).unwrap_or_default(),
+            };
+            match token.trim() {
+                "" => Ok(None),
+                token => Ok(Some(token.to_string())),
             }
         }
+        /// Test if weather data is kept in the directory instead of memory or a weather data server.
+        pub fn is_local(&self) -> bool {
+            self.url.is_none() && self.directory != crate::backend::MEMORY_DIRNAME
+        }
     }
 
     /// The configuration that can be serialized and deserialized.
-    #[derive(Debug, Default, Serialize, Deserialize)]
+    #[derive(Debug, Default, Clone, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
     pub struct Document {
         pub directory: Option<String>,
+        /// The seconds to wait for the weather data directory lock.
+        #[serde(rename = "lock-timeout")]
+        pub lock_timeout: Option<u64>,
+        /// The weather data server URL.
+        pub url: Option<String>,
+        /// The file containing the weather data server token.
+        #[serde(rename = "token-file")]
+        pub token_file: Option<String>,
+    }
+    impl Document {
+        /// Merge the document settings into another document.
+        pub fn merge(self, base: Document) -> Document {
+            Document {
+                directory: self.directory.or(base.directory),
+                lock_timeout: self.lock_timeout.or(base.lock_timeout),
+                url: self.url.or(base.url),
+                token_file: self.token_file.or(base.token_file),
+            }
+        }
+    }
+    impl From<&Properties> for Document {
+        /// Convert the configuration table into the document.
+        fn from(properties: &Properties) -> Self {
+            Document {
+                directory: Some(properties.directory.clone()),
+                lock_timeout: Some(properties.lock_timeout),
+                url: properties.url.clone(),
+                token_file: properties.token_file.clone(),
+            }
+        }
     }
 
     /// Gets the default API key from the process environment if [ENV_DIRNAME] is defined.
@@ -249,11 +1242,46 @@ mod visual_crossing {
     pub const DEFAULT_KEY: &'static str = "API_KEY";
     pub const DEFAULT_URI: &'static str =
         "https://weather.visualcrossing.com/VisualCrossingWebServices/rest/services/timeline";
+    pub const DEFAULT_CONCURRENCY: usize = 4;
+    /// The timeline elements weather history is built from.
+    pub const DEFAULT_ELEMENTS: [&str; 20] = [
+        "datetime",
+        "tempmax",
+        "tempmin",
+        "temp",
+        "dew",
+        "humidity",
+        "precip",
+        "precipprob",
+        "preciptype",
+        "windgust",
+        "windspeed",
+        "winddir",
+        "pressure",
+        "cloudcover",
+        "visibility",
+        "uvindex",
+        "sunriseEpoch",
+        "sunsetEpoch",
+        "moonphase",
+        "description",
+    ];
 
     #[derive(Debug)]
     pub struct Properties {
         pub endpoint: String,
-        pub api_key: String,
+        /// Where the API key will be read from.
+        pub api_key: ApiKey,
+        /// Save the raw response of history requests in the weather data directory.
+        pub cache: bool,
+        /// Get history from the saved responses instead of the API end-point.
+        pub replay: bool,
+        /// The maximum number of history requests that can be active at the same time.
+        pub concurrency: usize,
+        /// The maximum number of records that can be charged in a day.
+        pub daily_budget: Option<u64>,
+        /// The timeline elements requested for each day.
+        pub elements: Vec<String>,
     }
     impl From<Option<Document>> for Properties {
         /// Convert the document into the configuration table.
@@ -261,28 +1289,252 @@ mod visual_crossing {
             match value {
                 Some(dict) => {
                     let endpoint = dict.endpoint.unwrap_or(DEFAULT_URI.to_string());
-                    let api_key = dict.api_key.unwrap_or_else(default_api_key);
-                    Properties { endpoint, api_key }
+                    let api_key = ApiKey::from_document(dict.api_key, dict.api_key_file, dict.api_key_command);
+                    let cache = dict.cache.unwrap_or(true);
+                    let replay = dict.replay.unwrap_or(false);
+                    let concurrency = dict.concurrency.unwrap_or(DEFAULT_CONCURRENCY).max(1);
+                    let daily_budget = dict.daily_budget;
+                    let elements = dict.elements.unwrap_or_else(default_elements);
+                    Properties { endpoint, api_key, cache, replay, concurrency, daily_budget, elements }
                 }
-                None => Properties { endpoint: DEFAULT_URI.to_string(), api_key: default_api_key() },
+                None => Properties {
+                    endpoint: DEFAULT_URI.to_string(),
+                    api_key: ApiKey::from_document(None, None, None),
+                    cache: true,
+                    replay: false,
+                    concurrency: DEFAULT_CONCURRENCY,
+                    daily_budget: None,
+                    elements: default_elements(),
+                },
             }
         }
     }
 
     /// The Visual Crossing configuration options.
-    #[derive(Debug, Default, Serialize, Deserialize)]
+    #[derive(Debug, Default, Clone, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
     pub struct Document {
         /// The API end-point.
         pub endpoint: Option<String>,
         /// The API key token.
         #[serde(rename = "api-key")]
         pub api_key: Option<String>,
+        /// The name of a file that contains the API key.
+        #[serde(rename = "api-key-file")]
+        pub api_key_file: Option<String>,
+        /// A command whose standard output is the API key.
+        #[serde(rename = "api-key-command")]
+        pub api_key_command: Option<String>,
+        /// Save the raw response of history requests.
+        pub cache: Option<bool>,
+        /// Get history from the saved responses.
+        pub replay: Option<bool>,
+        /// The maximum number of active history requests.
+        pub concurrency: Option<usize>,
+        /// The maximum number of records charged in a day.
+        #[serde(rename = "daily-budget")]
+        pub daily_budget: Option<u64>,
+        /// The timeline elements to request.
+        pub elements: Option<Vec<String>>,
+    }
+    impl Document {
+        /// Merge the document settings into another document. A key source in the document replaces
+        /// all of the key sources in the other document.
+        pub fn merge(self, base: Document) -> Document {
+            let key_source = self.api_key.is_some() || self.api_key_file.is_some() || self.api_key_command.is_some();
+            let (api_key, api_key_file, api_key_command) = match key_source {
+                true => (self.api_key, self.api_key_file, self.api_key_command),
+                false => (base.api_key, base.api_key_file, base.api_key_command),
+            };
+            Document {
+                endpoint: self.endpoint.or(base.endpoint),
+                api_key,
+                api_key_file,
+                api_key_command,
+                cache: self.cache.or(base.cache),
+                replay: self.replay.or(base.replay),
+                concurrency: self.concurrency.or(base.concurrency),
+                daily_budget: self.daily_budget.or(base.daily_budget),
+                elements: self.elements.or(base.elements),
+            }
+        }
+    }
+    impl From<&Properties> for Document {
+        /// Convert the configuration table into the document.
+        fn from(properties: &Properties) -> Self {
+            let (api_key, api_key_file, api_key_command) = match &properties.api_key {
+                ApiKey::Value(key) => (Some(key.clone()), None, None),
+                ApiKey::File(path) => (None, Some(path.display().to_string()), None),
+                ApiKey::Command(command) => (None, None, Some(command.clone())),
+            };
+            Document {
+                endpoint: Some(properties.endpoint.clone()),
+                api_key,
+                api_key_file,
+                api_key_command,
+                cache: Some(properties.cache),
+                replay: Some(properties.replay),
+                concurrency: Some(properties.concurrency),
+                daily_budget: properties.daily_budget,
+                elements: Some(properties.elements.clone()),
+            }
+        }
+    }
+
+    /// Gets the default timeline elements.
+    fn default_elements() -> Vec<String> {
+        DEFAULT_ELEMENTS.iter().map(|element| element.to_string()).collect()
     }
 
     /// Gets the default API key from the process environment if [ENV_KEY] is defined.
     fn default_api_key() -> String {
         env::var(ENV_KEY).unwrap_or_else(|_| DEFAULT_KEY.to_string())
     }
+
+    /// The source of the Visual Crossing API key.
+    #[derive(Debug, PartialEq)]
+    pub enum ApiKey {
+        /// The key from the configuration file or process environment.
+        Value(String),
+        /// A file that contains the key.
+        File(PathBuf),
+        /// A command whose standard output is the key.
+        Command(String),
+    }
+    impl ApiKey {
+        /// Select the key source from the configuration document. A key file is preferred over a key command
+        /// and both are preferred over a key value. If none have been configured the key will come from the
+        /// process environment or be the [DEFAULT_KEY] placeholder.
+        ///
+        /// # Arguments
+        ///
+        /// * `key` is the API key value.
+        /// * `file` is the name of the file containing the key.
+        /// * `command` is the command that writes the key to standard output.
+        ///
+        fn from_document(key: Option<String>, file: Option<String>, command: Option<String>) -> Self {
+            let sources = [key.is_some(), file.is_some(), command.is_some()].iter().filter(|s| **s).count();
+            if sources > 1 {
+                log::warn!("More than one Visual Crossing API key source is configured.");
+            }
+            match (file, command, key) {
+                (Some(file), _, _) => Self::File(PathBuf::from(file)),
+                (None, Some(command), _) => Self::Command(command),
+                (None, None, Some(key)) => Self::Value(key),
+                (None, None, None) => Self::Value(default_api_key()),
+            }
+        }
+        /// Get the API key. An error is returned if the key cannot be read or it is still the placeholder
+        /// [DEFAULT_KEY].
+        pub fn resolve(&self) -> Result<String> {
+            let key = match self {
+                Self::Value(key) => key.trim().to_string(),
+                Self::File(path) => read_key_file(path)?,
+                Self::Command(command) => run_key_command(command)?,
+            };
+            if key.is_empty() {
+                err!("The Visual Crossing API key is empty.")
+            } else if key == DEFAULT_KEY {
+                err!(format!(
+                    "The Visual Crossing API key has not been configured. Set 'api-key-file' or 'api-key-command' \
+                    in the [visual-crossing] table or define the {} environment variable.",
+                    ENV_KEY
+                ))
+            } else {
+                Ok(key)
+            }
+        }
+    }
+
+    /// Reads the API key from a file. The file cannot be readable by everyone.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` is the key file pathname.
+    ///
+    fn read_key_file(path: &Path) -> Result<String> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            match std::fs::metadata(path) {
+                Ok(metadata) if metadata.permissions().mode() & 0o004 != 0 => {
+                    return err!(format!(
+                        "API key file '{}' is world-readable, restrict its permissions (chmod 600).",
+                        path.display()
+                    ));
+                }
+                Ok(_) => (),
+                Err(error) => return err!(format!("Could not access API key file '{}' ({}).", path.display(), error)),
+            }
+        }
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Ok(contents.trim().to_string()),
+            Err(error) => err!(format!("Could not read API key file '{}' ({}).", path.display(), error)),
+        }
+    }
+
+    /// Runs a command and uses its standard output as the API key.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` is the command that will be run by the shell.
+    ///
+    fn run_key_command(command: &str) -> Result<String> {
+        use std::process::Command;
+        #[cfg(windows)]
+        let output = Command::new("cmd").args(["/C", command]).output();
+        #[cfg(not(windows))]
+        let output = Command::new("sh").args(["-c", command]).output();
+        match output {
+            Err(error) => err!(format!("Could not run API key command '{}' ({}).", command, error)),
+            Ok(output) if !output.status.success() => {
+                err!(format!("API key command '{}' failed ({}).", command, output.status))
+            }
+            Ok(output) => match String::from_utf8(output.stdout) {
+                Ok(key) => Ok(key.trim().to_string()),
+                Err(_) => err!(format!("API key command '{}' output is not UTF-8.", command)),
+            },
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::backend::testlib::TestFixture;
+
+        #[test]
+        fn api_key() {
+            assert!(ApiKey::Value(DEFAULT_KEY.to_string()).resolve().is_err());
+            assert!(ApiKey::Value(" ".to_string()).resolve().is_err());
+            assert_eq!(ApiKey::Value(" key ".to_string()).resolve().unwrap(), "key");
+            let testcase = ApiKey::from_document(Some("key".to_string()), Some("file".to_string()), None);
+            assert_eq!(testcase, ApiKey::File(PathBuf::from("file")));
+            let testcase = ApiKey::from_document(Some("key".to_string()), None, Some("command".to_string()));
+            assert_eq!(testcase, ApiKey::Command("command".to_string()));
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn api_key_file() {
+            use std::os::unix::fs::PermissionsExt;
+            let fixture = TestFixture::create();
+            let path = PathBuf::from(&fixture).join("api.key");
+            std::fs::write(&path, "secret\n").unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+            assert_eq!(ApiKey::File(path.clone()).resolve().unwrap(), "secret");
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+            assert!(ApiKey::File(path.clone()).resolve().is_err());
+            assert!(ApiKey::File(PathBuf::from(&fixture).join("missing.key")).resolve().is_err());
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn api_key_command() {
+            assert_eq!(ApiKey::Command("echo secret".to_string()).resolve().unwrap(), "secret");
+            assert!(ApiKey::Command("exit 1".to_string()).resolve().is_err());
+            assert!(ApiKey::Command("true".to_string()).resolve().is_err());
+        }
+    }
 }
 
 mod us_cities {
@@ -310,13 +1562,117 @@ mod us_cities {
     }
 
     /// The US Cities configuration options.
-    #[derive(Debug, Default, Serialize, Deserialize)]
+    #[derive(Debug, Default, Clone, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
     pub struct Document {
         pub filename: Option<String>,
     }
+    impl Document {
+        /// Merge the document settings into another document.
+        pub fn merge(self, base: Document) -> Document {
+            Document { filename: self.filename.or(base.filename) }
+        }
+    }
+    impl From<&Properties> for Document {
+        /// Convert the configuration table into the document.
+        fn from(properties: &Properties) -> Self {
+            Document { filename: Some(properties.filename.clone()) }
+        }
+    }
 
     /// Gets the default filename from the process environment if [ENV_FILENAME] is defined.
     fn default_filename() -> String {
         env::var(ENV_FILENAME).unwrap_or_else(|_| DEFAULT_FILENAME.to_string())
     }
 }
+
+mod http {
+    //! The HTTP client configuration data.
+    use super::*;
+
+    /// The default number of seconds to wait for a connection.
+    pub const DEFAULT_CONNECT_TIMEOUT: u64 = 10;
+    /// The default number of seconds to wait for a request to complete.
+    pub const DEFAULT_TIMEOUT: u64 = 60;
+    /// The default number of times a request will be retried.
+    pub const DEFAULT_RETRIES: u32 = 3;
+    /// The default number of milliseconds to wait before the first retry.
+    pub const DEFAULT_BACKOFF: u64 = 500;
+    /// The default maximum number of milliseconds to wait before a retry.
+    pub const DEFAULT_MAX_BACKOFF: u64 = 30_000;
+
+    #[derive(Debug)]
+    pub struct Properties {
+        /// The seconds to wait for a connection.
+        pub connect_timeout: u64,
+        /// The seconds to wait for a request to complete.
+        pub timeout: u64,
+        /// The optional proxy URL.
+        pub proxy: Option<String>,
+        /// The user agent sent with requests.
+        pub user_agent: String,
+        /// The number of times a request will be retried.
+        pub retries: u32,
+        /// The milliseconds to wait before the first retry, it doubles for each retry.
+        pub backoff: u64,
+        /// The maximum milliseconds to wait before a retry.
+        pub max_backoff: u64,
+    }
+    impl From<Option<Document>> for Properties {
+        /// Convert the document into the configuration table.
+        fn from(value: Option<Document>) -> Self {
+            let dict = value.unwrap_or_default();
+            Properties {
+                connect_timeout: dict.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT),
+                timeout: dict.timeout.unwrap_or(DEFAULT_TIMEOUT),
+                proxy: dict.proxy,
+                user_agent: dict.user_agent.unwrap_or_else(default_user_agent),
+                retries: dict.retries.unwrap_or(DEFAULT_RETRIES),
+                backoff: dict.backoff.unwrap_or(DEFAULT_BACKOFF),
+                max_backoff: dict.max_backoff.unwrap_or(DEFAULT_MAX_BACKOFF),
+            }
+        }
+    }
+
+    /// The HTTP client configuration options.
+    #[derive(Debug, Default, Clone, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct Document {
+        /// The seconds to wait for a connection.
+        #[serde(rename = "connect-timeout")]
+        pub connect_timeout: Option<u64>,
+        /// The seconds to wait for a request to complete.
+        pub timeout: Option<u64>,
+        /// The proxy URL.
+        pub proxy: Option<String>,
+        /// The user agent sent with requests.
+        #[serde(rename = "user-agent")]
+        pub user_agent: Option<String>,
+        /// The number of times a request will be retried.
+        pub retries: Option<u32>,
+        /// The milliseconds to wait before the first retry.
+        pub backoff: Option<u64>,
+        /// The maximum milliseconds to wait before a retry.
+        #[serde(rename = "max-backoff")]
+        pub max_backoff: Option<u64>,
+    }
+    impl From<&Properties> for Document {
+        /// Convert the configuration table into the document.
+        fn from(properties: &Properties) -> Self {
+            Document {
+                connect_timeout: Some(properties.connect_timeout),
+                timeout: Some(properties.timeout),
+                proxy: properties.proxy.clone(),
+                user_agent: Some(properties.user_agent.clone()),
+                retries: Some(properties.retries),
+                backoff: Some(properties.backoff),
+                max_backoff: Some(properties.max_backoff),
+            }
+        }
+    }
+
+    /// The user agent identifies the weather data library and version.
+    fn default_user_agent() -> String {
+        format!("weather_lib/{}", env!("CARGO_PKG_VERSION"))
+    }
+}
@@ -16,7 +16,7 @@ use crate::backend::{filesys::WeatherDir, Backend, Config};
 pub(in crate::backend) fn create_db_backend(config: Config) -> crate::Result<Box<dyn Backend>> {
     log::debug!("Database data adapter");
     let weather_dir = WeatherDir::try_from(&config)?;
-    Ok(Box::new(sqlite::SqliteBackend::new(config, weather_dir)))
+    Ok(Box::new(sqlite::SqliteBackend::new(config, weather_dir)?))
 }
 
 /// Tests if the database has been initialized.
@@ -2,11 +2,12 @@
 
 use super::sqlite;
 use crate::{
-    admin::{DbDetails, UsCityDetails},
-    backend::filesys::WeatherDir,
+    admin::{DbDetails, SqlResults, SyncLocation, UsCityDetails, VerifyProblem},
+    backend::filesys::{LockMode, WeatherDir},
     entities::LocationFilters,
 };
-use std::path::PathBuf;
+use chrono::NaiveDate;
+use std::path::{Path, PathBuf};
 
 /// Initialize the database schema.
 ///
@@ -18,6 +19,7 @@ use std::path::PathBuf;
 /// * `load` when true will load weather data into the database.
 ///
 pub fn init_db(weather_dir: &WeatherDir, drop: bool, load: bool, threads: usize) -> crate::Result<()> {
+    let _lock = weather_dir.lock(LockMode::Exclusive)?;
     sqlite::admin::init_db(weather_dir, drop, load, threads)
 }
 
@@ -29,6 +31,7 @@ pub fn init_db(weather_dir: &WeatherDir, drop: bool, load: bool, threads: usize)
 /// * `delete` when true will remove the database file.
 ///
 pub fn drop_db(weather_dir: &WeatherDir, delete: bool) -> crate::Result<()> {
+    let _lock = weather_dir.lock(LockMode::Exclusive)?;
     sqlite::admin::drop_db(weather_dir, delete)
 }
 
@@ -39,6 +42,7 @@ pub fn drop_db(weather_dir: &WeatherDir, delete: bool) -> crate::Result<()> {
 /// * `weather_dir` is the weather data directory.
 ///
 pub fn db_details(weather_dir: &WeatherDir) -> crate::Result<Option<DbDetails>> {
+    let _lock = weather_dir.lock(LockMode::Shared)?;
     sqlite::admin::db_details(weather_dir)
 }
 
@@ -50,9 +54,72 @@ pub fn db_details(weather_dir: &WeatherDir) -> crate::Result<Option<DbDetails>>
 /// * `filters` identifies the locations that will be reloaded.
 ///
 pub fn reload(weather_dir: &WeatherDir, filters: LocationFilters) -> crate::Result<Vec<String>> {
+    let _lock = weather_dir.lock(LockMode::Exclusive)?;
     sqlite::admin::reload(weather_dir, filters)
 }
 
+/// Run a read-only query against the database.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+/// * `sql` is the query that will be run.
+///
+pub fn sql(weather_dir: &WeatherDir, sql: &str) -> crate::Result<SqlResults> {
+    let _lock = weather_dir.lock(LockMode::Shared)?;
+    sqlite::admin::sql(weather_dir, sql)
+}
+
+/// Remove location histories from the database.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+/// * `alias` is the location alias.
+/// * `dates` are the history dates that will be removed.
+///
+pub fn remove_histories(weather_dir: &WeatherDir, alias: &str, dates: &[NaiveDate]) -> crate::Result<()> {
+    let _lock = weather_dir.lock(LockMode::Exclusive)?;
+    sqlite::admin::remove_histories(weather_dir, alias, dates)
+}
+
+/// Compare the database history metadata with the location archives.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+///
+pub fn verify(weather_dir: &WeatherDir) -> crate::Result<Vec<VerifyProblem>> {
+    let _lock = weather_dir.lock(LockMode::Shared)?;
+    sqlite::admin::verify(weather_dir)
+}
+
+/// Copy the histories that are missing from the database or the location archives.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+/// * `filters` identifies the locations that will be synchronized.
+/// * `dry_run` when true finds the histories that are missing but does not copy them.
+///
+pub fn sync(weather_dir: &WeatherDir, filters: LocationFilters, dry_run: bool) -> crate::Result<Vec<SyncLocation>> {
+    let _lock = weather_dir.lock(if dry_run { LockMode::Shared } else { LockMode::Exclusive })?;
+    sqlite::admin::sync(weather_dir, filters, dry_run)
+}
+
+/// Write a copy of the database to a file. The name of the database file is returned or `None` if
+/// there is no database.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+/// * `path` is the database copy, it must not exist.
+///
+pub fn snapshot(weather_dir: &WeatherDir, path: &Path) -> crate::Result<Option<String>> {
+    let _lock = weather_dir.lock(LockMode::Shared)?;
+    sqlite::admin::snapshot(weather_dir, path)
+}
+
 /// Creates the database counting the US Cities `CSV` file.
 ///
 /// # Arguments
@@ -62,6 +129,7 @@ pub fn reload(weather_dir: &WeatherDir, filters: LocationFilters) -> crate::Resu
 ///
 // todo: change the signature to take a str
 pub fn uscities_load(weather_dir: &WeatherDir, csv_file: &PathBuf) -> crate::Result<usize> {
+    let _lock = weather_dir.lock(LockMode::Exclusive)?;
     sqlite::admin::uscities_load(weather_dir, csv_file.display().to_string().as_str())
 }
 
@@ -72,6 +140,7 @@ pub fn uscities_load(weather_dir: &WeatherDir, csv_file: &PathBuf) -> crate::Res
 /// * `weather_dir` is the weather data directory.
 ///
 pub fn uscities_delete(weather_dir: &WeatherDir) -> crate::Result<()> {
+    let _lock = weather_dir.lock(LockMode::Exclusive)?;
     sqlite::admin::uscities_delete(weather_dir)
 }
 
@@ -82,5 +151,6 @@ pub fn uscities_delete(weather_dir: &WeatherDir) -> crate::Result<()> {
 /// * `weather_dir` is the weather data directory.
 ///
 pub fn uscities_info(weather_dir: &WeatherDir) -> crate::Result<UsCityDetails> {
+    let _lock = weather_dir.lock(LockMode::Shared)?;
     sqlite::admin::uscities_info(weather_dir)
 }
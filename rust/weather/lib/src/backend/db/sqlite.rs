@@ -1,16 +1,19 @@
 //! The Sqlite database implementation for weather data.
 
 pub mod admin;
+mod connections;
 mod history;
 mod locations;
 mod metadata;
+mod migrations;
 // you need to expose this for filesys right now.
 pub mod us_cities;
 
 use super::LocationFilters;
+use connections::ConnectionPool;
 use crate::{
     backend::{
-        filesys::{WeatherDir, WeatherFile},
+        filesys::{LockMode, WeatherDir, WeatherFile},
         Backend, Config,
     },
     entities::{DailyHistories, DateRange, HistoryDates, HistorySummaries, Location, State, CityFilter},
@@ -57,10 +60,64 @@ pub(in crate::backend::db) fn db_connection(optional_file: Option<WeatherFile>)
     }
 }
 
-/// A helper to create a database connection.
+/// Create a connection to the weather data database. The database uses `WAL` journaling so readers
+/// are not blocked by a writer, and a busy database is waited on as long as the directory lock.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+///
+pub(in crate::backend::db) fn weather_db_connection(weather_dir: &WeatherDir) -> crate::Result<rusqlite::Connection> {
+    let conn = db_connection(Some(weather_dir.file(DB_FILENAME)))?;
+    if let Err(error) = conn.busy_timeout(weather_dir.lock_timeout()) {
+        err!("failed to set the busy timeout: {:?}", error)?;
+    }
+    // a read-only weather directory cannot change the journal, the database can still be read
+    match conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0)) {
+        Ok(mode) if !mode.eq_ignore_ascii_case("wal") => log::warn!("SQLite is using the '{}' journal.", mode),
+        Ok(_) => (),
+        Err(error) => log::warn!("SQLite could not use the WAL journal: {:?}", error),
+    }
+    // with WAL, NORMAL does not risk corruption and only fsyncs at checkpoints
+    if let Err(error) = conn.pragma_update(None, "synchronous", "NORMAL") {
+        err!("failed to set synchronous mode: {:?}", error)?;
+    }
+    if let Err(error) = conn.pragma_update(None, "temp_store", "MEMORY") {
+        err!("failed to set the temporary store: {:?}", error)?;
+    }
+    Ok(conn)
+}
+
+/// Create a read-only connection to the weather data database. A busy database is waited on as long
+/// as the directory lock.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+///
+pub(in crate::backend::db) fn weather_db_reader(weather_dir: &WeatherDir) -> crate::Result<rusqlite::Connection> {
+    let file = weather_dir.file(DB_FILENAME);
+    if !file.exists() {
+        err!("the weather database has not been created.")?;
+    }
+    let flags = rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX;
+    let conn = match rusqlite::Connection::open_with_flags(file.to_string(), flags) {
+        Ok(conn) => conn,
+        Err(error) => err!("failed to get a read-only database connection to {}: {:?}", file, error)?,
+    };
+    if let Err(error) = conn.busy_timeout(weather_dir.lock_timeout()) {
+        err!("failed to set the busy timeout: {:?}", error)?;
+    }
+    if let Err(error) = conn.pragma_update(None, "temp_store", "MEMORY") {
+        err!("failed to set the temporary store: {:?}", error)?;
+    }
+    Ok(conn)
+}
+
+/// A helper to create a weather data database connection.
 macro_rules! db_conn {
     ($weather_dir:expr) => {
-        $crate::backend::db::sqlite::db_connection(Some($weather_dir.file(crate::backend::db::sqlite::DB_FILENAME)))
+        $crate::backend::db::sqlite::weather_db_connection($weather_dir)
     };
 }
 use db_conn;
@@ -137,24 +194,41 @@ pub struct SqliteBackend {
     config: Config,
     /// The weather data directory.
     weather_dir: WeatherDir,
+    /// The database connections kept open between calls.
+    connections: ConnectionPool,
 }
 impl SqliteBackend {
-    pub fn new(config: Config, weather_dir: WeatherDir) -> Self {
-        Self { config, weather_dir }
+    /// Create the database backend, migrating the database schema if it is out of date.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` is the weather data configuration.
+    /// * `weather_dir` is the weather data directory.
+    ///
+    pub fn new(config: Config, weather_dir: WeatherDir) -> crate::Result<Self> {
+        let connections = ConnectionPool::new();
+        if migrations::is_pending(&*connections.reader(&weather_dir)?)? {
+            let _lock = weather_dir.lock(LockMode::Exclusive)?;
+            migrations::migrate(&mut *connections.writer(&weather_dir)?)?;
+        }
+        Ok(Self { config, weather_dir, connections })
     }
 }
+// The database is read without the weather data directory lock, WAL journaling lets readers run while
+// a writer is active. The exclusive lock is taken when archives or the locations file are written.
 impl Backend for SqliteBackend {
     fn get_config(&self) -> &Config {
         &self.config
     }
 
     fn add_daily_histories(&self, daily_histories: DailyHistories) -> crate::Result<usize> {
-        let mut conn = db_conn!(&self.weather_dir)?;
+        let _lock = self.weather_dir.lock(LockMode::Exclusive)?;
+        let mut conn = self.connections.writer(&self.weather_dir)?;
         history::add(&mut conn, &self.weather_dir, daily_histories)
     }
 
     fn get_daily_histories(&self, filters: LocationFilters, history_range: DateRange) -> crate::Result<DailyHistories> {
-        let mut conn = db_conn!(&self.weather_dir)?;
+        let mut conn = self.connections.reader(&self.weather_dir)?;
         let mut locations = locations::get(&conn, filters)?;
         let location = match locations.len() {
             1 => locations.pop().unwrap(),
@@ -164,29 +238,48 @@ impl Backend for SqliteBackend {
         history::get(&mut conn, location, history_range)
     }
 
+    fn get_location_histories(&self, location: Location, history_range: DateRange) -> crate::Result<DailyHistories> {
+        let mut conn = self.connections.reader(&self.weather_dir)?;
+        history::get(&mut conn, location, history_range)
+    }
+
     fn get_history_dates(&self, filters: LocationFilters) -> crate::Result<Vec<HistoryDates>> {
-        let conn = db_conn!(&self.weather_dir)?;
+        let conn = self.connections.reader(&self.weather_dir)?;
         history::history_dates(&conn, filters)
     }
 
     fn get_history_summaries(&self, filters: LocationFilters) -> crate::Result<Vec<HistorySummaries>> {
-        let mut conn = db_conn!(&self.weather_dir)?;
+        let mut conn = self.connections.reader(&self.weather_dir)?;
         history::summary(&mut conn, &self.weather_dir, filters)
     }
 
+    fn search_histories(
+        &self,
+        filters: LocationFilters,
+        query: &str,
+        history_range: Option<DateRange>,
+    ) -> crate::Result<Vec<DailyHistories>> {
+        let conn = self.connections.reader(&self.weather_dir)?;
+        history::search(&conn, filters, query, history_range)
+    }
+
     fn get_locations(&self, filters: LocationFilters) -> crate::Result<Vec<Location>> {
-        let conn = db_conn!(&self.weather_dir)?;
+        let conn = self.connections.reader(&self.weather_dir)?;
         locations::get(&conn, filters)
     }
 
     fn add_location(&self, location: Location) -> crate::Result<()> {
-        let mut conn = db_conn!(&self.weather_dir)?;
+        let _lock = self.weather_dir.lock(LockMode::Exclusive)?;
+        let mut conn = self.connections.writer(&self.weather_dir)?;
         locations::add(&mut conn, location, &self.weather_dir)
     }
 
     fn search_locations(&self, filter: CityFilter) -> crate::Result<Vec<Location>> {
         if !us_cities::exists(&self.weather_dir) {
-            us_cities::create(&self.weather_dir, &self.config.us_cities.filename)?;
+            let _lock = self.weather_dir.lock(LockMode::Exclusive)?;
+            if !us_cities::exists(&self.weather_dir) {
+                us_cities::create(&self.weather_dir, &self.config.us_cities.filename)?;
+            }
         }
         us_cities::get_cities(&us_cities::open(&self.weather_dir)?, filter)
     }
@@ -238,3 +331,37 @@ pub fn estimate_size(conn: &rusqlite::Connection, table: &str) -> crate::Result<
     }
     Ok(size_estimate)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{db::sqlite::admin::init_db, testlib};
+    use std::fs::OpenOptions;
+
+    #[test]
+    fn read_while_writing() {
+        let fixture = testlib::TestFixture::create();
+        let config = format!("[weather-data]\ndirectory = \"{}\"\nlock-timeout = 1", fixture);
+        let weather_dir = WeatherDir::try_from(&Config::try_from(config.as_str()).unwrap()).unwrap();
+        init_db(&weather_dir, false, false, 1).unwrap();
+        let testcase = SqliteBackend::new(Config::try_from(config.as_str()).unwrap(), weather_dir).unwrap();
+        let weather_dir = WeatherDir::try_from(&testcase.config).unwrap();
+
+        // another process holds the directory lock while it writes the database
+        let lock_path = weather_dir.file("weather.lock");
+        let lock_file = OpenOptions::new().read(true).append(true).create(true).open(lock_path.path()).unwrap();
+        lock_file.try_lock().unwrap();
+        let mut conn = weather_db_connection(&weather_dir).unwrap();
+        let tx = conn.transaction().unwrap();
+        let location = testlib::location("Tigard", "tigard");
+        locations::insert(&tx, &location).unwrap();
+
+        // the uncommitted location is not seen and the reader does not wait for the lock
+        assert!(testcase.get_locations(LocationFilters::default()).unwrap().is_empty());
+        tx.commit().unwrap();
+        let locations = testcase.get_locations(LocationFilters::default()).unwrap();
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].alias, "tigard");
+        lock_file.unlock().unwrap();
+    }
+}
@@ -1,12 +1,17 @@
 mod history_loader;
 
-use super::{history, locations, prepare_sql, query_rows, us_cities};
+use super::{history, locations, metadata, migrations, prepare_sql, query_rows, us_cities};
 use crate::{
-    admin::{DbDetails, LocationDetails, UsCityDetails},
-    backend::filesys::WeatherDir,
-    entities::LocationFilters,
+    admin::{DbDetails, LocationDetails, SqlResults, SyncLocation, SyncTarget, UsCityDetails, VerifyProblem},
+    backend::filesys::{HistoryArchive, Locations, WeatherDir, WeatherFile},
+    entities::{Location, LocationFilters},
+};
+use chrono::NaiveDate;
+use rusqlite::{types::ValueRef, Batch, Connection, Row};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::Path,
 };
-use rusqlite::{Connection, Row};
 
 /// Create a database history specific error message.
 macro_rules! error {
@@ -36,7 +41,7 @@ pub fn init_db(weather_dir: &WeatherDir, drop: bool, load: bool, threads: usize)
         drop_db(weather_dir, false)?;
     }
     let mut conn = super::db_conn!(weather_dir)?;
-    init_schema(&conn)?;
+    migrations::migrate(&mut conn)?;
     if load {
         log::debug!("loading data");
         locations::load(&mut conn, weather_dir)?;
@@ -45,21 +50,6 @@ pub fn init_db(weather_dir: &WeatherDir, drop: bool, load: bool, threads: usize)
     Ok(())
 }
 
-/// Initialize the database schema.
-///
-/// # Arguments
-///
-/// * `conn` is the database connection that will be used.
-/// * `db_mode` is the database configuration.
-fn init_schema(conn: &Connection) -> crate::Result<()> {
-    log::debug!("init schema");
-    let sql = include_str!("schema.sql");
-    if let Err(error) = conn.execute_batch(sql) {
-        err!("failed to initialize the schema: {:?}", error)?;
-    }
-    Ok(())
-}
-
 /// Provide information about the database.
 ///
 /// # Arguments
@@ -102,7 +92,8 @@ pub fn db_details(weather_dir: &WeatherDir) -> crate::Result<Option<DbDetails>>
                 }
             };
         }
-        db_details.replace(DbDetails { size: file.size() as usize, location_details });
+        let version = migrations::version(&conn)?;
+        db_details.replace(DbDetails { size: file.size() as usize, version, location_details });
     }
     Ok(db_details)
 }
@@ -118,13 +109,46 @@ pub fn drop_db(weather_dir: &WeatherDir, delete: bool) -> crate::Result<()> {
     let file = weather_dir.file(super::DB_FILENAME);
     if file.exists() {
         match delete {
-            true => file.remove()?,
+            true => {
+                file.remove()?;
+                remove_journal(weather_dir, super::DB_FILENAME)?;
+            }
             false => drop_schema(super::db_conn!(weather_dir)?)?,
         }
     }
     Ok(())
 }
 
+/// Remove the `WAL` journal files of a database. A journal left behind must not be applied to a
+/// new database with the same name.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+/// * `filename` is the database filename.
+///
+pub fn remove_journal(weather_dir: &WeatherDir, filename: &str) -> crate::Result<()> {
+    for file in journal_files(weather_dir, filename) {
+        file.remove()?;
+    }
+    Ok(())
+}
+
+/// Get the `WAL` journal files of a database that exist.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+/// * `filename` is the database filename.
+///
+pub fn journal_files(weather_dir: &WeatherDir, filename: &str) -> Vec<WeatherFile> {
+    ["-wal", "-shm"]
+        .iter()
+        .map(|suffix| weather_dir.file(&format!("{}{}", filename, suffix)))
+        .filter(|file| file.exists())
+        .collect()
+}
+
 /// Delete the database schema.
 ///
 /// Arguments
@@ -161,6 +185,252 @@ pub fn reload(weather_dir: &WeatherDir, filters: LocationFilters) -> crate::Resu
     Ok(reloaded)
 }
 
+/// Remove location histories from the database. Nothing is removed if the database does not exist or
+/// the location is not in the database.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+/// * `alias` is the location alias.
+/// * `dates` are the history dates that will be removed.
+///
+pub fn remove_histories(weather_dir: &WeatherDir, alias: &str, dates: &[NaiveDate]) -> crate::Result<()> {
+    if dates.is_empty() || !super::db_exists(weather_dir) {
+        return Ok(());
+    }
+    let mut conn = super::db_conn!(weather_dir)?;
+    let lid = locations::id_aliases(&conn)?.into_iter().find(|(_, id_alias)| id_alias == alias).map(|(lid, _)| lid);
+    match lid {
+        Some(lid) => history::remove(&mut conn, lid, dates),
+        None => Ok(()),
+    }
+}
+
+/// Compare the database history metadata with the location archives. Archives that cannot be opened
+/// are skipped, the archive verification reports them.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+///
+pub fn verify(weather_dir: &WeatherDir) -> crate::Result<Vec<VerifyProblem>> {
+    let mut problems = vec![];
+    if !super::db_exists(weather_dir) {
+        return Ok(problems);
+    }
+    let conn = super::db_conn!(weather_dir)?;
+    for (lid, alias) in locations::id_aliases(&conn)? {
+        let archived: HashMap<NaiveDate, usize> = match HistoryArchive::open(&alias, weather_dir.archive(&alias)) {
+            Ok(archive) => match archive.metadata() {
+                Ok(metadata) => metadata.map(|md| (md.date, md.compressed_size as usize)).collect(),
+                Err(error) => {
+                    problems.push(VerifyProblem { alias, history: None, problem: error.to_string() });
+                    continue;
+                }
+            },
+            Err(_) => continue,
+        };
+        let mut problem = |date: &NaiveDate, problem: String| {
+            problems.push(VerifyProblem { alias: alias.clone(), history: Some(date.to_string()), problem })
+        };
+        let stored = metadata::get(&conn, lid)?;
+        for (date, store_size) in &stored {
+            match archived.get(date) {
+                None => problem(date, "the database history is not in the archive.".to_string()),
+                Some(size) if size != store_size => {
+                    problem(date, format!("the database size is {} but the archive size is {}.", store_size, size))
+                }
+                Some(_) => (),
+            }
+        }
+        let stored: HashMap<NaiveDate, usize> = stored.into_iter().collect();
+        let mut missing: Vec<&NaiveDate> = archived.keys().filter(|date| !stored.contains_key(date)).collect();
+        missing.sort_unstable();
+        for date in missing {
+            problem(date, "the archive history is not in the database.".to_string());
+        }
+    }
+    Ok(problems)
+}
+
+/// Copy the histories that are missing from the database or the location archives. Locations that
+/// are missing from the database or the locations document are added first.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+/// * `filters` identifies the locations that will be synchronized.
+/// * `dry_run` when true finds the histories that are missing but does not copy them.
+///
+pub fn sync(weather_dir: &WeatherDir, filters: LocationFilters, dry_run: bool) -> crate::Result<Vec<SyncLocation>> {
+    if !super::db_exists(weather_dir) {
+        err!("the database does not exist.")?;
+    }
+    let mut conn = super::db_conn!(weather_dir)?;
+    let db_locations: BTreeMap<String, Location> = locations::get(&conn, filters.clone())?
+        .into_iter()
+        .map(|location| (location.alias.clone(), location))
+        .collect();
+    let fs_locations: BTreeMap<String, Location> = Locations::open(weather_dir)?
+        .find(filters)?
+        .map(|location| (location.alias.clone(), location))
+        .collect();
+    let mut aliases: Vec<&String> = db_locations.keys().chain(fs_locations.keys()).collect();
+    aliases.sort_unstable();
+    aliases.dedup();
+
+    let mut synced = vec![];
+    for alias in aliases {
+        let (location, added_to) = match (db_locations.get(alias), fs_locations.get(alias)) {
+            (Some(location), Some(_)) => (location, None),
+            (None, Some(location)) => (location, Some(SyncTarget::Database)),
+            (Some(location), None) => (location, Some(SyncTarget::Archives)),
+            (None, None) => unreachable!("the alias comes from the locations"),
+        };
+        let archived: BTreeSet<NaiveDate> = match added_to {
+            Some(SyncTarget::Archives) => BTreeSet::new(),
+            _ => HistoryArchive::open(alias, weather_dir.archive(alias))?.metadata()?.map(|md| md.date).collect(),
+        };
+        let stored: BTreeSet<NaiveDate> = match added_to {
+            Some(SyncTarget::Database) => BTreeSet::new(),
+            _ => {
+                let lid = locations::location_id(&conn, alias)?;
+                metadata::get(&conn, lid)?.into_iter().map(|(date, _)| date).collect()
+            }
+        };
+        let to_database: Vec<NaiveDate> = archived.difference(&stored).cloned().collect();
+        let to_archive: Vec<NaiveDate> = stored.difference(&archived).cloned().collect();
+        if added_to.is_none() && to_database.is_empty() && to_archive.is_empty() {
+            continue;
+        }
+        if !dry_run {
+            match added_to {
+                Some(SyncTarget::Database) => locations::insert(&conn, location)?,
+                Some(SyncTarget::Archives) => {
+                    Locations::open(weather_dir)?.add(location.clone())?;
+                }
+                None => (),
+            }
+            history::copy_from_archive(&mut conn, weather_dir, alias, &to_database)?;
+            history::copy_to_archive(&mut conn, weather_dir, location, &to_archive)?;
+        }
+        synced.push(SyncLocation { alias: alias.clone(), added_to, to_database, to_archive });
+    }
+    Ok(synced)
+}
+
+/// Write a copy of the database to a file.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+/// * `path` is the database copy, it must not exist.
+///
+pub fn snapshot(weather_dir: &WeatherDir, path: &Path) -> crate::Result<Option<String>> {
+    if !super::db_exists(weather_dir) {
+        return Ok(None);
+    }
+    let conn = super::db_conn!(weather_dir)?;
+    match conn.execute("VACUUM INTO ?1", [path.display().to_string()]) {
+        Ok(_) => Ok(Some(super::DB_FILENAME.to_string())),
+        Err(error) => err!("failed to copy the database to {}: {:?}", path.display(), error),
+    }
+}
+
+/// The convenience views available to queries. They are temporary so the database is not changed.
+const SQL_VIEWS: &str = r#"
+    CREATE TEMP VIEW daily AS
+        SELECT
+            l.alias AS alias, m.date AS date, h.temp_high AS temp_high, h.temp_low AS temp_low,
+            h.temp_mean AS temp_mean, h.dew_point AS dew_point, h.humidity AS humidity,
+            h.sunrise_t AS sunrise_t, h.sunset_t AS sunset_t, h.cloud_cover AS cloud_cover,
+            h.moon_phase AS moon_phase, h.uv_index AS uv_index, h.wind_speed AS wind_speed,
+            h.wind_gust AS wind_gust, h.wind_dir AS wind_dir, h.visibility AS visibility,
+            h.pressure AS pressure, h.precip AS precip, h.precip_prob AS precip_prob,
+            h.precip_type AS precip_type, h.description AS description
+        FROM history AS h
+            INNER JOIN metadata AS m ON h.mid = m.id
+            INNER JOIN locations AS l ON m.lid = l.id;
+    CREATE TEMP VIEW summary AS
+        SELECT
+            l.alias AS alias, l.name AS name, COUNT(m.id) AS histories, MIN(m.date) AS first_date,
+            MAX(m.date) AS last_date
+        FROM locations AS l
+            LEFT JOIN metadata AS m ON m.lid = l.id
+        GROUP BY l.id;
+"#;
+
+/// Run a read-only query against the database. The database is opened read-only and statements that
+/// would change it are rejected before they run.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+/// * `sql` is the query that will be run.
+///
+pub fn sql(weather_dir: &WeatherDir, sql: &str) -> crate::Result<SqlResults> {
+    let conn = super::weather_db_reader(weather_dir)?;
+    if let Err(error) = conn.execute_batch(SQL_VIEWS) {
+        err!("failed to create the query views: {:?}", error)?;
+    }
+    // temporary tables could otherwise still be changed
+    if let Err(error) = conn.pragma_update(None, "query_only", true) {
+        err!("failed to make the connection query only: {:?}", error)?;
+    }
+    let mut batch = Batch::new(&conn, sql);
+    let mut stmt = match batch.next() {
+        Ok(Some(stmt)) => stmt,
+        Ok(None) => err!("query is empty.")?,
+        Err(error) => err!("query is not valid ({}).", error)?,
+    };
+    if !matches!(batch.next(), Ok(None)) {
+        err!("query must be a single statement.")?;
+    }
+    if !stmt.readonly() {
+        err!("only queries that read the database are allowed.")?;
+    }
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let mut rows = query_rows!(stmt, [], "failed to run the query")?;
+    let mut sql_results = SqlResults { columns, rows: vec![] };
+    loop {
+        match rows.next() {
+            Err(error) => err!("failed to get the next query row ({}).", error)?,
+            Ok(None) => break,
+            Ok(Some(row)) => {
+                let mut values = Vec::with_capacity(sql_results.columns.len());
+                for index in 0..sql_results.columns.len() {
+                    let value = match row.get_ref(index) {
+                        Ok(value) => value,
+                        Err(error) => err!("failed to get query column {}: {:?}", index, error)?,
+                    };
+                    values.push(json_value(value));
+                }
+                sql_results.rows.push(values);
+            }
+        }
+    }
+    Ok(sql_results)
+}
+
+/// Convert a database value to `JSON`. Blobs are converted to hexadecimal text.
+///
+/// # Arguments
+///
+/// * `value` is the database column value.
+///
+fn json_value(value: ValueRef) -> serde_json::Value {
+    match value {
+        ValueRef::Null => serde_json::Value::Null,
+        ValueRef::Integer(value) => serde_json::Value::from(value),
+        ValueRef::Real(value) => serde_json::Value::from(value),
+        ValueRef::Text(text) => serde_json::Value::from(String::from_utf8_lossy(text).to_string()),
+        ValueRef::Blob(blob) => {
+            let hex: String = blob.iter().map(|byte| format!("{:02x}", byte)).collect();
+            serde_json::Value::from(hex)
+        }
+    }
+}
+
 /// Creates the database counting the US Cities `CSV` file.
 ///
 /// # Arguments
@@ -199,7 +469,11 @@ pub fn uscities_info(weather_dir: &WeatherDir) -> crate::Result<UsCityDetails> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::backend::{db::sqlite::DB_FILENAME, testlib};
+    use crate::{
+        backend::{db::sqlite::DB_FILENAME, testlib},
+        entities::{DailyHistories, History},
+    };
+    use serde_json::json;
     use std::path::PathBuf;
 
     #[test]
@@ -215,4 +489,95 @@ mod tests {
         db_details(&weather_dir).unwrap().expect("Did not get DbDetails");
         drop_db(&weather_dir, false).unwrap();
     }
+
+    #[test]
+    fn sync_histories() {
+        let fixture = testlib::TestFixture::create();
+        let weather_dir = WeatherDir::try_from(fixture.to_string()).unwrap();
+        init_db(&weather_dir, false, false, 1).unwrap();
+        let location = |alias: &str| testlib::location("Beaverton", alias);
+        let history = |alias: &str, day: u32| History {
+            alias: alias.to_string(),
+            date: NaiveDate::from_ymd_opt(2025, 6, day).unwrap(),
+            ..Default::default()
+        };
+        let mut conn = super::super::db_conn!(&weather_dir).unwrap();
+        locations::add(&mut conn, location("both"), &weather_dir).unwrap();
+        let daily_histories = DailyHistories { location: location("both"), histories: vec![history("both", 1)] };
+        history::add(&mut conn, &weather_dir, daily_histories).unwrap();
+
+        // a history only in the archive, one only in the database, and a location only in the archives
+        let archive = HistoryArchive::open("both", weather_dir.archive("both")).unwrap();
+        archive.append(&vec![history("both", 2)]).unwrap();
+        let lid = locations::location_id(&conn, "both").unwrap();
+        let mut tx = conn.transaction().unwrap();
+        history::insert_history(&mut tx, lid, 0, 0, &history("both", 3)).unwrap();
+        tx.commit().unwrap();
+        Locations::open(&weather_dir).unwrap().add(location("files")).unwrap();
+        let archive = HistoryArchive::open("files", weather_dir.archive("files")).unwrap();
+        archive.append(&vec![history("files", 4)]).unwrap();
+
+        let synced = sync(&weather_dir, LocationFilters::default(), true).unwrap();
+        assert_eq!(synced.len(), 2);
+        assert_eq!(synced[0].alias, "both");
+        assert_eq!(synced[0].added_to, None);
+        assert_eq!(synced[0].to_database, vec![NaiveDate::from_ymd_opt(2025, 6, 2).unwrap()]);
+        assert_eq!(synced[0].to_archive, vec![NaiveDate::from_ymd_opt(2025, 6, 3).unwrap()]);
+        assert_eq!(synced[1].alias, "files");
+        assert_eq!(synced[1].added_to, Some(SyncTarget::Database));
+        assert_eq!(synced[1].to_database.len(), 1);
+        assert_eq!(sync(&weather_dir, LocationFilters::default(), true).unwrap().len(), 2);
+
+        sync(&weather_dir, LocationFilters::default(), false).unwrap();
+        assert!(sync(&weather_dir, LocationFilters::default(), true).unwrap().is_empty());
+        assert!(verify(&weather_dir).unwrap().is_empty());
+        let archive = HistoryArchive::open("both", weather_dir.archive("both")).unwrap();
+        assert_eq!(archive.metadata().unwrap().count(), 3);
+        // removed histories are no longer in the database
+        remove_histories(&weather_dir, "both", &[NaiveDate::from_ymd_opt(2025, 6, 2).unwrap()]).unwrap();
+        remove_histories(&weather_dir, "missing", &[NaiveDate::from_ymd_opt(2025, 6, 2).unwrap()]).unwrap();
+        let problems = verify(&weather_dir).unwrap();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].history.as_deref(), Some("2025-06-02"));
+        assert_eq!(problems[0].problem, "the archive history is not in the database.");
+    }
+
+    #[test]
+    fn sql_query() {
+        let fixture = testlib::TestFixture::create();
+        let weather_dir = WeatherDir::try_from(fixture.to_string()).unwrap();
+        assert!(sql(&weather_dir, "SELECT 1").is_err());
+        init_db(&weather_dir, false, false, 1).unwrap();
+        let location = testlib::location("Tigard", "tigard");
+        let history = |day: u32, description: Option<&str>| History {
+            alias: "tigard".to_string(),
+            date: NaiveDate::from_ymd_opt(2024, 7, day).unwrap(),
+            temperature_high: Some(80.0 + day as f64),
+            description: description.map(String::from),
+            ..Default::default()
+        };
+        let mut conn = super::super::db_conn!(&weather_dir).unwrap();
+        locations::add(&mut conn, location.clone(), &weather_dir).unwrap();
+        let histories = vec![history(4, Some("Clear conditions.")), history(5, None)];
+        history::add(&mut conn, &weather_dir, DailyHistories { location, histories }).unwrap();
+        drop(conn);
+
+        let query = "SELECT alias, date, temp_high, description FROM daily ORDER BY date";
+        let testcase = sql(&weather_dir, query).unwrap();
+        assert_eq!(testcase.columns, vec!["alias", "date", "temp_high", "description"]);
+        assert_eq!(testcase.rows.len(), 2);
+        let row = vec![json!("tigard"), json!("2024-07-04"), json!(84.0), json!("Clear conditions.")];
+        assert_eq!(testcase.rows[0], row);
+        assert_eq!(testcase.rows[1][3], serde_json::Value::Null);
+        let testcase = sql(&weather_dir, "SELECT * FROM summary").unwrap();
+        assert_eq!(testcase.rows[0][2], json!(2));
+        assert_eq!(testcase.rows[0][3], json!("2024-07-04"));
+        assert_eq!(testcase.rows[0][4], json!("2024-07-05"));
+        assert!(sql(&weather_dir, "DELETE FROM history").is_err());
+        assert!(sql(&weather_dir, "CREATE TEMP TABLE scratch (id INTEGER)").is_err());
+        assert!(sql(&weather_dir, "SELECT 1; DELETE FROM history").is_err());
+        assert!(sql(&weather_dir, "SELECT * FROM nowhere").is_err());
+        assert!(sql(&weather_dir, " ").is_err());
+        assert_eq!(sql(&weather_dir, "SELECT COUNT(*) FROM history").unwrap().rows[0][0], json!(2));
+    }
 }
@@ -0,0 +1,217 @@
+//! The database connections kept open by the backend.
+//!
+//! Opening a connection for each call loses the prepared statement cache, so the backend keeps its
+//! connections open. Reads take an idle connection from the pool, or open one if none are idle.
+//! Writes share a single connection because `SQLite` allows one writer at a time. The database uses
+//! `WAL` journaling so readers run while a writer is active.
+//!
+//! A restore or drop can replace the database file. A connection opened on a file that has been
+//! replaced is closed and a new one opened.
+
+use super::{weather_db_connection, DB_FILENAME};
+use crate::backend::filesys::WeatherDir;
+use rusqlite::Connection;
+use std::{
+    fs,
+    ops::{Deref, DerefMut},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+/// The most idle read connections that will be kept open.
+const MAX_IDLE_READERS: usize = 4;
+
+/// Identifies the database file a connection was opened on.
+type FileId = Option<(u64, u64)>;
+
+/// A connection and the database file it was opened on.
+#[derive(Debug)]
+pub struct Opened {
+    /// The database connection.
+    conn: Connection,
+    /// The database file identity when the connection was opened.
+    file_id: FileId,
+}
+
+/// The pool of database connections.
+#[derive(Debug)]
+pub struct ConnectionPool {
+    /// The idle read connections.
+    readers: Mutex<Vec<Opened>>,
+    /// The write connection, `None` until it is first used.
+    writer: Mutex<Option<Opened>>,
+}
+impl ConnectionPool {
+    /// Create the pool, connections are opened as they are needed.
+    pub fn new() -> Self {
+        Self { readers: Mutex::new(vec![]), writer: Mutex::new(None) }
+    }
+    /// Get a connection used to read the database. The connection returns to the pool when it is
+    /// dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `weather_dir` is the weather data directory.
+    ///
+    pub fn reader(&self, weather_dir: &WeatherDir) -> crate::Result<PooledConnection<'_>> {
+        let file_id = file_id(weather_dir);
+        let idle = {
+            let mut readers = self.readers.lock().unwrap_or_else(PoisonError::into_inner);
+            readers.retain(|opened| opened.file_id == file_id);
+            readers.pop()
+        };
+        let opened = match idle {
+            Some(opened) => opened,
+            None => Opened { conn: weather_db_connection(weather_dir)?, file_id },
+        };
+        Ok(PooledConnection::Reader(self, Some(opened)))
+    }
+    /// Get the connection used to write the database. Other writers wait until the connection is
+    /// dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `weather_dir` is the weather data directory.
+    ///
+    pub fn writer(&self, weather_dir: &WeatherDir) -> crate::Result<PooledConnection<'_>> {
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let file_id = file_id(weather_dir);
+        if writer.as_ref().is_none_or(|opened| opened.file_id != file_id) {
+            writer.take();
+            writer.replace(Opened { conn: weather_db_connection(weather_dir)?, file_id });
+        }
+        Ok(PooledConnection::Writer(writer))
+    }
+    /// Return a read connection to the pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `opened` is the connection that is no longer being used.
+    ///
+    fn release(&self, opened: Opened) {
+        let mut readers = self.readers.lock().unwrap_or_else(PoisonError::into_inner);
+        if readers.len() < MAX_IDLE_READERS {
+            readers.push(opened);
+        }
+    }
+}
+
+/// Get the identity of the database file. The device and inode change when the file is replaced,
+/// the file size and modification time are not used since they change as the database is written.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+///
+#[cfg(unix)]
+fn file_id(weather_dir: &WeatherDir) -> FileId {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(weather_dir.file(DB_FILENAME).path()).map(|metadata| (metadata.dev(), metadata.ino())).ok()
+}
+
+/// Get the identity of the database file. Without an inode the creation time is used, it changes
+/// when the file is replaced.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+///
+#[cfg(not(unix))]
+fn file_id(weather_dir: &WeatherDir) -> FileId {
+    let created = fs::metadata(weather_dir.file(DB_FILENAME).path()).and_then(|metadata| metadata.created()).ok()?;
+    let created = created.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some((created.as_secs(), created.subsec_nanos() as u64))
+}
+
+/// A database connection borrowed from the [ConnectionPool].
+pub enum PooledConnection<'a> {
+    /// A read connection, it is `None` only after being returned to the pool.
+    Reader(&'a ConnectionPool, Option<Opened>),
+    /// The write connection, the guard is always `Some`.
+    Writer(MutexGuard<'a, Option<Opened>>),
+}
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+    fn deref(&self) -> &Self::Target {
+        match self {
+            PooledConnection::Reader(_, opened) => &opened.as_ref().unwrap().conn,
+            PooledConnection::Writer(writer) => &writer.as_ref().unwrap().conn,
+        }
+    }
+}
+impl DerefMut for PooledConnection<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            PooledConnection::Reader(_, opened) => &mut opened.as_mut().unwrap().conn,
+            PooledConnection::Writer(writer) => &mut writer.as_mut().unwrap().conn,
+        }
+    }
+}
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let PooledConnection::Reader(pool, opened) = self {
+            if let Some(opened) = opened.take() {
+                pool.release(opened);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{db::sqlite::admin::init_db, testlib};
+
+    #[test]
+    fn pool() {
+        let fixture = testlib::TestFixture::create();
+        let weather_dir = WeatherDir::try_from(fixture.to_string()).unwrap();
+        init_db(&weather_dir, false, false, 1).unwrap();
+        let pool = ConnectionPool::new();
+
+        // readers are reused
+        let reader = pool.reader(&weather_dir).unwrap();
+        let journal: String = reader.query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+        assert_eq!(journal, "wal");
+        drop(reader);
+        assert_eq!(pool.readers.lock().unwrap().len(), 1);
+        let first = pool.reader(&weather_dir).unwrap();
+        let second = pool.reader(&weather_dir).unwrap();
+        assert!(pool.readers.lock().unwrap().is_empty());
+        drop(first);
+        drop(second);
+        assert_eq!(pool.readers.lock().unwrap().len(), 2);
+
+        // a reader sees what was committed while it was open
+        let reader = pool.reader(&weather_dir).unwrap();
+        let mut writer = pool.writer(&weather_dir).unwrap();
+        let tx = writer.transaction().unwrap();
+        const LOCATION_SQL: &str = r#"
+            INSERT INTO locations (city, state_id, state, alias, longitude, latitude, tz)
+                VALUES ('Tigard', 'OR', 'Oregon', 'tigard', '-122.7717', '45.4312', 'America/Los_Angeles')
+        "#;
+        tx.execute(LOCATION_SQL, []).unwrap();
+        let count = |conn: &Connection| -> u32 {
+            conn.query_row("SELECT COUNT(*) FROM locations", [], |row| row.get(0)).unwrap()
+        };
+        assert_eq!(count(&reader), 0);
+        tx.commit().unwrap();
+        assert_eq!(count(&reader), 1);
+        drop(writer);
+
+        // connections to a replaced database are not reused
+        drop(reader);
+        let writer = pool.writer(&weather_dir).unwrap();
+        writer.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(())).unwrap();
+        drop(writer);
+        let db_file = weather_dir.file(DB_FILENAME);
+        let original_id = file_id(&weather_dir);
+        let replacement = weather_dir.file("replacement.db");
+        fs::copy(db_file.path(), replacement.path()).unwrap();
+        replacement.rename(&db_file).unwrap();
+        assert!(file_id(&weather_dir).is_some());
+        assert_ne!(file_id(&weather_dir), original_id);
+        let reader = pool.reader(&weather_dir).unwrap();
+        assert!(pool.readers.lock().unwrap().is_empty());
+        drop(reader);
+    }
+}
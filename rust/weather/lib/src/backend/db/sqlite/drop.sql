@@ -1,9 +1,10 @@
+-- remove the history full-text index
+DROP TABLE IF EXISTS history_fts;
 -- remove tables that have a foriegn key to metadata
 DROP TABLE IF EXISTS history;
-DROP TABLE IF EXISTS documents;
 -- remove tables that have a foriegn key to locations
 DROP TABLE IF EXISTS metadata;
 -- now drop the locations table
 DROP TABLE IF EXISTS locations;
--- drop the mode table
-DROP TABLE IF EXISTS config;
\ No newline at end of file
+-- drop the schema version table
+DROP TABLE IF EXISTS schema_version;
@@ -11,7 +11,9 @@ use crate::{
     backend::filesys::{HistoryArchive, WeatherDir},
     entities::{DailyHistories, DateRange, History, HistorySummaries, Location, LocationFilters},
 };
+use chrono::NaiveDate;
 use rusqlite::{named_params, Connection, Row, Transaction};
+use std::collections::HashMap;
 
 /// Create a database history specific error message.
 macro_rules! error {
@@ -172,6 +174,79 @@ pub fn get(conn: &mut Connection, location: Location, date_range: DateRange) ->
     Ok(DailyHistories { location, histories })
 }
 
+/// Search the weather history descriptions of locations. Locations without matching histories are
+/// not returned.
+///
+/// # Arguments
+///
+/// * `conn` is the database connection that will be used.
+/// * `filters` identifies the locations that will be searched.
+/// * `query` is the `FTS5` full-text query used to match history descriptions.
+/// * `date_range` restricts the history dates searched, if `None` all history is searched.
+///
+pub fn search(
+    conn: &Connection,
+    filters: LocationFilters,
+    query: &str,
+    date_range: Option<DateRange>,
+) -> crate::Result<Vec<DailyHistories>> {
+    let locations = locations::get(conn, filters)?;
+    if locations.is_empty() {
+        return Ok(vec![]);
+    }
+    const SEARCH_SQL: &str = r#"
+        SELECT
+            l.alias AS alias, m.date AS date,
+            h.temp_high AS temp_high, h.temp_low AS temp_low, h.temp_mean AS temp_mean,
+            h.dew_point AS dew_point, h.humidity AS humidity,
+            h.sunrise_t AS sunrise_t, h.sunset_t AS sunset_t,
+            h.cloud_cover AS cloud_cover, h.moon_phase AS moon_phase, h.uv_index AS uv_index,
+            h.wind_speed AS wind_speed, h.wind_gust AS wind_gust, h.wind_dir AS wind_dir,
+            h.visibility as visibility, h.pressure as pressure,
+            h.precip as precip, h.precip_prob as precip_prob, h.precip_type as precip_type,
+            h.description AS description
+        FROM history_fts AS f
+            INNER JOIN history AS h ON f.rowid=h.id
+            INNER JOIN metadata AS m ON h.mid=m.id
+            INNER JOIN locations AS l ON m.lid=l.id
+        WHERE
+            history_fts MATCH :query
+            AND (:from IS NULL OR m.date >= :from)
+            AND (:thru IS NULL OR m.date <= :thru)
+        ORDER BY alias, date
+    "#;
+    let mut stmt = prepare_sql!(conn, SEARCH_SQL, "failed to prepare history search query")?;
+    let from = date_range.as_ref().map(|date_range| date_range.start);
+    let thru = date_range.as_ref().map(|date_range| date_range.end);
+    let params = named_params![":query": query, ":from": from, ":thru": thru];
+    let mut rows = query_rows!(stmt, params, "'{}' history search failed", query)?;
+    let mut alias_histories: HashMap<String, Vec<History>> = HashMap::new();
+    loop {
+        match rows.next() {
+            Ok(None) => break,
+            Err(error) => err!("'{}' is not a valid search: {:?}", query, error)?,
+            Ok(Some(row)) => {
+                let alias: String = match row.get("alias") {
+                    Ok(alias) => alias,
+                    Err(error) => err!("failed to get the search alias: {:?}", error)?,
+                };
+                match row_to_history(&alias, row) {
+                    Ok(history) => alias_histories.entry(alias).or_default().push(history),
+                    Err(error) => err!("failed to create history from row: {:?}", error)?,
+                }
+            }
+        }
+    }
+    let daily_histories = locations
+        .into_iter()
+        .filter_map(|location| {
+            let histories = alias_histories.remove(&location.alias)?;
+            Some(DailyHistories { location, histories })
+        })
+        .collect();
+    Ok(daily_histories)
+}
+
 /// Create history from the database.
 ///
 /// # Arguments
@@ -235,6 +310,29 @@ pub fn summary(
     Ok(history_summaries)
 }
 
+/// Remove location histories and their metadata.
+///
+/// # Arguments
+///
+/// * `conn` is the database connection that will be used.
+/// * `lid` is the location id.
+/// * `dates` are the history dates that will be removed.
+pub(super) fn remove(conn: &mut Connection, lid: i64, dates: &[NaiveDate]) -> crate::Result<()> {
+    const SQL: &str = r#"
+        DELETE FROM history
+        WHERE mid IN (SELECT id FROM metadata WHERE lid = :lid AND date = :date)
+        "#;
+    let tx = create_tx!(conn, "failed to create remove transaction")?;
+    for date in dates {
+        let mut stmt = prepare_cached_sql!(tx, SQL, "failed to prepare delete SQL")?;
+        let params = named_params! {":lid": lid, ":date": date};
+        execute_sql!(stmt, params, "failed to delete history for lid={lid} on {date}")?;
+        drop(stmt);
+        metadata::delete_date(&tx, lid, date)?;
+    }
+    commit_tx!(tx, "failed to commit remove for lid={lid}")
+}
+
 /// Reload a locations weather history for the *normalized* implementation of weather data.
 ///
 /// # Argument
@@ -264,3 +362,116 @@ pub(super) fn reload(conn: &mut Connection, weather_dir: &WeatherDir, alias: &st
     }
     commit_tx!(tx, "failed to commit reload for '{alias}'")
 }
+
+/// Copy histories from the location archive into the database.
+///
+/// # Argument
+///
+/// * `conn` is the database connection that will be used.
+/// * `weather_dir` is the weather data directory.
+/// * `alias` is the location whose histories will be copied.
+/// * `dates` are the sorted dates of the histories to copy.
+pub(super) fn copy_from_archive(
+    conn: &mut Connection,
+    weather_dir: &WeatherDir,
+    alias: &str,
+    dates: &[NaiveDate],
+) -> crate::Result<()> {
+    if dates.is_empty() {
+        return Ok(());
+    }
+    let size = estimate_size(conn, "history")?;
+    let lid = locations::location_id(conn, alias)?;
+    let archive = HistoryArchive::open(alias, weather_dir.archive(alias))?;
+    let mut tx = create_tx!(conn, "failed to create copy transaction")?;
+    for (md, history) in archive.metadata_and_history()? {
+        if dates.binary_search(&md.date).is_ok() {
+            insert_history(&mut tx, lid, size, md.compressed_size as usize, &history)?;
+        }
+    }
+    commit_tx!(tx, "failed to commit copy for '{alias}'")
+}
+
+/// Copy histories from the database into the location archive.
+///
+/// # Argument
+///
+/// * `conn` is the database connection that will be used.
+/// * `weather_dir` is the weather data directory.
+/// * `location` is whose histories will be copied.
+/// * `dates` are the sorted dates of the histories to copy.
+pub(super) fn copy_to_archive(
+    conn: &mut Connection,
+    weather_dir: &WeatherDir,
+    location: &Location,
+    dates: &[NaiveDate],
+) -> crate::Result<()> {
+    let (first, last) = match (dates.first(), dates.last()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => return Ok(()),
+    };
+    let alias = &location.alias;
+    let lid = locations::location_id(conn, alias)?;
+    let mut histories = get(conn, location.clone(), DateRange::new(first, last))?.histories;
+    histories.retain(|history| dates.binary_search(&history.date).is_ok());
+    let archive = HistoryArchive::open(alias, weather_dir.archive(alias))?;
+    let added_dates = archive.append(&histories)?;
+    // the archive sizes were not known when the histories were added to the database
+    let tx = create_tx!(conn, "failed to create copy transaction")?;
+    for md in archive.metadata_by_dates(added_dates)? {
+        metadata::update_store_size(&tx, lid, &md.date, md.compressed_size as usize)?;
+    }
+    commit_tx!(tx, "failed to commit copy for '{alias}'")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{db::sqlite::admin::init_db, testlib};
+
+    #[test]
+    fn search_histories() {
+        let fixture = testlib::TestFixture::create();
+        let weather_dir = WeatherDir::try_from(fixture.to_string()).unwrap();
+        init_db(&weather_dir, false, false, 1).unwrap();
+        let location = testlib::location("Beaverton", "beaverton");
+        let history = |day: u32, description: &str| History {
+            alias: "beaverton".to_string(),
+            date: NaiveDate::from_ymd_opt(2025, 1, day).unwrap(),
+            description: Some(description.to_string()),
+            ..Default::default()
+        };
+        let mut conn = super::super::db_conn!(&weather_dir).unwrap();
+        locations::add(&mut conn, location.clone(), &weather_dir).unwrap();
+        let histories = vec![
+            history(1, "Freezing fog in the morning."),
+            history(2, "Clear conditions throughout the day."),
+            history(3, "Rain and thunderstorms in the afternoon."),
+            history(4, "Fog clearing by noon with thunderstorms later."),
+        ];
+        add(&mut conn, &weather_dir, DailyHistories { location, histories }).unwrap();
+        let dates = |daily_histories: Vec<DailyHistories>| -> Vec<u32> {
+            use chrono::Datelike;
+            daily_histories.into_iter().flat_map(|dh| dh.histories).map(|history| history.date.day()).collect()
+        };
+
+        let found = search(&conn, LocationFilters::default(), "thunderstorms", None).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].location.alias, "beaverton");
+        assert_eq!(dates(found), vec![3, 4]);
+        assert_eq!(dates(search(&conn, LocationFilters::default(), "freezing fog", None).unwrap()), vec![1]);
+        assert_eq!(dates(search(&conn, LocationFilters::default(), "\"freezing fog\"", None).unwrap()), vec![1]);
+        let date_range =
+            DateRange::new(NaiveDate::from_ymd_opt(2025, 1, 2).unwrap(), NaiveDate::from_ymd_opt(2025, 1, 3).unwrap());
+        let found = search(&conn, LocationFilters::default(), "thunderstorms", Some(date_range)).unwrap();
+        assert_eq!(dates(found), vec![3]);
+        assert!(search(&conn, LocationFilters::default(), "snow", None).unwrap().is_empty());
+        assert!(search(&conn, LocationFilters::default(), "\"unbalanced", None).is_err());
+
+        // the index follows the history when it is reloaded
+        reload(&mut conn, &weather_dir, "beaverton").unwrap();
+        assert_eq!(dates(search(&conn, LocationFilters::default(), "fog", None).unwrap()), vec![1, 4]);
+        let count: usize = conn.query_row("SELECT COUNT(*) FROM history_fts", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 4);
+    }
+}
@@ -4,7 +4,7 @@
 use super::{locations, metadata, prepare_sql, query_rows, SqlResult};
 use crate::entities::{DateRanges, HistoryDates, LocationFilters};
 use chrono::NaiveDate;
-use rusqlite::{named_params, Connection, Row};
+use rusqlite::{named_params, params_from_iter, Connection, Row};
 use sql_query_builder as sql;
 use std::collections::HashMap;
 
@@ -64,13 +64,13 @@ fn query_history_dates(conn: &Connection, aliases: Vec<&str>) -> crate::Result<H
         .from("locations AS l")
         .inner_join("metadata AS m ON l.id = m.lid")
         .order_by("l.alias, m.date");
-    for alias in aliases {
-        query = query.where_or(&format!("l.alias = '{}'", alias));
+    for index in 1..=aliases.len() {
+        query = query.where_or(&format!("l.alias = ?{}", index));
     }
 
     // execute the query
     let mut stmt = prepare_sql!(conn, &query.to_string(), "failed to prepare history dates query")?;
-    let mut rows = query_rows!(stmt, [], "failed to query history dates")?;
+    let mut rows = query_rows!(stmt, params_from_iter(aliases), "failed to query history dates")?;
 
     // collect the location dates
     let mut location_dates: Vec<(String, Vec<NaiveDate>)> = vec![];
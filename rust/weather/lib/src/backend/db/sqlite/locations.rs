@@ -2,12 +2,12 @@
 
 use crate::{
     backend::{
-        db::sqlite::{create_tx, execute_sql, prepare_sql, query_rows, SqlResult},
+        db::sqlite::{commit_tx, create_tx, execute_sql, prepare_sql, query_rows, SqlResult},
         filesys::{self, WeatherDir}
     },
     entities::{Location, LocationFilters},
 };
-use rusqlite::{named_params, Connection, Row};
+use rusqlite::{named_params, params_from_iter, Connection, Row};
 use sql_query_builder as sql;
 
 /// Create a database locations specific error message.
@@ -37,19 +37,27 @@ pub fn add(conn: &mut Connection, mut location: Location, weather_dir: &WeatherD
     // add the location to the filesys first
     let locations = filesys::Locations::open(weather_dir)?;
     location = locations.add(location)?;
+    insert(conn, &location)
+}
 
-    // not sure that you need a transaction here but use one anyway
-    let tx = create_tx!(conn, "failed getting transaction")?;
+/// Add a location that is already in the location file to the database.
+///
+/// # Arguments
+///
+/// * `conn` is the database connection that will be used.
+/// * `location` is what will be added.
+///
+pub fn insert(conn: &Connection, location: &Location) -> crate::Result<()> {
     const SQL: &str = r#"
         INSERT INTO locations (city, state, state_id, alias, latitude, longitude, tz)
-            VALUES (:city, :state, :state_id, :alias, :latitude, :longitude, tz)
+            VALUES (:city, :state, :state_id, :alias, :latitude, :longitude, :tz)
         "#;
-    let mut stmt = prepare_sql!(tx, SQL, "failed to prepare insert SQL")?;
-    let alias = location.alias.clone();
+    let mut stmt = prepare_sql!(conn, SQL, "failed to prepare insert SQL")?;
+    let alias = &location.alias;
     let params = named_params! {
         ":city": location.city,
         ":state": location.state,
-        ":state_id": location.state,
+        ":state_id": location.state_id,
         ":alias": location.alias,
         ":latitude": location.latitude,
         ":longitude": location.longitude,
@@ -67,9 +75,9 @@ pub fn add(conn: &mut Connection, mut location: Location, weather_dir: &WeatherD
 ///
 pub fn get(conn: &Connection, filters: LocationFilters) -> crate::Result<Vec<Location>> {
     // run the query
-    let sql = get_query(filters);
+    let (sql, params) = get_query(filters);
     let mut stmt = prepare_sql!(conn, &sql, "failed to prepare query SQL")?;
-    let mut rows = query_rows!(stmt, [], "failed to execute query")?;
+    let mut rows = query_rows!(stmt, params_from_iter(params), "failed to execute query")?;
 
     let mut locations = vec![];
     loop {
@@ -100,51 +108,54 @@ pub fn get(conn: &Connection, filters: LocationFilters) -> crate::Result<Vec<Loc
     Ok(locations)
 }
 
-fn get_query(location_filters: LocationFilters) -> String {
+/// Create the locations query and the values bound to its parameters. Filter wildcards (`*`) are
+/// converted to `LIKE` wildcards.
+///
+/// # Arguments
+///
+/// * `location_filters` determines what locations will be returned.
+///
+fn get_query(location_filters: LocationFilters) -> (String, Vec<String>) {
+    #[inline]
+    fn bind(params: &mut Vec<String>, value: &str) -> String {
+        params.push(value.replace("*", "%"));
+        format!("?{}", params.len())
+    }
     #[inline]
-    fn like_city(value: &str) -> String {
-        format!("city LIKE '{}'", value.replace("*", "%"))
+    fn like_city(params: &mut Vec<String>, city: &str) -> String {
+        format!("city LIKE {}", bind(params, city))
     }
     #[inline]
-    fn like_state(state: &str) -> String {
-        let state = state.replace("*", "%");
-        format!("(state LIKE '{state}' OR state_id LIKE '{state}')")
+    fn like_state(params: &mut Vec<String>, state: &str) -> String {
+        let state = bind(params, state);
+        format!("(state LIKE {state} OR state_id LIKE {state})")
     }
     #[inline]
-    fn like_name(name: &str) -> String {
-        let name = name.replace("*", "%");
-        format!("(name LIKE '{name}' OR alias LIKE '{name}')")
+    fn like_name(params: &mut Vec<String>, name: &str) -> String {
+        let name = bind(params, name);
+        format!("(name LIKE {name} OR alias LIKE {name})")
     }
+    let mut params = vec![];
     let mut query =
         sql::Select::new().from("locations").select("city, state, state_id, alias, latitude, longitude, tz");
     for filter in location_filters {
-        match (&filter.city, &filter.state, &filter.name) {
-            (Some(city), None, None) => {
-                query = query.where_or(&like_city(city));
-            }
-            (None, Some(state), None) => {
-                query = query.where_or(&like_state(state));
-            }
-            (None, None, Some(name)) => {
-                query = query.where_or(&like_name(name));
-            }
-            (Some(city), Some(state), None) => {
-                query = query.where_or(&format!("({} AND {})", like_city(city), like_state(state)));
-            }
-            (Some(city), None, Some(name)) => {
-                query = query.where_or(&format!("({} AND {})", like_city(city), like_name(name)));
-            }
-            (None, Some(state), Some(name)) => {
-                query = query.where_or(&format!("({} AND {})", like_state(state), like_name(name)));
-            }
-            (Some(city), Some(state), Some(name)) => {
-                query =
-                    query.where_or(&format!("({} AND {} AND {})", like_city(city), like_state(state), like_name(name)));
-            }
-            _ => (),
+        let mut conditions = vec![];
+        if let Some(city) = &filter.city {
+            conditions.push(like_city(&mut params, city));
+        }
+        if let Some(state) = &filter.state {
+            conditions.push(like_state(&mut params, state));
+        }
+        if let Some(name) = &filter.name {
+            conditions.push(like_name(&mut params, name));
+        }
+        match conditions.len() {
+            0 => (),
+            1 => query = query.where_or(&conditions[0]),
+            _ => query = query.where_or(&format!("({})", conditions.join(" AND "))),
         }
     }
-    query.order_by("city, state_id ASC").to_string()
+    (query.order_by("city, state_id ASC").to_string(), params)
 }
 
 /// Get the location id and alias.
@@ -206,24 +217,13 @@ pub fn location_id(conn: &Connection, alias: &str) -> crate::Result<i64> {
 /// * `weather_dir` is the weather data directory.
 ///
 pub fn load(conn: &mut Connection, weather_dir: &WeatherDir) -> crate::Result<()> {
-    let mut insert =
-        sql::Insert::new().insert_into("locations (city, state, state_id, alias, latitude, longitude, tz)");
+    let tx = create_tx!(conn, "failed to create load transaction")?;
+    let mut rows_inserted = 0;
     for location in filesys::Locations::open(weather_dir)?.get()? {
-        insert = insert.values(&format!(
-            "('{}', '{}', '{}', '{}', '{}', '{}', '{}')",
-            location.city,
-            location.state,
-            location.state_id,
-            location.alias,
-            location.latitude,
-            location.longitude,
-            location.tz
-        ));
+        insert(&tx, &location)?;
+        rows_inserted += 1;
     }
-    let rows_inserted = match conn.execute(&insert.to_string(), []) {
-        Ok(rows_inserted) => rows_inserted,
-        Err(error) => err!("failed to insert the locations: {:?}", error)?,
-    };
+    commit_tx!(tx, "failed to commit the locations")?;
     log::debug!("{} locations added.", rows_inserted);
     Ok(())
 }
@@ -282,4 +282,53 @@ mod tests {
     //         ])
     //     );
     // }
+
+    use super::*;
+    use crate::{
+        backend::{db::sqlite::history, testlib},
+        location_filter, location_filters,
+    };
+
+    #[test]
+    fn quoted_filters() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::backend::db::sqlite::migrations::migrate(&mut conn).unwrap();
+        let idaho = |city: &str, alias: &str| Location {
+            state_id: "ID".to_string(),
+            state: "Idaho".to_string(),
+            name: format!("{}, ID", city),
+            ..testlib::location(city, alias)
+        };
+        insert(&conn, &idaho("Coeur d'Alene", "o'alene")).unwrap();
+        insert(&conn, &idaho("Boise", "boise")).unwrap();
+        insert(&conn, &testlib::location("Bend", "bend")).unwrap();
+        let aliases = |filters| get(&conn, filters).unwrap().into_iter().map(|l| l.alias).collect::<Vec<_>>();
+
+        assert_eq!(aliases(location_filters![location_filter!(city = "coeur d'*")]), vec!["o'alene"]);
+        assert_eq!(aliases(location_filters![location_filter!(name = "o'alene")]), vec!["o'alene"]);
+        assert_eq!(aliases(location_filters![location_filter!(city = "b*", state = "id")]), vec!["boise"]);
+        assert_eq!(
+            aliases(location_filters![location_filter!(city = "b*", state = "or"), location_filter!(name = "boise")]),
+            vec!["bend", "boise"]
+        );
+        assert!(aliases(location_filters![location_filter!(city = "x' OR '1'='1")]).is_empty());
+
+        // the history dates query binds the aliases
+        let history_dates = history::history_dates(&conn, location_filters![location_filter!(name = "o*")]).unwrap();
+        assert_eq!(history_dates.len(), 1);
+        assert!(history_dates[0].history_dates.is_empty());
+
+        // the locations file is loaded with bound values
+        let fixture = testlib::TestFixture::create();
+        let weather_dir = WeatherDir::try_from(fixture.to_string()).unwrap();
+        let locations = filesys::Locations::open(&weather_dir).unwrap();
+        locations.add(idaho("Coeur d'Alene", "o'alene")).unwrap();
+        locations.add(idaho("Boise", "boise")).unwrap();
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::backend::db::sqlite::migrations::migrate(&mut conn).unwrap();
+        load(&mut conn, &weather_dir).unwrap();
+        let aliases = |filters| get(&conn, filters).unwrap().into_iter().map(|l| l.alias).collect::<Vec<_>>();
+        assert_eq!(aliases(location_filters![location_filter!(city = "coeur d'*")]), vec!["o'alene"]);
+        assert_eq!(aliases(location_filters![]), vec!["boise", "o'alene"]);
+    }
 }
@@ -1,8 +1,8 @@
 //! This module manages the metadata surrounding weather data history.
 //!
-use super::{execute_sql, prepare_cached_sql, prepare_sql};
+use super::{execute_sql, prepare_cached_sql, prepare_sql, query_rows, SqlResult};
 use chrono::NaiveDate;
-use rusqlite::{named_params, Transaction};
+use rusqlite::{named_params, Connection, Row, Transaction};
 
 pub const TABLE_NAME: &'static str = "metadata";
 
@@ -42,6 +42,65 @@ pub fn insert(tx: &Transaction, lid: i64, date: &NaiveDate, store_size: usize, s
     Ok(tx.last_insert_rowid())
 }
 
+/// Get the history dates and archive sizes for a location id.
+///
+/// # Arguments
+///
+/// * `conn` is the database connection that will be used.
+/// * `lid` is the location id.
+///
+pub fn get(conn: &Connection, lid: i64) -> crate::Result<Vec<(NaiveDate, usize)>> {
+    const SQL: &str = "SELECT date, store_size FROM metadata WHERE lid=:lid ORDER BY date";
+    let mut stmt = prepare_sql!(conn, SQL, "failed to prepare metadata SQL")?;
+    let mut rows = query_rows!(stmt, named_params! {":lid": lid}, "failed to query metadata for lid={lid}")?;
+    let mut metadata = vec![];
+    loop {
+        let row = match rows.next() {
+            Ok(Some(row)) => row,
+            Ok(None) => break,
+            Err(error) => err!("failed to get next metadata row: {:?}", error)?,
+        };
+        fn next_metadata(row_: &Row) -> SqlResult<(NaiveDate, Option<usize>)> {
+            Ok((row_.get(0)?, row_.get(1)?))
+        }
+        match next_metadata(row) {
+            Ok((date, store_size)) => metadata.push((date, store_size.unwrap_or_default())),
+            Err(error) => err!("failed to get metadata for lid={lid}: {:?}", error)?,
+        }
+    }
+    Ok(metadata)
+}
+
+/// Update the archive size of a history.
+///
+/// # Arguments
+///
+/// * `tx` is the database transaction that will be used.
+/// * `lid` is the location id.
+/// * `date` is the history date.
+/// * `store_size` is the size of the history in the archive.
+///
+pub fn update_store_size(tx: &Transaction, lid: i64, date: &NaiveDate, store_size: usize) -> crate::Result<()> {
+    const SQL: &str = "UPDATE metadata SET store_size=:store_size WHERE lid=:lid AND date=:date";
+    let mut stmt = prepare_cached_sql!(tx, SQL, "failed to prepare update SQL")?;
+    let params = named_params![":store_size": store_size, ":lid": lid, ":date": date];
+    execute_sql!(stmt, params, "failed to update metadata for lid={lid} on {date}")
+}
+
+/// Remove the metadata of a location history.
+///
+/// # Arguments
+///
+/// * `tx` is the database transaction that will be used.
+/// * `lid` is the location id.
+/// * `date` is the history date.
+///
+pub fn delete_date(tx: &Transaction, lid: i64, date: &NaiveDate) -> crate::Result<()> {
+    const SQL: &str = "DELETE FROM metadata WHERE lid=:lid AND date=:date";
+    let mut stmt = prepare_cached_sql!(tx, SQL, "failed to prepare delete date SQL")?;
+    execute_sql!(stmt, named_params! {":lid": lid, ":date": date}, "failed to delete metadata for lid={lid} on {date}")
+}
+
 /// Remove all metadata associated with a location id.
 ///
 /// # Arguments
@@ -0,0 +1,166 @@
+//! The database schema versions and the migrations that create them.
+//!
+//! Each migration moves the schema up one version. Migrations that have not been applied are run
+//! in order, each in its own transaction, and recorded in the `schema_version` table.
+
+use super::{commit_tx, create_tx};
+use rusqlite::{named_params, Connection, OptionalExtension};
+
+/// Create a database migration specific error message.
+macro_rules! error {
+    ($($arg:tt)*) => {
+        crate::Error::from(format!("SQLite migration {}", format!($($arg)*)))
+    }
+}
+
+/// Create an error from the migration specific error message.
+macro_rules! err {
+    ($($arg:tt)*) => {
+        Err(error!($($arg)*))
+    };
+}
+
+/// A step that changes the database schema.
+struct Migration {
+    /// The schema version after the migration has been applied.
+    version: u32,
+    /// What the migration does.
+    description: &'static str,
+    /// The SQL statements that change the schema.
+    sql: &'static str,
+}
+
+/// The schema migrations in version order.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create the locations, metadata, and history tables",
+        sql: include_str!("migrations/v001_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        description: "create the history description full-text index",
+        sql: include_str!("migrations/v002_history_fts.sql"),
+    },
+];
+
+/// The schema version this version of weather data uses.
+pub const SCHEMA_VERSION: u32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+/// The table that records the applied migrations.
+const VERSION_TABLE_SQL: &str = r#"
+    CREATE TABLE IF NOT EXISTS schema_version
+    (
+        version INTEGER PRIMARY KEY,
+        description TEXT NOT NULL,
+        applied TEXT NOT NULL
+    )
+"#;
+
+/// Get the schema version of the database. Databases created before schema versions were tracked
+/// are version `0`.
+///
+/// # Arguments
+///
+/// * `conn` is the database connection that will be used.
+///
+pub fn version(conn: &Connection) -> crate::Result<u32> {
+    const EXISTS_SQL: &str = "SELECT 1 FROM sqlite_master WHERE type='table' AND name='schema_version'";
+    let exists: Option<u32> = match conn.query_row(EXISTS_SQL, [], |row| row.get(0)).optional() {
+        Ok(exists) => exists,
+        Err(error) => err!("failed to find the schema version table: {:?}", error)?,
+    };
+    if exists.is_none() {
+        return Ok(0);
+    }
+    const VERSION_SQL: &str = "SELECT MAX(version) FROM schema_version";
+    match conn.query_row(VERSION_SQL, [], |row| row.get::<_, Option<u32>>(0)) {
+        Ok(version) => Ok(version.unwrap_or_default()),
+        Err(error) => err!("failed to get the schema version: {:?}", error),
+    }
+}
+
+/// Tests if the database schema needs to be migrated. An error is returned if the database was
+/// created by a newer version of weather data.
+///
+/// # Arguments
+///
+/// * `conn` is the database connection that will be used.
+///
+pub fn is_pending(conn: &Connection) -> crate::Result<bool> {
+    let version = version(conn)?;
+    if version > SCHEMA_VERSION {
+        err!(
+            "the database schema is version {} but this weather supports version {}, upgrade weather to use it.",
+            version,
+            SCHEMA_VERSION
+        )?;
+    }
+    Ok(version < SCHEMA_VERSION)
+}
+
+/// Apply the migrations the database schema is missing. The schema version is returned.
+///
+/// # Arguments
+///
+/// * `conn` is the database connection that will be used.
+///
+pub fn migrate(conn: &mut Connection) -> crate::Result<u32> {
+    if !is_pending(conn)? {
+        return Ok(SCHEMA_VERSION);
+    }
+    if let Err(error) = conn.execute_batch(VERSION_TABLE_SQL) {
+        err!("failed to create the schema version table: {:?}", error)?;
+    }
+    let current = version(conn)?;
+    for migration in MIGRATIONS.iter().filter(|migration| migration.version > current) {
+        log::info!("Migrating the database schema to version {} ({}).", migration.version, migration.description);
+        let tx = create_tx!(conn, "failed to create the migration transaction")?;
+        if let Err(error) = tx.execute_batch(migration.sql) {
+            err!("version {} failed: {:?}", migration.version, error)?;
+        }
+        const INSERT_SQL: &str = r#"
+            INSERT INTO schema_version (version, description, applied)
+                VALUES (:version, :description, datetime('now'))
+        "#;
+        let params = named_params! {":version": migration.version, ":description": migration.description};
+        if let Err(error) = tx.execute(INSERT_SQL, params) {
+            err!("failed to record version {}: {:?}", migration.version, error)?;
+        }
+        commit_tx!(tx, "failed to commit version {}", migration.version)?;
+    }
+    Ok(SCHEMA_VERSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrations() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(version(&conn).unwrap(), 0);
+        assert!(is_pending(&conn).unwrap());
+        assert_eq!(migrate(&mut conn).unwrap(), SCHEMA_VERSION);
+        assert_eq!(version(&conn).unwrap(), SCHEMA_VERSION);
+        assert!(!is_pending(&conn).unwrap());
+        assert_eq!(migrate(&mut conn).unwrap(), SCHEMA_VERSION);
+
+        // a database created before versions were tracked
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0].sql).unwrap();
+        const LOCATION_SQL: &str = r#"
+            INSERT INTO locations (city, state_id, state, alias, longitude, latitude, tz)
+                VALUES ('Tigard', 'OR', 'Oregon', 'tigard', '-122.7717', '45.4312', 'America/Los_Angeles')
+        "#;
+        conn.execute(LOCATION_SQL, []).unwrap();
+        assert_eq!(migrate(&mut conn).unwrap(), SCHEMA_VERSION);
+        let count: u32 = conn.query_row("SELECT COUNT(*) FROM locations", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+
+        // a database from a newer version
+        conn.execute("INSERT INTO schema_version VALUES (999, 'future', datetime('now'))", []).unwrap();
+        let error = migrate(&mut conn).unwrap_err().to_string();
+        assert!(error.contains("version 999"), "{}", error);
+    }
+}
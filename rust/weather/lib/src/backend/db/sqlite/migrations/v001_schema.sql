@@ -1,4 +1,5 @@
-BEGIN;
+-- The initial weather data schema. The statements are safe to run on databases created before
+-- schema versions were tracked.
 
 -- The weather locations table
 CREATE TABLE IF NOT EXISTS locations
@@ -73,5 +74,3 @@ CREATE TABLE IF NOT EXISTS history
 );
 -- cover the metadata id with an index
 CREATE INDEX IF NOT EXISTS idx_history_mid on history(mid);
-
-COMMIT;
@@ -0,0 +1,30 @@
+-- The full-text search index over the daily weather descriptions. The index does not keep a copy of
+-- the descriptions, it reads them from the history table.
+CREATE VIRTUAL TABLE IF NOT EXISTS history_fts USING fts5
+(
+    description,
+    content='history',
+    content_rowid='id'
+);
+
+-- add descriptions to the index as history is added
+CREATE TRIGGER IF NOT EXISTS history_fts_insert AFTER INSERT ON history
+BEGIN
+    INSERT INTO history_fts (rowid, description) VALUES (new.id, new.description);
+END;
+
+-- remove descriptions from the index as history is deleted
+CREATE TRIGGER IF NOT EXISTS history_fts_delete AFTER DELETE ON history
+BEGIN
+    INSERT INTO history_fts (history_fts, rowid, description) VALUES ('delete', old.id, old.description);
+END;
+
+-- keep the index current if a description changes
+CREATE TRIGGER IF NOT EXISTS history_fts_update AFTER UPDATE OF description ON history
+BEGIN
+    INSERT INTO history_fts (history_fts, rowid, description) VALUES ('delete', old.id, old.description);
+    INSERT INTO history_fts (rowid, description) VALUES (new.id, new.description);
+END;
+
+-- index the history that already exists
+INSERT INTO history_fts (history_fts) VALUES ('rebuild');
@@ -92,3 +92,39 @@ pub fn db_metrics(weather_dir: &WeatherDir) -> crate::Result<UsCityDetails> {
         Ok(UsCityDetails { db_size: 0, state_info: Vec::with_capacity(0) })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{backend::testlib, entities::CityFilter};
+    use std::fs;
+
+    #[test]
+    fn quoted_filters() {
+        let fixture = testlib::TestFixture::create();
+        let weather_dir = WeatherDir::try_from(fixture.to_string()).unwrap();
+        let csv_file = PathBuf::from(&fixture).join("uscities.csv");
+        let csv = [
+            r#""city","city_ascii","state_id","state_name","county_fips","county_name","lat","lng","population","#,
+            r#""density","source","military","incorporated","timezone","ranking","zips","id""#,
+            "\n",
+            r#""Coeur d'Alene","Coeur d'Alene","ID","Idaho","16055","Kootenai","47.7041","-116.7935","54628","#,
+            r#""1060","polygon","FALSE","TRUE","America/Los_Angeles","2","83814 83815","1840018402""#,
+            "\n",
+            r#""Boise","Boise","ID","Idaho","16001","Ada","43.6007","-116.2312","235421","#,
+            r#""1139","polygon","FALSE","TRUE","America/Boise","1","83702 83814","1840027142""#,
+        ];
+        fs::write(&csv_file, csv.concat()).unwrap();
+        create(&weather_dir, &csv_file.display().to_string()).unwrap();
+        let conn = open(&weather_dir).unwrap();
+        let cities = |filter| get_cities(&conn, filter).unwrap().into_iter().map(|l| l.city).collect::<Vec<_>>();
+
+        let filter = CityFilter { name: Some("coeur d'*".to_string()), ..Default::default() };
+        assert_eq!(cities(filter), vec!["Coeur d'Alene"]);
+        let zip_code = Some("83814".to_string());
+        let filter = CityFilter { state: Some("id".to_string()), zip_code, ..Default::default() };
+        assert_eq!(cities(filter), vec!["Boise", "Coeur d'Alene"]);
+        let filter = CityFilter { name: Some("x' OR '1'='1".to_string()), ..Default::default() };
+        assert!(cities(filter).is_empty());
+    }
+}
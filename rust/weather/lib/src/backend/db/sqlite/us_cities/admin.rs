@@ -6,7 +6,7 @@ use crate::{
     LogElapsedTime
 };
 use csv::{Reader, StringRecord};
-use rusqlite::{named_params, Connection, Row, Statement, Transaction, };
+use rusqlite::{named_params, params_from_iter, Connection, Row, Statement, Transaction, };
 use sql_query_builder as sql;
 use std::path::PathBuf;
 
@@ -169,11 +169,14 @@ fn insert_states(tx: &Transaction, cities: &Vec<City>) -> crate::Result<Vec<Stat
     rows.iter_mut().zip(1i64..).for_each(|(row, id)| row.id = id);
 
     let mut insert = sql::Insert::new().insert_into("states(id, name, state_id)");
+    let mut params = vec![];
     for row in rows.iter() {
-        insert = insert.values(&format!("({}, '{}', '{}')", row.id, row.state.name, row.state.state_id))
+        params.push(&row.state.name);
+        params.push(&row.state.state_id);
+        insert = insert.values(&format!("({}, ?{}, ?{})", row.id, params.len() - 1, params.len()))
     }
     let mut insert_stmt = prepare_sql!(tx, &insert.to_string(), "failed to prepare states INSERT SQL")?;
-    execute_sql!(insert_stmt, [], "failed to insert states")?;
+    execute_sql!(insert_stmt, params_from_iter(params), "failed to insert states")?;
     Ok(rows)
 }
 
@@ -249,11 +252,11 @@ impl<'t> CityWriter<'t> {
         if !city.zip_codes.is_empty() {
             let mut insert = sql::Insert::new().insert_or("IGNORE into zip_codes(zip_code)");
             let zip_codes = city.zip_codes.split_whitespace().collect::<Vec<_>>();
-            for zip_code in &zip_codes {
-                insert = insert.values(&format!("('{zip_code}')"));
+            for index in 1..=zip_codes.len() {
+                insert = insert.values(&format!("(?{index})"));
             }
             let mut stmt = prepare_sql!(self.tx, &insert.to_string(), "failed to prepare INSERT Zip code SQL")?;
-            execute_sql!(stmt, [], "failed to INSERT zip codes")?;
+            execute_sql!(stmt, params_from_iter(&zip_codes), "failed to INSERT zip codes")?;
             self.insert_city_zip_codes(city_id, zip_codes)?;
         }
         Ok(())
@@ -268,10 +271,10 @@ impl<'t> CityWriter<'t> {
     ///
     fn insert_city_zip_codes(&self, city_id: i64, zip_codes: Vec<&str>) -> crate::Result<()> {
         // query the zip code row ids
-        let zip_codes = zip_codes.into_iter().map(|s| format!("'{s}'")).collect::<Vec<_>>().join(",");
-        let query = format!("SELECT id FROM zip_codes WHERE zip_code IN ({zip_codes})");
+        let placeholders = (1..=zip_codes.len()).map(|index| format!("?{index}")).collect::<Vec<_>>().join(",");
+        let query = format!("SELECT id FROM zip_codes WHERE zip_code IN ({placeholders})");
         let mut query_stmt = prepare_sql!(self.tx, &query, "failed to prepare SELECT Zip code id SQL")?;
-        let mut rows = query_rows!(query_stmt, [], "failed to get Zip code IDs")?;
+        let mut rows = query_rows!(query_stmt, params_from_iter(zip_codes), "failed to get Zip code IDs")?;
 
         // create the insert statement
         let mut insert = sql::Insert::new().insert_into("city_zip_codes(cities_id, zip_codes_id)");
@@ -5,7 +5,7 @@ use crate::{
     entities::{CityFilter, Location, State},
     log_elapsed_time,
 };
-use rusqlite::{Connection, Row};
+use rusqlite::{params_from_iter, Connection, Row};
 use sql_query_builder as sql;
 
 /// Get a collection of US City metadata.
@@ -18,10 +18,10 @@ use sql_query_builder as sql;
 ///
 pub fn cities(conn: &Connection, filter: CityFilter) -> crate::Result<Vec<Location>> {
     let elapsed_query = crate::LogElapsedTime::new("locations: query", Some(log::Level::Trace));
-    let query = build_query(filter);
+    let (query, params) = build_query(filter);
     // println!("{query}");
     let mut stmt = prepare_sql!(conn, &query, "failed to prepare query")?;
-    let mut rows = query_rows!(stmt, [], "error executing query")?;
+    let mut rows = query_rows!(stmt, params_from_iter(params), "error executing query")?;
     drop(elapsed_query);
 
     log_elapsed_time!("locations: create");
@@ -56,14 +56,14 @@ pub fn cities(conn: &Connection, filter: CityFilter) -> crate::Result<Vec<Locati
     Ok(locations)
 }
 
-/// Create the query used to get the US Cities data.
+/// Create the query used to get the US Cities data and the values bound to its parameters.
 ///
 /// # Arguments
 ///
 /// * `filter` is the optional city filters.
 /// * `limit` is the number of cities to return.
 ///
-fn build_query(filter: CityFilter) -> String {
+fn build_query(filter: CityFilter) -> (String, Vec<String>) {
     const SELECT_CLAUSE: &str = "
         DISTINCT
         cities.city AS city,
@@ -78,28 +78,33 @@ fn build_query(filter: CityFilter) -> String {
         .from("cities")
         .inner_join("states ON cities.states_id=states.id")
         .inner_join("city_zip_codes ON cities.id=city_zip_codes.cities_id");
+    let mut params = vec![];
     if let Some(mut city) = filter.name {
         if city.contains('*') {
             city = city.replace('*', "%");
         }
-        query = query.where_and(&format!("city LIKE '{city}'"));
+        params.push(city);
+        query = query.where_and(&format!("city LIKE ?{}", params.len()));
     }
     if let Some(mut state) = filter.state {
         if state.contains('*') {
             state = state.replace("*", "%");
         };
-        query = query.where_and(&format!("(state LIKE '{state}' OR state_id LIKE '{state}')"));
+        params.push(state);
+        let state = format!("?{}", params.len());
+        query = query.where_and(&format!("(state LIKE {state} OR state_id LIKE {state})"));
     }
     if let Some(mut zip_code) = filter.zip_code {
         if zip_code.contains('*') {
             zip_code = zip_code.replace("*", "%");
         }
+        params.push(zip_code);
         query = query
             .inner_join("zip_codes ON city_zip_codes.zip_codes_id=zip_codes.id")
-            .where_and(&format!("zip_codes.zip_code LIKE '{zip_code}'"));
+            .where_and(&format!("zip_codes.zip_code LIKE ?{}", params.len()));
     }
 
-    query.order_by("city, state_id").limit(&format!("{}", filter.limit)).to_string()
+    (query.order_by("city, state_id").limit(&format!("{}", filter.limit)).to_string(), params)
 }
 
 /// Get the collection of US City states.
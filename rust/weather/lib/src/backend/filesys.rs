@@ -2,16 +2,22 @@
 
 pub(crate) mod admin;
 
+mod backup;
+mod dir_lock;
 mod history;
 
 mod history_archive;
 mod locations;
+mod recovery;
 mod weather_dir;
 mod weather_file;
 
 pub(in crate::backend) use {
+    backup::{backup, restore},
+    dir_lock::{DirLock, LockMode},
     history_archive::{ArchiveMetadata, HistoryArchive},
-    locations::Locations,
+    locations::{filter_locations, validate_location, Locations},
+    recovery::{recover, recover_and_log},
     weather_dir::WeatherDir,
     weather_file::WeatherFile,
 };
@@ -19,11 +25,12 @@ pub(in crate::backend) use {
 use super::LocationFilters;
 use crate::{
     backend::{Backend, Config},
-    entities::{DailyHistories, DateRange, HistoryDates, HistorySummaries, Location, State, CityFilter},
+    entities::{DailyHistories, DateRange, History, HistoryDates, HistorySummaries, Location, State, CityFilter},
 };
+use std::time::Duration;
 
 /// Get a [WeatherDir] instance.
-pub(in crate::backend) fn create_weather_dir(dirname: &str) -> crate::Result<WeatherDir> {
+pub(in crate::backend) fn create_weather_dir(dirname: &str, lock_timeout: Duration) -> crate::Result<WeatherDir> {
     let weather_dir = if dirname.len() > 0 {
         WeatherDir::try_from(dirname)?
     } else if let Ok(env_pathname) = std::env::var("WEATHER_DATA") {
@@ -31,7 +38,7 @@ pub(in crate::backend) fn create_weather_dir(dirname: &str) -> crate::Result<Wea
     } else {
         WeatherDir::try_from("weather_data")?
     };
-    Ok(weather_dir)
+    Ok(weather_dir.with_lock_timeout(lock_timeout))
 }
 
 /// Create a Locations specific error message.
@@ -56,7 +63,8 @@ macro_rules! err {
 ///
 pub fn create_filesys_backend(config: Config) -> crate::Result<Box<dyn Backend>> {
     log::debug!("ArchiveBackend");
-    let weather_dir = create_weather_dir(&config.weather_data.directory)?;
+    let lock_timeout = Duration::from_secs(config.weather_data.lock_timeout);
+    let weather_dir = create_weather_dir(&config.weather_data.directory, lock_timeout)?;
     Ok(Box::new(ArchiveBackend { config, weather_dir }))
 }
 
@@ -114,7 +122,18 @@ impl Backend for ArchiveBackend {
             0 => err!("a location was not found.")?,
             _ => err!("Multiple locations were found.")?,
         };
-        crate::log_elapsed_time!(trace, "get_daily_histories");
+        self.get_location_histories(location, history_range)
+    }
+
+    /// Get daily weather history for a location selected by its alias.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` is the location whose history is returned.
+    /// * `history_range` covers the history dates returned.
+    ///
+    fn get_location_histories(&self, location: Location, history_range: DateRange) -> crate::Result<DailyHistories> {
+        crate::log_elapsed_time!(trace, "get_location_histories");
         let archive = self.get_archive(&location.alias)?;
         let daily_histories = archive.histories(&history_range)?.collect();
         Ok(DailyHistories { location, histories: daily_histories })
@@ -162,6 +181,46 @@ impl Backend for ArchiveBackend {
         Ok(history_summaries)
     }
 
+    /// Search the weather history descriptions of locations. The archives do not have a full-text
+    /// index so a description matches when it contains all the words in the query, ignoring case.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` identifies the locations that should be used.
+    /// * `query` contains the words a history description must have.
+    /// * `history_range` restricts the history dates searched, if `None` all history is searched.
+    ///
+    fn search_histories(
+        &self,
+        filters: LocationFilters,
+        query: &str,
+        history_range: Option<DateRange>,
+    ) -> crate::Result<Vec<DailyHistories>> {
+        let locations = self.get_locations(filters)?;
+        crate::log_elapsed_time!(trace, "search_histories");
+        let query_words = words(query);
+        if query_words.is_empty() {
+            err!("the search query does not contain any words.")?;
+        }
+        let is_match = |history: &History| {
+            let description_words = history.description.as_deref().map_or(vec![], words);
+            query_words.iter().all(|word| description_words.contains(word))
+        };
+        let mut search_histories = vec![];
+        for location in locations {
+            let archive = self.get_archive(&location.alias)?;
+            let mut histories: Vec<History> = match &history_range {
+                Some(history_range) => archive.histories(history_range)?.filter(is_match).collect(),
+                None => archive.metadata_and_history()?.map(|(_, history)| history).filter(is_match).collect(),
+            };
+            if !histories.is_empty() {
+                histories.sort_by_key(|history| history.date);
+                search_histories.push(DailyHistories { location, histories });
+            }
+        }
+        Ok(search_histories)
+    }
+
     /// Get the metadata for weather locations.
     ///
     /// # Arguments
@@ -215,3 +274,16 @@ impl Backend for ArchiveBackend {
         }
     }
 }
+
+/// Split text into lowercase words for the history description search.
+///
+/// # Arguments
+///
+/// * `text` is what will be split into words.
+///
+pub(in crate::backend) fn words(text: &str) -> Vec<String> {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
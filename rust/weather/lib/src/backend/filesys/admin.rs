@@ -1,12 +1,17 @@
 //! The filesys module admin API
 //!
-pub(in crate::backend) use v2::filesys_details;
+pub(in crate::backend) use v2::{filesys_details, verify};
 mod v2 {
     //! The current implementation of administration for the file system.
     use crate::{
-        admin_prelude::{FilesysDetails, LocationDetails},
-        backend::filesys::{HistoryArchive, Locations, WeatherDir}
+        admin_prelude::{FilesysDetails, LocationDetails, VerifyDetails, VerifyProblem},
+        backend::filesys::{HistoryArchive, Locations, WeatherDir, WeatherFile}
     };
+    use chrono::NaiveDate;
+    use std::fs;
+
+    /// The weather data directory folder that holds quarantined history files.
+    const QUARANTINE_DIRNAME: &str = "quarantine";
 
     pub fn filesys_details(weather_dir: &WeatherDir) -> crate::Result<FilesysDetails> {
         let mut location_details = vec![];
@@ -27,4 +32,51 @@ mod v2 {
         }
         Ok(FilesysDetails { size: archives_size as usize, location_details })
     }
+
+    /// Check the integrity of the location archives.
+    ///
+    /// # Arguments
+    ///
+    /// * `weather_dir` is the weather data directory.
+    /// * `quarantine` when true moves history files with problems to a quarantine archive.
+    /// * `quarantined` is called with the location alias and dates of the histories moved to quarantine.
+    ///
+    pub fn verify(
+        weather_dir: &WeatherDir,
+        quarantine: bool,
+        mut quarantined: impl FnMut(&str, &[NaiveDate]) -> crate::Result<()>,
+    ) -> crate::Result<VerifyDetails> {
+        let mut verify_details = VerifyDetails::default();
+        for location in Locations::open(weather_dir)?.get()? {
+            let alias = location.alias;
+            verify_details.archives += 1;
+            let verification = HistoryArchive::open(&alias, weather_dir.archive(&alias))
+                .and_then(|archive| archive.verify().map(|verification| (archive, verification)));
+            let (archive, verification) = match verification {
+                Ok(archive_verification) => archive_verification,
+                Err(error) => {
+                    let problem = VerifyProblem { alias, history: None, problem: error.to_string() };
+                    verify_details.problems.push(problem);
+                    continue;
+                }
+            };
+            verify_details.histories += verification.histories;
+            let filenames: Vec<String> = verification.problems.iter().map(|problem| problem.filename.clone()).collect();
+            for archive_problem in verification.problems {
+                let (history, problem) = (Some(archive_problem.filename), archive_problem.problem);
+                verify_details.problems.push(VerifyProblem { alias: alias.clone(), history, problem });
+            }
+            if quarantine && !filenames.is_empty() {
+                let quarantine_path = weather_dir.path().join(QUARANTINE_DIRNAME);
+                if let Err(error) = fs::create_dir_all(&quarantine_path) {
+                    Err(crate::Error::from(format!("Could not create {} ({}).", quarantine_path.display(), error)))?;
+                }
+                let quarantine_file = WeatherFile::new(quarantine_path.join(&alias).with_extension("zip"));
+                let dates = archive.quarantine(&filenames, &quarantine_file)?;
+                verify_details.quarantined += filenames.len();
+                quarantined(&alias, &dates)?;
+            }
+        }
+        Ok(verify_details)
+    }
 }
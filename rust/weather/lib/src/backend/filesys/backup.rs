@@ -0,0 +1,442 @@
+//! Snapshot backup and restore of the weather data directory.
+//!
+//! A backup bundle is a compressed `ZIP` file that holds the locations document, the location
+//! archives, and optionally a copy of the database. The bundle manifest records the size and
+//! `CRC32` checksum of each file. A bundle is validated against its manifest before anything in the
+//! weather data directory is replaced.
+
+use super::{LockMode, Locations, WeatherDir, WeatherFile};
+use crate::{
+    admin_prelude::{BackupDetails, RestoreDetails},
+    backend::db::sqlite::admin::journal_files,
+    entities::{Location, LocationFilters},
+};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+/// The name of the manifest in the backup bundle.
+const MANIFEST_FILENAME: &str = "manifest.json";
+
+/// The version of the backup bundle layout.
+const BUNDLE_FORMAT: u32 = 1;
+
+/// The extension of files being restored before they replace weather data files.
+const RESTORE_EXT: &str = "rst";
+
+/// The extension of database journal files set aside while the database is replaced.
+const JOURNAL_EXT: &str = "jnl";
+
+/// The backup bundle error builder.
+macro_rules! error {
+    ($bundle:expr, $($arg:tt)*) => {
+        crate::Error::from(format!("Bundle ({}): {}", $bundle.display(), format!($($arg)*)))
+    };
+}
+
+/// The contents of a backup bundle.
+#[derive(Debug, Deserialize, Serialize)]
+struct Manifest {
+    /// The version of the bundle layout.
+    format: u32,
+    /// When the backup was made.
+    created: String,
+    /// The alias names of the locations whose archive is in the bundle.
+    locations: Vec<String>,
+    /// The name of the database file if it is in the bundle.
+    database: Option<String>,
+    /// The files in the bundle.
+    files: Vec<ManifestFile>,
+}
+impl Manifest {
+    /// Get the file entry from the manifest.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` is the file name.
+    ///
+    fn file(&self, name: &str) -> Option<&ManifestFile> {
+        self.files.iter().find(|file| file.name == name)
+    }
+}
+
+/// A file in the backup bundle.
+#[derive(Debug, Deserialize, Serialize)]
+struct ManifestFile {
+    /// The name of the file in the weather data directory.
+    name: String,
+    /// The size of the file.
+    size: u64,
+    /// The file checksum.
+    crc32: u32,
+}
+
+/// A reader that calculates the size and checksum of what it reads.
+struct ChecksumReader<R> {
+    /// The source of the data.
+    reader: R,
+    /// The checksum of the data read.
+    hasher: crc32fast::Hasher,
+    /// The count of bytes read.
+    size: u64,
+}
+impl<R: Read> ChecksumReader<R> {
+    /// Create the reader.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` is the source of the data.
+    ///
+    fn new(reader: R) -> Self {
+        Self { reader, hasher: crc32fast::Hasher::new(), size: 0 }
+    }
+    /// Get the size and checksum of what was read.
+    fn finish(self) -> (u64, u32) {
+        (self.size, self.hasher.finalize())
+    }
+}
+impl<R: Read> Read for ChecksumReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.reader.read(buf)?;
+        self.hasher.update(&buf[..count]);
+        self.size += count as u64;
+        Ok(count)
+    }
+}
+
+/// Backup the weather data directory to a bundle. The directory is locked so the backup is a
+/// consistent snapshot.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+/// * `bundle` is the backup bundle that will be created or replaced.
+/// * `database` is the name and path of the database copy that will be included.
+///
+pub fn backup(
+    weather_dir: &WeatherDir,
+    bundle: &Path,
+    database: Option<(String, &Path)>,
+) -> crate::Result<BackupDetails> {
+    let _lock = weather_dir.lock(LockMode::Shared)?;
+    let locations_file = Locations::file(weather_dir);
+    let mut files = vec![(locations_file.filename.clone(), locations_file.path().to_path_buf())];
+    let mut locations = vec![];
+    for location in Locations::open(weather_dir)?.get()? {
+        let archive = weather_dir.archive(&location.alias);
+        if archive.exists() {
+            files.push((archive.filename.clone(), archive.path().to_path_buf()));
+            locations.push(location.alias);
+        } else {
+            log::warn!("{}", error!(bundle, "the {} archive does not exist and was skipped.", location.alias));
+        }
+    }
+    let database_name = database.as_ref().map(|(name, _)| name.clone());
+    if let Some((name, path)) = database {
+        files.push((name, path.to_path_buf()));
+    }
+    let created = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut manifest = Manifest { format: BUNDLE_FORMAT, created, locations, database: database_name, files: vec![] };
+
+    // write the bundle beside the old one so the old one is not lost if the backup fails
+    let update_path = bundle.with_extension(RESTORE_EXT);
+    let write_result = write_bundle(&update_path, &files, &mut manifest);
+    let rename_result = write_result.and_then(|_| match fs::rename(&update_path, bundle) {
+        Ok(_) => Ok(()),
+        Err(error) => Err(error!(bundle, "could not replace the bundle ({}).", error)),
+    });
+    if let Err(error) = rename_result {
+        let _ = fs::remove_file(&update_path);
+        return Err(error);
+    }
+    Ok(BackupDetails {
+        locations: manifest.locations.len(),
+        files: manifest.files.len(),
+        database: manifest.database.is_some(),
+        size: fs::metadata(bundle).map_or(0, |metadata| metadata.len()),
+    })
+}
+
+/// Write the files and manifest to a bundle.
+///
+/// # Arguments
+///
+/// * `path` is the bundle that will be written.
+/// * `files` are the names and paths of the files that will be added.
+/// * `manifest` is the bundle manifest that will be completed and added.
+///
+fn write_bundle(path: &Path, files: &[(String, PathBuf)], manifest: &mut Manifest) -> crate::Result<()> {
+    let write_error = |error: &dyn std::fmt::Display| error!(path, "write error ({}).", error);
+    let file = File::create(path).map_err(|error| error!(path, "could not create the bundle ({}).", error))?;
+    let mut writer = ZipWriter::new(BufWriter::new(file));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated).large_file(true);
+    for (name, source) in files {
+        let source_file = File::open(source).map_err(|error| error!(path, "could not read {} ({}).", name, error))?;
+        let mut reader = ChecksumReader::new(BufReader::new(source_file));
+        writer.start_file(name.as_str(), options).map_err(|error| write_error(&error))?;
+        io::copy(&mut reader, &mut writer).map_err(|error| write_error(&error))?;
+        let (size, crc32) = reader.finish();
+        manifest.files.push(ManifestFile { name: name.clone(), size, crc32 });
+    }
+    writer.start_file(MANIFEST_FILENAME, options).map_err(|error| write_error(&error))?;
+    serde_json::to_writer_pretty(&mut writer, manifest).map_err(|error| write_error(&error))?;
+    let mut buf_writer = writer.finish().map_err(|error| write_error(&error))?;
+    buf_writer.flush().map_err(|error| write_error(&error))
+}
+
+/// Restore the weather data directory from a backup bundle. The bundle is validated before any
+/// files are replaced. When locations are selected only their archives are restored and the other
+/// locations are not changed. The database is restored only when all locations are restored.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+/// * `bundle` is the backup bundle.
+/// * `filters` selects the locations to restore, all locations are restored if it is empty.
+///
+pub fn restore(weather_dir: &WeatherDir, bundle: &Path, filters: LocationFilters) -> crate::Result<RestoreDetails> {
+    let file = File::open(bundle).map_err(|error| error!(bundle, "could not open the bundle ({}).", error))?;
+    let mut archive = match ZipArchive::new(BufReader::new(file)) {
+        Ok(archive) => archive,
+        Err(error) => Err(error!(bundle, "the bundle could not be read ({}).", error))?,
+    };
+    let manifest = validate(bundle, &mut archive)?;
+
+    // get the locations being restored
+    let restore_all = filters.is_empty();
+    let locations_file = Locations::file(weather_dir);
+    let locations_entry = match archive.by_name(&locations_file.filename) {
+        Ok(entry) => entry,
+        Err(error) => Err(error!(bundle, "{} could not be read ({}).", locations_file.filename, error))?,
+    };
+    let source = format!("{}:{}", bundle.display(), locations_file.filename);
+    let locations: Vec<Location> = Locations::read(locations_entry, &source, filters)?
+        .into_iter()
+        .filter(|location| restore_all || manifest.locations.contains(&location.alias))
+        .collect();
+    if locations.is_empty() && !restore_all {
+        Err(error!(bundle, "the bundle does not have the selected locations."))?;
+    }
+
+    // copy the files out of the bundle then replace the weather data files
+    let mut restore_files: Vec<WeatherFile> = locations
+        .iter()
+        .filter(|location| manifest.locations.contains(&location.alias))
+        .map(|location| weather_dir.archive(&location.alias))
+        .collect();
+    let database = match (&manifest.database, restore_all) {
+        (Some(database), true) => {
+            restore_files.push(weather_dir.file(database));
+            true
+        }
+        _ => false,
+    };
+    let _lock = weather_dir.lock(LockMode::Exclusive)?;
+    let mut extracted = vec![];
+    for weather_file in &restore_files {
+        let restore_file = weather_dir.file(&format!("{}.{}", weather_file.filename, RESTORE_EXT));
+        let extract_result = extract(bundle, &mut archive, &weather_file.filename, &restore_file);
+        extracted.push(restore_file);
+        if let Err(error) = extract_result {
+            extracted.iter().for_each(|restore_file| {
+                let _ = restore_file.remove();
+            });
+            return Err(error);
+        }
+    }
+    // the database is last so its journal is kept until everything else has been replaced
+    for (restore_file, weather_file) in extracted.iter().zip(&restore_files) {
+        match manifest.database.as_ref().filter(|name| database && **name == weather_file.filename) {
+            Some(_) => replace_database(weather_dir, restore_file, weather_file)?,
+            None => restore_file.rename(weather_file)?,
+        }
+    }
+    Locations::open(weather_dir)?.restore(&locations, restore_all)?;
+    let locations = locations.into_iter().map(|location| location.alias).collect();
+    Ok(RestoreDetails { locations, database, stale_database: false })
+}
+
+/// Replace the database with the restored copy. The journal of the current database must not be
+/// applied to the restored database so it is set aside while the database is replaced and put back
+/// if the database cannot be replaced.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+/// * `restore_file` is the restored database.
+/// * `database_file` is the database that will be replaced.
+///
+fn replace_database(
+    weather_dir: &WeatherDir,
+    restore_file: &WeatherFile,
+    database_file: &WeatherFile,
+) -> crate::Result<()> {
+    let mut set_aside: Vec<(WeatherFile, WeatherFile)> = vec![];
+    let mut result = Ok(());
+    for journal_file in journal_files(weather_dir, &database_file.filename) {
+        let aside_file = weather_dir.file(&format!("{}.{}", journal_file.filename, JOURNAL_EXT));
+        result = journal_file.rename(&aside_file);
+        if result.is_err() {
+            break;
+        }
+        set_aside.push((journal_file, aside_file));
+    }
+    if result.is_ok() {
+        result = restore_file.rename(database_file);
+    }
+    for (journal_file, aside_file) in set_aside {
+        let cleanup = match result {
+            Ok(_) => aside_file.remove(),
+            Err(_) => aside_file.rename(&journal_file),
+        };
+        if let Err(error) = cleanup {
+            log::warn!("{}", error);
+        }
+    }
+    result
+}
+
+/// Check the bundle files match the manifest.
+///
+/// # Arguments
+///
+/// * `bundle` is the backup bundle path.
+/// * `archive` is the backup bundle.
+///
+fn validate<R: Read + Seek>(bundle: &Path, archive: &mut ZipArchive<R>) -> crate::Result<Manifest> {
+    let manifest: Manifest = match archive.by_name(MANIFEST_FILENAME) {
+        Ok(entry) => match serde_json::from_reader(entry) {
+            Ok(manifest) => manifest,
+            Err(error) => Err(error!(bundle, "the manifest is not valid ({}).", error))?,
+        },
+        Err(error) => Err(error!(bundle, "the manifest could not be read ({}).", error))?,
+    };
+    if manifest.format != BUNDLE_FORMAT {
+        Err(error!(bundle, "bundle format {} is not supported.", manifest.format))?;
+    }
+    let mut expected: Vec<String> = manifest.locations.iter().map(|alias| format!("{}.zip", alias)).collect();
+    expected.extend(manifest.database.iter().cloned());
+    for name in expected {
+        if manifest.file(&name).is_none() {
+            Err(error!(bundle, "{} is missing from the manifest.", name))?;
+        }
+    }
+    for manifest_file in &manifest.files {
+        let name = &manifest_file.name;
+        if Path::new(name).file_name().is_none_or(|file_name| file_name != name.as_str()) {
+            Err(error!(bundle, "{} is not a weather data file name.", name))?;
+        }
+        let entry = match archive.by_name(name) {
+            Ok(entry) => entry,
+            Err(error) => Err(error!(bundle, "{} could not be read ({}).", name, error))?,
+        };
+        let mut reader = ChecksumReader::new(entry);
+        if let Err(error) = io::copy(&mut reader, &mut io::sink()) {
+            Err(error!(bundle, "{} could not be read ({}).", name, error))?;
+        }
+        if reader.finish() != (manifest_file.size, manifest_file.crc32) {
+            Err(error!(bundle, "{} does not match its checksum.", name))?;
+        }
+    }
+    Ok(manifest)
+}
+
+/// Copy a file out of the bundle.
+///
+/// # Arguments
+///
+/// * `bundle` is the backup bundle path.
+/// * `archive` is the backup bundle.
+/// * `name` is the name of the file in the bundle.
+/// * `weather_file` is where the file will be copied.
+///
+fn extract<R: Read + Seek>(
+    bundle: &Path,
+    archive: &mut ZipArchive<R>,
+    name: &str,
+    weather_file: &WeatherFile,
+) -> crate::Result<()> {
+    let mut entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(error) => Err(error!(bundle, "{} could not be read ({}).", name, error))?,
+    };
+    let file = File::create(weather_file.path())
+        .map_err(|error| error!(bundle, "could not create {} ({}).", weather_file, error))?;
+    let mut writer = BufWriter::new(file);
+    match io::copy(&mut entry, &mut writer).and_then(|_| writer.flush()) {
+        Ok(_) => Ok(()),
+        Err(error) => Err(error!(bundle, "could not restore {} ({}).", name, error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::testlib;
+    use crate::location_filter;
+
+    #[test]
+    fn backup_and_restore() {
+        let fixture = testlib::TestFixture::create();
+        let source = testlib::test_resources().join("filesys");
+        fixture.copy_resources(&source);
+        let weather_dir = WeatherDir::try_from(fixture.to_string()).unwrap();
+        let bundle = PathBuf::from(&fixture).join("backup.zip");
+        let database = PathBuf::from(&fixture).join("database.db");
+        fs::write(&database, b"database").unwrap();
+        let backup_details = backup(&weather_dir, &bundle, Some(("weather_data.db".to_string(), &database))).unwrap();
+        assert_eq!(backup_details.locations, 3);
+        assert_eq!(backup_details.files, 5);
+        assert!(backup_details.database);
+        assert!(!bundle.with_extension(RESTORE_EXT).exists());
+
+        // restore a single location after it has been changed
+        let archive = weather_dir.archive("north");
+        let original = fs::read(archive.path()).unwrap();
+        fs::write(archive.path(), b"changed").unwrap();
+        let filters = LocationFilters::new(vec![location_filter!(name = "north")]);
+        let restore_details = restore(&weather_dir, &bundle, filters).unwrap();
+        assert_eq!(restore_details.locations, vec!["north".to_string()]);
+        assert!(!restore_details.database);
+        assert_eq!(fs::read(archive.path()).unwrap(), original);
+        assert!(!weather_dir.file("weather_data.db").exists());
+
+        // the journal is put back if the database cannot be replaced
+        let journal = weather_dir.file("weather_data.db-wal");
+        fs::write(journal.path(), b"journal").unwrap();
+        let missing = weather_dir.file("missing.db");
+        assert!(replace_database(&weather_dir, &missing, &weather_dir.file("weather_data.db")).is_err());
+        assert_eq!(fs::read(journal.path()).unwrap(), b"journal");
+
+        // restore everything
+        let restore_details = restore(&weather_dir, &bundle, LocationFilters::default()).unwrap();
+        assert_eq!(restore_details.locations.len(), 3);
+        assert!(restore_details.database);
+        assert_eq!(fs::read(weather_dir.file("weather_data.db").path()).unwrap(), b"database");
+        assert!(!journal.exists());
+        assert!(!weather_dir.file(&format!("weather_data.db-wal.{}", JOURNAL_EXT)).exists());
+        let filters = LocationFilters::new(vec![location_filter!(name = "nowhere")]);
+        assert!(restore(&weather_dir, &bundle, filters).is_err());
+
+        // a bundle that does not match its manifest is not restored
+        let corrupt = PathBuf::from(&fixture).join("corrupt.zip");
+        let mut writer = ZipWriter::new(File::create(&corrupt).unwrap());
+        writer.start_file(MANIFEST_FILENAME, SimpleFileOptions::default()).unwrap();
+        let manifest = r#"{"format":1,"created":"","locations":["north"],"database":null,"files":[
+            {"name":"locations.json","size":2,"crc32":0},{"name":"north.zip","size":7,"crc32":0}]}"#;
+        writer.write_all(manifest.as_bytes()).unwrap();
+        writer.start_file("locations.json", SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"{}").unwrap();
+        writer.start_file("north.zip", SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"changed").unwrap();
+        writer.finish().unwrap();
+        let error = restore(&weather_dir, &corrupt, LocationFilters::default()).unwrap_err().to_string();
+        assert!(error.contains("does not match its checksum"), "{}", error);
+        assert_eq!(fs::read(archive.path()).unwrap(), original);
+    }
+}
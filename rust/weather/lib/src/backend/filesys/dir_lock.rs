@@ -0,0 +1,332 @@
+//! Advisory locks on the weather data directory.
+//!
+//! Processes that share a weather data directory coordinate through a lock file in the directory.
+//! Readers hold a shared lock and writers hold an exclusive lock. The lock file describes who is
+//! holding the lock so a timeout error can report it.
+//!
+//! Locks belong to the process, not a thread. Taking a lock the process already holds counts the
+//! lock and taking an exclusive lock while holding a shared lock upgrades it. The lock returns to
+//! shared when the exclusive lock is released.
+
+use chrono::Local;
+use std::{
+    collections::HashMap,
+    env,
+    fs::{self, File, OpenOptions, TryLockError},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{LazyLock, Mutex, PoisonError},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The name of the lock file in the weather data directory.
+pub const LOCK_FILENAME: &str = "weather.lock";
+
+/// How long to wait between attempts to get the lock.
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The directory lock error builder.
+macro_rules! error {
+    ($dir:expr, $reason:expr) => {
+        crate::Error::from(format!("WeatherDir ({}): {}", $dir.display(), $reason))
+    };
+}
+
+/// The kind of lock held on the weather data directory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LockMode {
+    /// The lock used when reading weather data.
+    Shared,
+    /// The lock used when writing weather data.
+    Exclusive,
+}
+impl std::fmt::Display for LockMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockMode::Shared => write!(f, "shared"),
+            LockMode::Exclusive => write!(f, "exclusive"),
+        }
+    }
+}
+
+/// The lock file and the count of locks the process holds on a directory.
+#[derive(Debug)]
+struct LockState {
+    /// The open lock file.
+    file: File,
+    /// The count of shared locks.
+    shared: usize,
+    /// The count of exclusive locks.
+    exclusive: usize,
+}
+
+/// The directory locks held by the process.
+static LOCKS: LazyLock<Mutex<HashMap<PathBuf, LockState>>> = LazyLock::new(Default::default);
+
+/// The weather data directory lock, it is released when dropped.
+#[derive(Debug)]
+#[must_use = "the lock is released when it is dropped"]
+pub struct DirLock {
+    /// The locked directory, `None` if the directory cannot be locked.
+    dir: Option<PathBuf>,
+    /// The kind of lock.
+    mode: LockMode,
+}
+impl DirLock {
+    /// Lock the weather data directory. An error is returned if the lock is not available before the
+    /// timeout.
+    ///
+    /// A read-only directory cannot be written so a shared lock is not required.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` is the weather data directory.
+    /// * `mode` is the kind of lock.
+    /// * `timeout` is how long to wait for the lock.
+    ///
+    pub fn acquire(dir: &Path, mode: LockMode, timeout: Duration) -> crate::Result<Self> {
+        let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        let mut locks = LOCKS.lock().unwrap_or_else(PoisonError::into_inner);
+        match locks.get_mut(&dir) {
+            Some(state) => {
+                if mode == LockMode::Exclusive && state.exclusive == 0 {
+                    upgrade(&state.file, &dir, timeout)?;
+                    record(&state.file, mode, true);
+                }
+                state.count(mode, true);
+            }
+            None => {
+                let open_result = OpenOptions::new().read(true).append(true).create(true).open(dir.join(LOCK_FILENAME));
+                let file = match open_result {
+                    Ok(file) => file,
+                    Err(error) if mode == LockMode::Shared && is_read_only(error.kind()) => {
+                        log::debug!("{}", error!(dir, format!("not locking a read-only directory ({}).", error)));
+                        return Ok(Self { dir: None, mode });
+                    }
+                    Err(error) => return Err(error!(dir, format!("could not open the lock file ({}).", error))),
+                };
+                wait(&file, &dir, mode, timeout)?;
+                record(&file, mode, mode == LockMode::Exclusive);
+                let mut state = LockState { file, shared: 0, exclusive: 0 };
+                state.count(mode, true);
+                locks.insert(dir.clone(), state);
+            }
+        }
+        Ok(Self { dir: Some(dir), mode })
+    }
+}
+impl Drop for DirLock {
+    /// Release the lock on the weather data directory.
+    fn drop(&mut self) {
+        let dir = match self.dir.take() {
+            Some(dir) => dir,
+            None => return,
+        };
+        let mut locks = LOCKS.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(state) = locks.get_mut(&dir) {
+            state.count(self.mode, false);
+            if state.shared == 0 && state.exclusive == 0 {
+                if let Some(state) = locks.remove(&dir) {
+                    release(state.file);
+                }
+            } else if self.mode == LockMode::Exclusive && state.exclusive == 0 {
+                match state.file.lock_shared() {
+                    Ok(_) => record(&state.file, LockMode::Shared, true),
+                    Err(error) => {
+                        log::warn!("{}", error!(dir, format!("failed to restore the shared lock ({}).", error)))
+                    }
+                }
+            }
+        }
+    }
+}
+impl LockState {
+    /// Count a lock being taken or released.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` is the kind of lock.
+    /// * `taken` is `true` when the lock is taken and `false` when it is released.
+    ///
+    fn count(&mut self, mode: LockMode, taken: bool) {
+        let count = match mode {
+            LockMode::Shared => &mut self.shared,
+            LockMode::Exclusive => &mut self.exclusive,
+        };
+        *count = if taken { *count + 1 } else { count.saturating_sub(1) };
+    }
+}
+
+/// Wait for the lock file to be locked.
+///
+/// # Arguments
+///
+/// * `file` is the lock file.
+/// * `dir` is the weather data directory.
+/// * `mode` is the kind of lock.
+/// * `timeout` is how long to wait for the lock.
+///
+fn wait(file: &File, dir: &Path, mode: LockMode, timeout: Duration) -> crate::Result<()> {
+    let started = Instant::now();
+    loop {
+        let lock_result = match mode {
+            LockMode::Shared => file.try_lock_shared(),
+            LockMode::Exclusive => file.try_lock(),
+        };
+        match lock_result {
+            Ok(_) => return Ok(()),
+            Err(TryLockError::WouldBlock) if started.elapsed() < timeout => thread::sleep(RETRY_INTERVAL),
+            Err(TryLockError::WouldBlock) => {
+                let reason = format!(
+                    "timed out after {}s waiting for the {} lock, it is held by {}.",
+                    timeout.as_secs(),
+                    mode,
+                    holders(dir)
+                );
+                return Err(error!(dir, reason));
+            }
+            Err(TryLockError::Error(error)) => {
+                return Err(error!(dir, format!("failed to get the {} lock ({}).", mode, error)));
+            }
+        }
+    }
+}
+
+/// Upgrade the shared lock to an exclusive lock. A failed upgrade can drop the shared lock so it is
+/// restored before trying again.
+///
+/// # Arguments
+///
+/// * `file` is the lock file.
+/// * `dir` is the weather data directory.
+/// * `timeout` is how long to wait for the lock.
+///
+fn upgrade(file: &File, dir: &Path, timeout: Duration) -> crate::Result<()> {
+    let started = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(_) => return Ok(()),
+            Err(TryLockError::WouldBlock) => {
+                if let Err(error) = file.try_lock_shared() {
+                    log::warn!("{}", error!(dir, format!("failed to restore the shared lock ({}).", error)));
+                }
+                if started.elapsed() >= timeout {
+                    let reason = format!(
+                        "timed out after {}s waiting to upgrade the shared lock, it is held by {}.",
+                        timeout.as_secs(),
+                        holders(dir)
+                    );
+                    return Err(error!(dir, reason));
+                }
+                thread::sleep(RETRY_INTERVAL);
+            }
+            Err(TryLockError::Error(error)) => {
+                return Err(error!(dir, format!("failed to upgrade the shared lock ({}).", error)));
+            }
+        }
+    }
+}
+
+/// Release the lock file. The last process to release the lock clears the lock holders.
+///
+/// # Arguments
+///
+/// * `file` is the lock file.
+///
+fn release(file: File) {
+    if let Err(error) = file.unlock() {
+        log::warn!("failed to unlock {}: {}", LOCK_FILENAME, error);
+    } else if file.try_lock().is_ok() {
+        if let Err(error) = file.set_len(0) {
+            log::debug!("failed to clear {}: {}", LOCK_FILENAME, error);
+        }
+        let _ = file.unlock();
+    }
+}
+
+/// Write the process holding the lock into the lock file.
+///
+/// # Arguments
+///
+/// * `file` is the lock file.
+/// * `mode` is the kind of lock.
+/// * `replace` is `true` when the process is the only lock holder.
+///
+fn record(mut file: &File, mode: LockMode, replace: bool) {
+    let program = env::args()
+        .next()
+        .and_then(|arg| Path::new(&arg).file_name().map(|name| name.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "unknown".to_string());
+    let since = Local::now().format("%Y-%m-%d %H:%M:%S");
+    let holder = format!("{} (pid {}) {} since {}\n", program, process::id(), mode, since);
+    let clear_result = if replace { file.set_len(0) } else { Ok(()) };
+    if let Err(error) = clear_result.and_then(|_| file.write_all(holder.as_bytes())) {
+        log::debug!("failed to record the lock holder in {}: {}", LOCK_FILENAME, error);
+    }
+}
+
+/// Get the processes recorded in the lock file.
+///
+/// # Arguments
+///
+/// * `dir` is the weather data directory.
+///
+fn holders(dir: &Path) -> String {
+    let contents = fs::read_to_string(dir.join(LOCK_FILENAME)).unwrap_or_default();
+    let holders: Vec<&str> = contents.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+    match holders.is_empty() {
+        true => "another process".to_string(),
+        false => holders.join(", "),
+    }
+}
+
+/// Tests if an error opening the lock file means the directory is read-only.
+///
+/// # Arguments
+///
+/// * `kind` is the kind of error.
+///
+fn is_read_only(kind: ErrorKind) -> bool {
+    matches!(kind, ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::testlib;
+
+    /// Lock the directory with a lock file that is not part of the process registry.
+    fn foreign_lock(dir: &Path) -> File {
+        let file = OpenOptions::new().read(true).append(true).create(true).open(dir.join(LOCK_FILENAME)).unwrap();
+        file.try_lock().unwrap();
+        (&file).write_all(b"other (pid 1) exclusive since now\n").unwrap();
+        file
+    }
+
+    #[test]
+    fn locking() {
+        let fixture = testlib::TestFixture::create();
+        let dir = PathBuf::from(&fixture);
+        let timeout = Duration::from_millis(100);
+        {
+            let shared = DirLock::acquire(&dir, LockMode::Shared, timeout).unwrap();
+            let exclusive = DirLock::acquire(&dir, LockMode::Exclusive, timeout).unwrap();
+            let contents = fs::read_to_string(dir.join(LOCK_FILENAME)).unwrap();
+            assert!(contents.contains(&format!("(pid {}) exclusive", process::id())));
+            drop(exclusive);
+            let contents = fs::read_to_string(dir.join(LOCK_FILENAME)).unwrap();
+            assert!(contents.contains(&format!("(pid {}) shared", process::id())));
+            drop(shared);
+        }
+        assert_eq!(fs::read_to_string(dir.join(LOCK_FILENAME)).unwrap(), "");
+
+        // a lock held by another process times out and names the holder
+        let other = foreign_lock(&dir);
+        let error = DirLock::acquire(&dir, LockMode::Shared, timeout).unwrap_err().to_string();
+        assert!(error.contains("other (pid 1) exclusive since now"), "{}", error);
+        other.unlock().unwrap();
+        drop(DirLock::acquire(&dir, LockMode::Exclusive, timeout).unwrap());
+    }
+}
@@ -10,13 +10,15 @@
 //! collections.
 
 use crate::{
-    backend::filesys::{history, WeatherFile},
+    admin_prelude::Recovery,
+    backend::filesys::{history, recovery::recover_file, LockMode, WeatherFile},
     entities::{DateRange, DateRanges, History, HistorySummary},
 };
+use std::{collections::HashSet, path::Path};
 use toolslib::{fmt::commafy, stopwatch::StopWatch};
 
 mod archive_file;
-pub use archive_file::ArchiveMetadata;
+pub use archive_file::{ArchiveMetadata, ArchiveVerification};
 use archive_file::{ArchiveContent, ArchiveData, ArchiveFile};
 use chrono::NaiveDate;
 
@@ -54,6 +56,60 @@ impl HistoryArchive {
         Ok(self_)
     }
 
+    /// Tests if a file in the weather directory is left over from an archive update.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` is the file pathname.
+    ///
+    pub fn is_interrupted(path: &Path) -> bool {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) => extension == archive_file::UPDATE_EXT || extension == archive_file::BACKUP_EXT,
+            None => false,
+        }
+    }
+
+    /// Roll an interrupted update of the history archive forward or back. The update is complete
+    /// if it is a valid archive that has all the histories of the original archive.
+    ///
+    /// # Arguments
+    ///
+    /// * `alias` is the locations unique identifier.
+    /// * `archive_file` is the weather history archive file.
+    ///
+    pub fn recover(alias: &str, archive_file: WeatherFile) -> crate::Result<Option<Recovery>> {
+        let update_file = archive_file.with_extension(archive_file::UPDATE_EXT);
+        let backup_file = archive_file.with_extension(archive_file::BACKUP_EXT);
+        let dates = |file: &WeatherFile| -> Option<HashSet<NaiveDate>> {
+            let archive = ArchiveFile::open(alias, WeatherFile::new(file.path().to_path_buf())).ok()?;
+            archive.history_dates(None, false).ok().map(|dates| dates.into_iter().collect())
+        };
+        let complete = || match (dates(&update_file), dates(&archive_file)) {
+            (Some(updated), Some(original)) => updated.is_superset(&original),
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        recover_file(&archive_file, &update_file, &backup_file, complete, |file| dates(file).is_some())
+    }
+
+    /// Check the integrity of the history files in the archive.
+    ///
+    pub fn verify(&self) -> crate::Result<ArchiveVerification> {
+        self.archive.verify()
+    }
+
+    /// Move history files out of the archive and into a quarantine archive. The dates of the history
+    /// files with a valid history name are returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `filenames` are the history files that will be moved.
+    /// * `quarantine` is the archive that will hold the history files.
+    ///
+    pub fn quarantine(&self, filenames: &[String], quarantine: &WeatherFile) -> crate::Result<Vec<NaiveDate>> {
+        self.archive.quarantine(filenames, quarantine)
+    }
+
     /// Used by the [Backend] to get a summary of the history information for a location.
     ///
     pub fn summary(&self) -> crate::Result<HistorySummary> {
@@ -114,6 +170,8 @@ impl HistoryArchive {
     ///
     pub fn append(&self, histories: &Vec<History>) -> crate::Result<Vec<NaiveDate>> {
         let stopwatch = StopWatch::start_new();
+        // hold the lock so history added by others is seen
+        let _lock = self.archive.lock(LockMode::Exclusive)?;
         // find histories dates that already exist
         let append_dates = histories.iter().map(|history| history.date.clone()).collect::<Vec<_>>();
         let existing_dates = self.archive.metadata_by_date(append_dates, true)?.map(|md| md.date).collect::<Vec<_>>();
@@ -282,4 +340,88 @@ mod tests {
         assert_eq!(added_dates.len(), 0);
         assert_eq!(testcase.summary().unwrap().count, 5);
     }
+
+    #[test]
+    fn verify_and_quarantine() {
+        use std::io::Write;
+        use zip::{write::SimpleFileOptions, ZipWriter};
+        let fixture = testlib::TestFixture::create();
+        let weather_dir = WeatherDir::new(PathBuf::from(&fixture)).unwrap();
+        let alias = "test";
+        let testcase = HistoryArchive::create(alias, weather_dir.archive(alias)).unwrap();
+        let test_dates = DateRange::new(get_date(2025, 5, 15), get_date(2025, 5, 17));
+        let history_data: Vec<History> =
+            test_dates.iter().map(|date| History { alias: alias.to_string(), date, ..Default::default() }).collect();
+        testcase.append(&history_data).unwrap();
+        let verification = testcase.verify().unwrap();
+        assert_eq!(verification.histories, 3);
+        assert!(verification.problems.is_empty());
+
+        // add a history that is not a document and one with the wrong name
+        let mut writer = ZipWriter::new_append(weather_dir.archive(alias).writer().unwrap()).unwrap();
+        writer.start_file("test/test-20250518.json", SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"not a history").unwrap();
+        writer.start_file("test/other-20250519.json", SimpleFileOptions::default()).unwrap();
+        let history = History { alias: alias.to_string(), date: get_date(2025, 5, 19), ..Default::default() };
+        writer.write_all(&history::to_bytes(&history).unwrap()).unwrap();
+        writer.finish().unwrap();
+        let verification = testcase.verify().unwrap();
+        assert_eq!(verification.histories, 5);
+        assert_eq!(verification.problems.len(), 2);
+        assert_eq!(verification.problems[0].filename, "test/test-20250518.json");
+        assert_eq!(verification.problems[1].filename, "test/other-20250519.json");
+
+        // quarantine the problems
+        let quarantine_file = weather_dir.file("quarantine.zip");
+        let filenames: Vec<String> = verification.problems.into_iter().map(|problem| problem.filename).collect();
+        // only the history with a valid name has a date
+        assert_eq!(testcase.quarantine(&filenames, &quarantine_file).unwrap(), vec![get_date(2025, 5, 18)]);
+        let verification = testcase.verify().unwrap();
+        assert_eq!(verification.histories, 3);
+        assert!(verification.problems.is_empty());
+        let quarantine = HistoryArchive::open(alias, weather_dir.file("quarantine.zip")).unwrap();
+        assert_eq!(quarantine.verify().unwrap().histories, 2);
+
+        // quarantine the same history again
+        let mut writer = ZipWriter::new_append(weather_dir.archive(alias).writer().unwrap()).unwrap();
+        writer.start_file("test/test-20250518.json", SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"still not a history").unwrap();
+        writer.finish().unwrap();
+        testcase.quarantine(&["test/test-20250518.json".to_string()], &quarantine_file).unwrap();
+        let quarantine = zip::ZipArchive::new(std::fs::File::open(quarantine_file.path()).unwrap()).unwrap();
+        let mut filenames: Vec<&str> = quarantine.file_names().collect();
+        filenames.sort();
+        assert_eq!(filenames, vec!["test/other-20250519.json", "test/test-20250518-1.json", "test/test-20250518.json"]);
+    }
+
+    #[test]
+    fn verify_misnamed_first() {
+        use std::io::Write;
+        use zip::{write::SimpleFileOptions, ZipWriter};
+        let fixture = testlib::TestFixture::create();
+        let weather_dir = WeatherDir::new(PathBuf::from(&fixture)).unwrap();
+        let alias = "test";
+        let testcase = HistoryArchive::create(alias, weather_dir.archive(alias)).unwrap();
+
+        // the misnamed history comes before the correctly named history for the same date
+        let history = History { alias: alias.to_string(), date: get_date(2025, 5, 19), ..Default::default() };
+        let mut writer = ZipWriter::new_append(weather_dir.archive(alias).writer().unwrap()).unwrap();
+        writer.start_file("x/other-20250519.json", SimpleFileOptions::default()).unwrap();
+        writer.write_all(&history::to_bytes(&history).unwrap()).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(testcase.append(&vec![history]).unwrap(), vec![get_date(2025, 5, 19)]);
+        let verification = testcase.verify().unwrap();
+        assert_eq!(verification.histories, 2);
+        assert_eq!(verification.problems.len(), 1);
+        assert_eq!(verification.problems[0].filename, "x/other-20250519.json");
+
+        // quarantine does not report the date of the correctly named history
+        let filenames: Vec<String> = verification.problems.into_iter().map(|problem| problem.filename).collect();
+        let quarantine_file = weather_dir.file("quarantine.zip");
+        assert!(testcase.quarantine(&filenames, &quarantine_file).unwrap().is_empty());
+        let verification = testcase.verify().unwrap();
+        assert_eq!(verification.histories, 1);
+        assert!(verification.problems.is_empty());
+        assert_eq!(testcase.summary().unwrap().count, 1);
+    }
 }
@@ -9,14 +9,13 @@ use reader::ArchiveReader;
 
 mod writer;
 use writer::ArchiveWriter;
-#[cfg(test)]
 pub use writer::{BACKUP_EXT, UPDATE_EXT};
 
 mod iterators;
 
 use super::history;
 use crate::{
-    backend::filesys::WeatherFile,
+    backend::filesys::{DirLock, LockMode, WeatherFile},
     entities::{DateRange, History},
 };
 use chrono::NaiveDate;
@@ -154,6 +153,35 @@ impl ArchiveFile {
         ArchiveWriter::new(&self.lid, &self.file).add_data(data)
     }
 
+    /// Check the integrity of the history files in the archive.
+    ///
+    pub fn verify(&self) -> crate::Result<ArchiveVerification> {
+        let mut archive = ArchiveReader::open(&self.lid, &self.file)?;
+        Ok(archive.verify())
+    }
+
+    /// Move history files out of the archive and into a quarantine archive. The dates of the history
+    /// files with a valid history name are returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `filenames` are the history files that will be moved.
+    /// * `quarantine` is the archive that will hold the history files.
+    ///
+    pub fn quarantine(&self, filenames: &[String], quarantine: &WeatherFile) -> crate::Result<Vec<NaiveDate>> {
+        ArchiveWriter::new(&self.lid, &self.file).quarantine(filenames, quarantine)
+    }
+
+    /// Lock the weather directory that contains the archive.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` is the kind of lock.
+    ///
+    pub fn lock(&self, mode: LockMode) -> crate::Result<DirLock> {
+        self.file.lock(mode)
+    }
+
     /// Get the size of the file.
     ///
     pub fn size(&self) -> u64 {
@@ -161,6 +189,24 @@ impl ArchiveFile {
     }
 }
 
+/// A history file in the archive that has a problem.
+#[derive(Debug)]
+pub struct ArchiveProblem {
+    /// The name of the history file in the archive.
+    pub filename: String,
+    /// What is wrong with the history file.
+    pub problem: String,
+}
+
+/// The results of checking the history files in an archive.
+#[derive(Debug, Default)]
+pub struct ArchiveVerification {
+    /// The number of history files in the archive.
+    pub histories: usize,
+    /// The history files that have problems.
+    pub problems: Vec<ArchiveProblem>,
+}
+
 /// A bean providing stats about a weather history file in the archive.
 #[derive(Debug)]
 pub struct ArchiveMetadata {
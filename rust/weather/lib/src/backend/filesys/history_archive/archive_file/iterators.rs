@@ -1,6 +1,7 @@
 //! The various history archive iterators are located here.
 
 use super::{archive, ArchiveContent, ArchiveData, ArchiveMetadata};
+use crate::backend::filesys::DirLock;
 use chrono::NaiveDate;
 use std::io::{Read, Seek};
 use zip::{read::ZipFile, ZipArchive};
@@ -18,6 +19,8 @@ struct ArchiveIterator<R: Read + Seek> {
     index: usize,
     /// The size of the date collection.
     max_index: usize,
+    /// The weather directory lock held while iterating.
+    _lock: DirLock,
 }
 impl<R: Read + Seek> ArchiveIterator<R> {
     /// Create a new instance of the iterator.
@@ -27,10 +30,11 @@ impl<R: Read + Seek> ArchiveIterator<R> {
     /// * `lid` is the location alias.
     /// * `archive` is the zip archive that will be used.
     /// * `dates` selects which history files to iterate over.
+    /// * `lock` is the weather directory lock held while iterating.
     ///
-    pub fn new(lid: &str, archive: ZipArchive<R>, dates: Vec<NaiveDate>) -> Self {
+    pub fn new(lid: &str, archive: ZipArchive<R>, dates: Vec<NaiveDate>, lock: DirLock) -> Self {
         let max_index = dates.len();
-        Self { lid: lid.into(), archive, dates, index: 0, max_index }
+        Self { lid: lid.into(), archive, dates, index: 0, max_index, _lock: lock }
     }
 
     /// There should never be an error getting contents unless something is pretty AFU.
@@ -73,9 +77,10 @@ impl<R: Read + Seek> ArchiveMetadataIterator<R> {
     /// * `lid` is the location alias name.
     /// * `archive` is the zip archive that will be used by the iterator.
     /// * `dates` identifies what history dates will be used.
+    /// * `lock` is the weather directory lock held while iterating.
     /// 
-    pub fn new(lid: &str, archive: ZipArchive<R>, dates: Vec<NaiveDate>) -> Self {
-        Self { archive: ArchiveIterator::new(lid, archive, dates) }
+    pub fn new(lid: &str, archive: ZipArchive<R>, dates: Vec<NaiveDate>, lock: DirLock) -> Self {
+        Self { archive: ArchiveIterator::new(lid, archive, dates, lock) }
     }
 }
 /// Allow the collection archive metadata to be returned as an iterator.
@@ -108,9 +113,10 @@ impl<R: Read + Seek> ArchiveDataIterator<R> {
     /// * `lid` is the location alias name.
     /// * `archive` is the zip archive that will be used by the iterator.
     /// * `dates` identifies what history dates will be used.
+    /// * `lock` is the weather directory lock held while iterating.
     ///
-    pub fn new(lid: &str, archive: ZipArchive<R>, dates: Vec<NaiveDate>) -> Self {
-        Self { lid: lid.into(), archive: ArchiveIterator::new(lid, archive, dates), get_next: true }
+    pub fn new(lid: &str, archive: ZipArchive<R>, dates: Vec<NaiveDate>, lock: DirLock) -> Self {
+        Self { lid: lid.into(), archive: ArchiveIterator::new(lid, archive, dates, lock), get_next: true }
     }
 }
 /// Allow the collection archive data to be returned as an iterator.
@@ -155,9 +161,10 @@ impl<R: Read + Seek> ArchiveContentIterator<R> {
     /// * `lid` is the location alias name.
     /// * `archive` is the zip archive that will be used by the iterator.
     /// * `dates` identifies what history dates will be used.
+    /// * `lock` is the weather directory lock held while iterating.
     ///
-    pub fn new(lid: &str, archive: ZipArchive<R>, dates: Vec<NaiveDate>) -> Self {
-        Self { lid: lid.into(), archive: ArchiveIterator::new(lid, archive, dates), get_next: true }
+    pub fn new(lid: &str, archive: ZipArchive<R>, dates: Vec<NaiveDate>, lock: DirLock) -> Self {
+        Self { lid: lid.into(), archive: ArchiveIterator::new(lid, archive, dates, lock), get_next: true }
     }
 }
 /// Allow the collection archive content to be returned as an iterator.
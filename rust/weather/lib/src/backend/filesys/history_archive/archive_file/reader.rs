@@ -1,12 +1,17 @@
 //! The archive file reader.
 //!
 use super::{
-    archive,
+    archive, history,
     iterators::{ArchiveContentIterator, ArchiveDataIterator, ArchiveMetadataIterator},
+    ArchiveProblem, ArchiveVerification,
+};
+use crate::{
+    backend::filesys::{DirLock, LockMode, WeatherFile},
+    entities::DateRange,
 };
-use crate::{backend::filesys::WeatherFile, entities::DateRange};
 use chrono::NaiveDate;
 use std::{
+    collections::HashMap,
     fs::{File, OpenOptions},
     io::{BufReader, Read, Seek},
 };
@@ -29,6 +34,8 @@ pub struct ArchiveReader<R: Read + Seek> {
     pub lid: String,
     /// The zip archive reader
     archive: ZipArchive<R>,
+    /// The weather directory lock held while reading.
+    lock: DirLock,
 }
 impl<R: Read + Seek> ArchiveReader<R> {
     /// Get the dates of all history files in the archive.
@@ -69,6 +76,64 @@ impl<R: Read + Seek> ArchiveReader<R> {
         self.archive.index_for_name(&filename).is_some()
     }
 
+    /// Check the integrity of each history file in the archive.
+    ///
+    /// The history file name must contain a date not used by another history file and be the name
+    /// expected for the date. The history file contents are read, which verifies the checksum, and
+    /// must be a history document for the same date.
+    ///
+    pub fn verify(&mut self) -> ArchiveVerification {
+        let mut verification = ArchiveVerification::default();
+        let mut history_dates: HashMap<NaiveDate, String> = HashMap::new();
+        for index in 0..self.archive.len() {
+            verification.histories += 1;
+            let filename = self.archive.name_for_index(index).unwrap_or_default().to_string();
+            if let Err(problem) = self.verify_file(index, &filename, &mut history_dates) {
+                verification.problems.push(ArchiveProblem { filename, problem });
+            }
+        }
+        verification
+    }
+
+    /// Check the integrity of a history file in the archive.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` is the history file index in the archive.
+    /// * `filename` is the history file name.
+    /// * `history_dates` holds the history file names that have already been checked.
+    ///
+    fn verify_file(
+        &mut self,
+        index: usize,
+        filename: &str,
+        history_dates: &mut HashMap<NaiveDate, String>,
+    ) -> std::result::Result<(), String> {
+        let date = archive::filename_to_date(filename).map_err(|error| error.to_string())?;
+        // only correctly named histories are recorded so a misnamed one cannot make them a duplicate
+        let history_name = archive::date_to_filename(&self.lid, &date);
+        if filename != history_name {
+            return Err(format!("the name should be '{}'.", history_name));
+        }
+        if let Some(other) = history_dates.insert(date, filename.to_string()) {
+            return Err(format!("{} is a duplicate of '{}'.", date, other));
+        }
+        let mut data = vec![];
+        match self.archive.by_index(index) {
+            Ok(mut zipfile) => {
+                if let Err(error) = zipfile.read_to_end(&mut data) {
+                    return Err(format!("read error ({}).", error));
+                }
+            }
+            Err(error) => return Err(format!("open error ({}).", error)),
+        }
+        let history = history::from_bytes(&self.lid, &data).map_err(|error| error.to_string())?;
+        match history.date == date {
+            true => Ok(()),
+            false => Err(format!("the history date is {}.", history.date)),
+        }
+    }
+
     /// Get history metadata for a collection of dates.
     ///
     /// # Arguments
@@ -76,7 +141,7 @@ impl<R: Read + Seek> ArchiveReader<R> {
     /// * `dates` identifies which history metadata will be returned.
     ///
     pub fn metadata_by_date(self, dates: Vec<NaiveDate>) -> crate::Result<ArchiveMetadataIterator<R>> {
-        Ok(ArchiveMetadataIterator::new(&self.lid, self.archive, dates))
+        Ok(ArchiveMetadataIterator::new(&self.lid, self.archive, dates, self.lock))
     }
 
     /// Get history data for a collection of dates.
@@ -86,7 +151,7 @@ impl<R: Read + Seek> ArchiveReader<R> {
     /// * `dates` identifies which history metadata will be returned.
     ///
     pub fn data_by_date(self, dates: Vec<NaiveDate>) -> crate::Result<ArchiveDataIterator<R>> {
-        Ok(ArchiveDataIterator::new(&self.lid, self.archive, dates))
+        Ok(ArchiveDataIterator::new(&self.lid, self.archive, dates, self.lock))
     }
 
     /// Get history data for a collection of dates.
@@ -96,7 +161,7 @@ impl<R: Read + Seek> ArchiveReader<R> {
     /// * `dates` identifies which history metadata will be returned.
     ///
     pub fn content_by_date(self, dates: Vec<NaiveDate>) -> crate::Result<ArchiveContentIterator<R>> {
-        Ok(ArchiveContentIterator::new(&self.lid, self.archive, dates))
+        Ok(ArchiveContentIterator::new(&self.lid, self.archive, dates, self.lock))
     }
 }
 /// Implement the archive reader using a buffered file reader.
@@ -112,7 +177,8 @@ impl ArchiveReader<BufReader<File>> {
     /// * `file` is the archive containing of weather data.
     ///
     pub fn open(lid: &str, file: &WeatherFile) -> crate::Result<Self> {
-        Ok(Self { lid: lid.to_string(), archive: archive::open(file)? })
+        let lock = file.lock(LockMode::Shared)?;
+        Ok(Self { lid: lid.to_string(), archive: archive::open(file)?, lock })
     }
 
     /// Creates a weather data archive.
@@ -125,6 +191,7 @@ impl ArchiveReader<BufReader<File>> {
     /// * `file` is the container of weather data.
     ///
     pub fn create(lid: &str, file: &WeatherFile) -> crate::Result<Self> {
+        let _lock = file.lock(LockMode::Exclusive)?;
         // touch the file
         if let Err(open_error) = OpenOptions::new().create_new(true).write(true).open(&file.to_string()) {
             Err(error!(lid, format!("did not create history file {}: {:?}", file, open_error)))
@@ -1,9 +1,13 @@
 //! The history archive file writer.
 //!
 
-use super::{archive::date_to_filename, ArchiveData};
-use crate::backend::filesys::WeatherFile;
-use chrono::{Datelike, Timelike, Utc};
+use super::{
+    archive::{self, date_to_filename},
+    ArchiveData,
+};
+use crate::backend::filesys::{LockMode, WeatherFile};
+use chrono::{Datelike, NaiveDate, Timelike, Utc};
+use std::collections::HashSet;
 use std::fs::{self, File};
 use std::io::Write;
 use zip::{write::SimpleFileOptions, CompressionMethod, DateTime, ZipWriter};
@@ -48,6 +52,7 @@ impl<'w> ArchiveWriter<'w> {
     ///
     /// `histories` is what will be added to the archive.
     pub fn add_data(&mut self, histories: Vec<ArchiveData>) -> crate::Result<()> {
+        let _lock = self.archive.lock(LockMode::Exclusive)?;
         let mut writer = self.open()?;
         for file_data in histories {
             self.write_file(&mut writer, file_data)?;
@@ -55,6 +60,73 @@ impl<'w> ArchiveWriter<'w> {
         self.close(writer)
     }
 
+    /// Moves history files out of the archive and into a quarantine archive. The archive is rewritten
+    /// without the history files. A history file already in the quarantine archive is renamed with a
+    /// numbered suffix. The dates of the history files with a valid history name are returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `filenames` are the history files that will be moved.
+    /// * `quarantine` is the archive that will hold the history files.
+    pub fn quarantine(&mut self, filenames: &[String], quarantine: &WeatherFile) -> crate::Result<Vec<NaiveDate>> {
+        let _lock = self.archive.lock(LockMode::Exclusive)?;
+        let mut reader = archive::open(self.archive)?;
+        let mut quarantined: HashSet<String> = match quarantine.exists() {
+            true => archive::open(quarantine)?.file_names().map(String::from).collect(),
+            false => HashSet::new(),
+        };
+        let mut quarantine_writer = self.create(quarantine, quarantine.exists())?;
+        let mut writer = self.create(&self.archive.with_extension(UPDATE_EXT), false)?;
+        for index in 0..reader.len() {
+            let zipfile = match reader.by_index_raw(index) {
+                Ok(zipfile) => zipfile,
+                Err(error) => Err(error!(self.lid, format!("failed to read history file {}: {}.", index, error)))?,
+            };
+            let filename = zipfile.name().to_string();
+            let copy_result = match filenames.contains(&filename) {
+                true => {
+                    let quarantine_name = quarantine_name(&filename, &quarantined);
+                    quarantined.insert(quarantine_name.clone());
+                    quarantine_writer.raw_copy_file_rename(zipfile, quarantine_name)
+                }
+                false => writer.raw_copy_file(zipfile),
+            };
+            if let Err(error) = copy_result {
+                Err(error!(self.lid, format!("failed to copy '{}': {}.", filename, error)))?;
+            }
+        }
+        drop(reader);
+        if let Err(error) = quarantine_writer.finish() {
+            Err(error!(self.lid, format!("failed to finish quarantine {}: {}.", quarantine, error)))?;
+        }
+        self.close(writer)?;
+        let dates = filenames
+            .iter()
+            .filter_map(|filename| archive::filename_to_date(filename).ok().map(|date| (filename, date)))
+            .filter(|(filename, date)| **filename == date_to_filename(&self.lid, date))
+            .map(|(_, date)| date)
+            .collect();
+        Ok(dates)
+    }
+
+    /// Creates a [ZipWriter] for an archive.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` is the archive that will be written.
+    /// * `append` when true will add to an existing archive.
+    fn create(&self, file: &WeatherFile, append: bool) -> crate::Result<ZipWriter<File>> {
+        match append {
+            true => file.writer().and_then(|file| {
+                ZipWriter::new_append(file).map_err(|error| error!(self.lid, format!("failed to append: {}", error)))
+            }),
+            false => match File::create(file.path()) {
+                Ok(file) => Ok(ZipWriter::new(file)),
+                Err(error) => Err(error!(self.lid, format!("failed to create {}: {}.", file, error))),
+            },
+        }
+    }
+
     /// Writes history into the archive.
     ///
     /// # Arguments
@@ -189,3 +261,24 @@ impl<'w> Drop for ArchiveWriter<'w> {
         }
     }
 }
+
+/// Get the name a history file will have in the quarantine archive. If the name is already used a
+/// numbered suffix is added before the extension.
+///
+/// # Arguments
+///
+/// * `filename` is the history file name.
+/// * `quarantined` are the names already in the quarantine archive.
+fn quarantine_name(filename: &str, quarantined: &HashSet<String>) -> String {
+    if !quarantined.contains(filename) {
+        return filename.to_string();
+    }
+    let (stem, extension) = filename.rsplit_once('.').unwrap_or((filename, ""));
+    (1..)
+        .map(|suffix| match extension.is_empty() {
+            true => format!("{}-{}", stem, suffix),
+            false => format!("{}-{}.{}", stem, suffix, extension),
+        })
+        .find(|name| !quarantined.contains(name))
+        .unwrap()
+}
@@ -4,11 +4,13 @@ mod locations_file;
 mod validate;
 
 use crate::{
-    backend::filesys::{HistoryArchive, WeatherDir},
+    admin_prelude::Recovery,
+    backend::filesys::{HistoryArchive, LockMode, WeatherDir, WeatherFile},
     entities::{Location, LocationFilters},
     location_filters,
 };
 use locations_file::{LocationDocument, LocationsFile};
+use std::io::Read;
 
 /// Create a Locations specific error message.
 macro_rules! error {
@@ -47,6 +49,59 @@ impl<'w> Locations<'w> {
         Ok(Self { file, weather_dir })
     }
 
+    /// Tests if a file stem is the same as the locations file.
+    ///
+    /// # Arguments
+    ///
+    /// * `stem` is the file name without an extension.
+    ///
+    pub fn is_locations(stem: &str) -> bool {
+        LocationsFile::is_locations(stem)
+    }
+
+    /// Tests if the locations file has update or backup files.
+    ///
+    /// # Arguments
+    ///
+    /// * `weather_dir` is the location of the locations file.
+    ///
+    pub fn is_interrupted(weather_dir: &WeatherDir) -> bool {
+        LocationsFile::is_interrupted(weather_dir)
+    }
+
+    /// Roll an interrupted update of the locations file forward or back.
+    ///
+    /// # Arguments
+    ///
+    /// * `weather_dir` is the location of the locations file.
+    ///
+    pub fn recover(weather_dir: &WeatherDir) -> crate::Result<Option<Recovery>> {
+        LocationsFile::recover(weather_dir)
+    }
+
+    /// Get the locations file in the weather directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `weather_dir` is the location of the locations file.
+    ///
+    pub fn file(weather_dir: &WeatherDir) -> WeatherFile {
+        LocationsFile::file(weather_dir)
+    }
+
+    /// Get locations from a locations document that is not in the weather directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` has the locations document contents.
+    /// * `source` describes where the locations document came from.
+    /// * `filters` are used select locations.
+    ///
+    pub fn read(reader: impl Read, source: &str, filters: LocationFilters) -> crate::Result<Vec<Location>> {
+        let documents: Vec<LocationDocument> = LocationsFile::read(reader, source)?.collect();
+        Ok(LocationsIterator::new(Box::new(documents.into_iter()), filters).collect())
+    }
+
     /// Get all locations.
     ///
     pub fn get(&self) -> crate::Result<impl Iterator<Item = Location>> {
@@ -71,17 +126,12 @@ impl<'w> Locations<'w> {
     ///
     /// * `location` is the location that will be added.
     ///
-    pub fn add(&self, mut location: Location) -> crate::Result<Location> {
+    pub fn add(&self, location: Location) -> crate::Result<Location> {
         // even though it should come in okay, validate JIC
-        location.city = validate::city(&location.city)?;
-        location.state_id = validate::city(&location.state_id)?;
-        location.state = validate::city(&location.state)?;
-        // todo: this can go away once not persisting
-        location.name = validate::name(&location.name)?;
-        location.alias = validate::alias(&location.alias)?;
-        location.latitude = validate::latitude(&location.latitude)?;
-        location.longitude = validate::longitude(&location.longitude)?;
-        location.tz = validate::tz(&location.tz)?;
+        let location = validate_location(location)?;
+
+        // hold the lock until the location and its archive have been added
+        let _lock = self.weather_dir.lock(LockMode::Exclusive)?;
 
         // get the file contents and make sure the alias is unique
         let mut location_documents: Vec<LocationDocument> = self.file.load()?.collect();
@@ -99,6 +149,64 @@ impl<'w> Locations<'w> {
         HistoryArchive::create(&location.alias, archive)?;
         Ok(location)
     }
+
+    /// Replace the locations document or update it with locations restored from a backup. The
+    /// location archives are not changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `locations` are the restored locations.
+    /// * `replace` when `true` replaces all locations otherwise locations with the same alias are
+    ///   replaced and the others are added.
+    ///
+    pub fn restore(&self, locations: &[Location], replace: bool) -> crate::Result<()> {
+        let _lock = self.weather_dir.lock(LockMode::Exclusive)?;
+        let mut location_documents: Vec<LocationDocument> = match replace {
+            true => vec![],
+            false => self
+                .file
+                .load()?
+                .filter(|document| !locations.iter().any(|location| location.alias == document.alias))
+                .collect(),
+        };
+        location_documents.extend(locations.iter().map(LocationDocument::from));
+        location_documents.sort_by(|lhs, rhs| lhs.name.cmp(&rhs.name));
+        self.file.save(location_documents)
+    }
+}
+
+/// Validate the location metadata. The location is returned with its values cleaned up.
+///
+/// # Arguments
+///
+/// * `location` is the location that will be validated.
+///
+pub(in crate::backend) fn validate_location(mut location: Location) -> crate::Result<Location> {
+    location.city = validate::city(&location.city)?;
+    location.state_id = validate::city(&location.state_id)?;
+    location.state = validate::city(&location.state)?;
+    // todo: this can go away once not persisting
+    location.name = validate::name(&location.name)?;
+    location.alias = validate::alias(&location.alias)?;
+    location.latitude = validate::latitude(&location.latitude)?;
+    location.longitude = validate::longitude(&location.longitude)?;
+    location.tz = validate::tz(&location.tz)?;
+    Ok(location)
+}
+
+/// Select locations the same way they are selected from the locations document.
+///
+/// # Arguments
+///
+/// * `locations` are the locations that will be filtered.
+/// * `filters` select which locations will be returned.
+///
+pub(in crate::backend) fn filter_locations(
+    locations: &[Location],
+    filters: LocationFilters,
+) -> impl Iterator<Item = Location> {
+    let documents: Vec<LocationDocument> = locations.iter().map(LocationDocument::from).collect();
+    LocationsIterator::new(Box::new(documents.into_iter()), filters)
 }
 
 /// An iterator that returns locations from a source JSON document. The iterator
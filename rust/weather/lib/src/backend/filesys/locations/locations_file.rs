@@ -1,8 +1,10 @@
 use super::validate;
-use crate::backend::filesys::{WeatherDir, WeatherFile};
+use crate::admin_prelude::Recovery;
+use crate::backend::filesys::{recovery::recover_file, LockMode, WeatherDir, WeatherFile};
 use crate::entities::Location;
 use serde::{Deserialize, Serialize};
-use std::io::{BufWriter, Write};
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
 
 /// The name of the locations document in the weather data directory.
 const LOCATIONS_FILENAME: &'static str = "locations.json";
@@ -43,6 +45,45 @@ impl LocationsFile {
         weather_dir.file(LOCATIONS_FILENAME).exists()
     }
 
+    /// Tests if a file stem is the same as the locations file.
+    ///
+    /// Arguments
+    ///
+    /// * `stem` is the file name without an extension.
+    ///
+    pub fn is_locations(stem: &str) -> bool {
+        Path::new(LOCATIONS_FILENAME).file_stem().is_some_and(|locations| locations == stem)
+    }
+
+    /// Tests if the location file has update or backup files.
+    ///
+    /// Arguments
+    ///
+    /// * `weather_dir` is the weather directory.
+    ///
+    pub fn is_interrupted(weather_dir: &WeatherDir) -> bool {
+        let file = weather_dir.file(LOCATIONS_FILENAME);
+        file.with_extension(UPDATE_EXTENSION).exists() || file.with_extension(BACKUP_EXTENSION).exists()
+    }
+
+    /// Roll an interrupted update of the location file forward or back. The update is complete if
+    /// it is a valid locations document.
+    ///
+    /// Arguments
+    ///
+    /// * `weather_dir` is the weather directory.
+    ///
+    pub fn recover(weather_dir: &WeatherDir) -> crate::Result<Option<Recovery>> {
+        let file = weather_dir.file(LOCATIONS_FILENAME);
+        let update_file = file.with_extension(UPDATE_EXTENSION);
+        let backup_file = file.with_extension(BACKUP_EXTENSION);
+        let valid = |file: &WeatherFile| match file.reader() {
+            Ok(reader) => serde_json::from_reader::<_, LocationDocuments>(reader).is_ok(),
+            Err(_) => false,
+        };
+        recover_file(&file, &update_file, &backup_file, || valid(&update_file), valid)
+    }
+
     /// Opens the location file in the weather directory returning an error if the file does not
     /// exist.
     ///
@@ -77,13 +118,35 @@ impl LocationsFile {
         }
     }
 
+    /// Get the locations file in the weather directory.
+    ///
+    /// Arguments
+    ///
+    /// * `weather_dir` is the weather directory.
+    ///
+    pub fn file(weather_dir: &WeatherDir) -> WeatherFile {
+        weather_dir.file(LOCATIONS_FILENAME)
+    }
+
     /// Read the contents of the location file.
     ///
     pub fn load(&self) -> crate::Result<impl Iterator<Item = LocationDocument>> {
+        let _lock = self.file.lock(LockMode::Shared)?;
         let reader = self.file.reader()?;
+        Self::read(reader, &self.file.to_string())
+    }
+
+    /// Read the contents of a locations document.
+    ///
+    /// Arguments
+    ///
+    /// * `reader` has the locations document contents.
+    /// * `source` describes where the locations document came from.
+    ///
+    pub fn read(reader: impl Read, source: &str) -> crate::Result<impl Iterator<Item = LocationDocument>> {
         let result: Result<LocationDocuments, serde_json::Error> = serde_json::from_reader(reader);
         match result {
-            Err(error) => err!("failed to load locations from {}: {:?}", self.file, error),
+            Err(error) => err!("failed to load locations from {}: {:?}", source, error),
             Ok(mut documents) => {
                 documents = documents.validate_and_dedup();
                 Ok(documents.into_iter())
@@ -98,6 +161,7 @@ impl LocationsFile {
     /// * `documents` replaces the locations file contents.
     ///
     pub fn save(&self, documents: Vec<LocationDocument>) -> crate::Result<()> {
+        let _lock = self.file.lock(LockMode::Exclusive)?;
         // make sure the update file doesn't exist
         let update_file = self.file.with_extension(UPDATE_EXTENSION);
         if update_file.exists() {
@@ -0,0 +1,179 @@
+//! Recovers weather data files whose update was interrupted.
+//!
+//! History archives and the locations document are updated the same way. The changes are written
+//! to an update file, the original is copied to a backup file, the update file is renamed over the
+//! original, and then the backup file is removed. If the process stops part way through the update
+//! or backup files are left in the weather data directory.
+//!
+//! An update file that is complete replaces the original (rolled forward), otherwise it is removed
+//! (rolled back). If the original is not valid but the backup is, the backup replaces it.
+
+use super::{HistoryArchive, LockMode, Locations, WeatherDir, WeatherFile};
+use crate::admin_prelude::{Recovery, RecoveryAction};
+use std::{collections::BTreeSet, fs};
+
+/// Recover the interrupted updates in the weather data directory.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+///
+pub fn recover(weather_dir: &WeatherDir) -> crate::Result<Vec<Recovery>> {
+    let mut recoveries = vec![];
+    // an update in progress looks the same as an interrupted update, wait for it to finish
+    if !is_interrupted(weather_dir)? {
+        return Ok(recoveries);
+    }
+    let _lock = weather_dir.lock(LockMode::Exclusive)?;
+    if let Some(recovery) = Locations::recover(weather_dir)? {
+        recoveries.push(recovery);
+    }
+    for alias in interrupted_archives(weather_dir)? {
+        if let Some(recovery) = HistoryArchive::recover(&alias, weather_dir.archive(&alias))? {
+            recoveries.push(recovery);
+        }
+    }
+    Ok(recoveries)
+}
+
+/// Recover the interrupted updates in the weather data directory and log what was done.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+///
+pub fn recover_and_log(weather_dir: &WeatherDir) -> crate::Result<()> {
+    for recovery in recover(weather_dir)? {
+        log::warn!("Interrupted update of {} was {}.", recovery.filename, recovery.action);
+    }
+    Ok(())
+}
+
+/// Tests if there are update or backup files in the weather data directory.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+///
+fn is_interrupted(weather_dir: &WeatherDir) -> crate::Result<bool> {
+    Ok(Locations::is_interrupted(weather_dir) || !interrupted_archives(weather_dir)?.is_empty())
+}
+
+/// Get the aliases of archives that have update or backup files in the weather data directory.
+///
+/// # Arguments
+///
+/// * `weather_dir` is the weather data directory.
+///
+fn interrupted_archives(weather_dir: &WeatherDir) -> crate::Result<BTreeSet<String>> {
+    let entries = match fs::read_dir(weather_dir.path()) {
+        Ok(entries) => entries,
+        Err(error) => Err(crate::Error::from(format!("Recovery could not read {} ({}).", weather_dir, error)))?,
+    };
+    let aliases = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && HistoryArchive::is_interrupted(path))
+        .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()).map(|stem| stem.to_string()))
+        .filter(|alias| !Locations::is_locations(alias))
+        .collect();
+    Ok(aliases)
+}
+
+/// Roll an interrupted update forward or back.
+///
+/// # Arguments
+///
+/// * `original` is the file that was being updated.
+/// * `update` is the file that was holding the changes.
+/// * `backup` is the copy of the original file.
+/// * `complete` checks if the update file is complete.
+/// * `valid` checks if a file is valid.
+///
+pub fn recover_file(
+    original: &WeatherFile,
+    update: &WeatherFile,
+    backup: &WeatherFile,
+    complete: impl FnOnce() -> bool,
+    valid: impl Fn(&WeatherFile) -> bool,
+) -> crate::Result<Option<Recovery>> {
+    if !update.exists() && !backup.exists() {
+        return Ok(None);
+    }
+    let action = if update.exists() && complete() {
+        update.rename(original)?;
+        RecoveryAction::RolledForward
+    } else {
+        update.remove()?;
+        if backup.exists() && !(original.exists() && valid(original)) && valid(backup) {
+            backup.rename(original)?;
+            RecoveryAction::Restored
+        } else {
+            RecoveryAction::RolledBack
+        }
+    };
+    backup.remove()?;
+    Ok(Some(Recovery { filename: original.filename.clone(), action }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::testlib;
+    use std::path::PathBuf;
+
+    #[test]
+    fn recover_files() {
+        let fixture = testlib::TestFixture::create();
+        let file = |name: &str| WeatherFile::new(PathBuf::from(&fixture).join(name));
+        let (original, update, backup) = (file("test.dat"), file("test.upd"), file("test.bu"));
+        let valid = |file: &WeatherFile| fs::read_to_string(file.path()).unwrap_or_default() != "bad";
+        fs::write(original.path(), "original").unwrap();
+        assert!(recover_file(&original, &update, &backup, || true, valid).unwrap().is_none());
+        // a complete update rolls forward
+        fs::write(update.path(), "update").unwrap();
+        fs::write(backup.path(), "original").unwrap();
+        let testcase = recover_file(&original, &update, &backup, || true, valid).unwrap().unwrap();
+        assert_eq!(testcase.action, RecoveryAction::RolledForward);
+        assert_eq!(fs::read_to_string(original.path()).unwrap(), "update");
+        assert!(!update.exists() && !backup.exists());
+        // an incomplete update rolls back
+        fs::write(update.path(), "partial").unwrap();
+        let testcase = recover_file(&original, &update, &backup, || false, valid).unwrap().unwrap();
+        assert_eq!(testcase.action, RecoveryAction::RolledBack);
+        assert_eq!(fs::read_to_string(original.path()).unwrap(), "update");
+        assert!(!update.exists());
+        // a bad original is restored from the backup
+        fs::write(original.path(), "bad").unwrap();
+        fs::write(backup.path(), "backup").unwrap();
+        let testcase = recover_file(&original, &update, &backup, || false, valid).unwrap().unwrap();
+        assert_eq!(testcase.action, RecoveryAction::Restored);
+        assert_eq!(fs::read_to_string(original.path()).unwrap(), "backup");
+        assert!(!backup.exists());
+    }
+
+    #[test]
+    fn recover_dir() {
+        let fixture = testlib::TestFixture::create();
+        let weather_dir = WeatherDir::new(PathBuf::from(&fixture)).unwrap();
+        Locations::open(&weather_dir).unwrap();
+        HistoryArchive::create("test", weather_dir.archive("test")).unwrap();
+        assert!(recover(&weather_dir).unwrap().is_empty());
+        // a partial archive update and locations update are rolled back
+        fs::write(weather_dir.file("test.upd").path(), "PK").unwrap();
+        weather_dir.archive("test").copy(&weather_dir.file("test.bu")).unwrap();
+        fs::write(weather_dir.file("locations.upd").path(), "{\"locations\": [").unwrap();
+        let testcase = recover(&weather_dir).unwrap();
+        assert_eq!(testcase.len(), 2);
+        assert_eq!(testcase[0].filename, "locations.json");
+        assert_eq!(testcase[0].action, RecoveryAction::RolledBack);
+        assert_eq!(testcase[1].filename, "test.zip");
+        assert_eq!(testcase[1].action, RecoveryAction::RolledBack);
+        assert!(HistoryArchive::open("test", weather_dir.archive("test")).is_ok());
+        // a complete archive update rolls forward
+        weather_dir.archive("test").copy(&weather_dir.file("test.upd")).unwrap();
+        let testcase = recover(&weather_dir).unwrap();
+        assert_eq!(testcase.len(), 1);
+        assert_eq!(testcase[0].action, RecoveryAction::RolledForward);
+        assert!(recover(&weather_dir).unwrap().is_empty());
+    }
+}
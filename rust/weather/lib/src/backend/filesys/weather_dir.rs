@@ -1,10 +1,16 @@
 #![allow(unused)]
 
 use crate::{
-    backend::{filesys::WeatherFile, Config},
+    backend::{
+        filesys::{DirLock, LockMode, WeatherFile},
+        Config,
+    },
     Error, Result,
 };
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 /// The [crate::backend::filesys::WeatherDir] error builder.
 macro_rules! error {
@@ -18,6 +24,8 @@ macro_rules! error {
 pub struct WeatherDir(
     /// The directory managed by the weather directory.
     PathBuf,
+    /// How long to wait for the directory lock.
+    Duration,
 );
 
 impl std::fmt::Display for WeatherDir {
@@ -47,7 +55,8 @@ impl TryFrom<&str> for WeatherDir {
 impl TryFrom<&Config> for WeatherDir {
     type Error = Error;
     fn try_from(config: &Config) -> std::result::Result<Self, Self::Error> {
-        WeatherDir::new(PathBuf::from(&config.weather_data.directory))
+        let weather_dir = WeatherDir::new(PathBuf::from(&config.weather_data.directory))?;
+        Ok(weather_dir.with_lock_timeout(Duration::from_secs(config.weather_data.lock_timeout)))
     }
 }
 
@@ -61,21 +70,42 @@ impl WeatherDir {
     /// * `directory_name` is the name of the directory.
     pub fn new(path: PathBuf) -> Result<WeatherDir> {
         match path.is_dir() {
-            true => Ok(WeatherDir(path)),
+            true => Ok(WeatherDir(path, WeatherFile::DEFAULT_LOCK_TIMEOUT)),
             false => Err(error!(path.display().to_string(), "Not a directory...")),
         }
     }
+    /// Set how long to wait for the directory lock.
+    ///
+    /// # Arguments
+    ///
+    /// * `lock_timeout` is how long to wait.
+    pub fn with_lock_timeout(mut self, lock_timeout: Duration) -> Self {
+        self.1 = lock_timeout;
+        self
+    }
+    /// Get how long to wait for the directory lock.
+    pub fn lock_timeout(&self) -> Duration {
+        self.1
+    }
+    /// Lock the weather directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` is the kind of lock.
+    pub fn lock(&self, mode: LockMode) -> Result<DirLock> {
+        DirLock::acquire(&self.0, mode, self.1)
+    }
     /// Get a weather file from within the managed directory.
     ///
     /// # Arguments
     ///
     /// * `filename` is the name of the file within the weather directory.
     pub fn file(&self, filename: &str) -> WeatherFile {
-        WeatherFile::new(self.0.join(filename))
+        WeatherFile::new(self.0.join(filename)).with_lock_timeout(self.1)
     }
     pub fn archive(&self, alias: &str) -> WeatherFile {
         let archive_name = self.0.join(alias).with_extension("zip");
-        WeatherFile::new(archive_name)
+        WeatherFile::new(archive_name).with_lock_timeout(self.1)
     }
     /// Get the weather directory path.
     pub fn path(&self) -> &Path {
@@ -1,8 +1,10 @@
 #![allow(unused)]
+use super::{DirLock, LockMode};
 use std::path::{Path, PathBuf};
 use std::{
     fs::{self, File, Metadata, OpenOptions},
     io::ErrorKind,
+    time::Duration,
 };
 
 /// The [WeatherFile] error builder.
@@ -19,6 +21,8 @@ pub struct WeatherFile {
     pub filename: String,
     /// The file path.
     path: PathBuf,
+    /// How long to wait for the weather directory lock.
+    lock_timeout: Duration,
 }
 
 impl std::fmt::Display for WeatherFile {
@@ -29,6 +33,9 @@ impl std::fmt::Display for WeatherFile {
 }
 
 impl WeatherFile {
+    /// How long to wait for the weather directory lock if it is not configured.
+    pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
     /// Create the manager for files in the weather directory.
     ///
     /// # Arguments
@@ -37,7 +44,18 @@ impl WeatherFile {
     pub fn new(path: PathBuf) -> Self {
         // this should always work since the path comes from a DirEntry
         let filename = path.file_name().unwrap().to_str().unwrap().to_string();
-        WeatherFile { filename, path }
+        WeatherFile { filename, path, lock_timeout: Self::DEFAULT_LOCK_TIMEOUT }
+    }
+
+    /// Set how long to wait for the weather directory lock.
+    ///
+    /// # Argument
+    ///
+    /// * `lock_timeout` is how long to wait.
+    ///
+    pub fn with_lock_timeout(mut self, lock_timeout: Duration) -> Self {
+        self.lock_timeout = lock_timeout;
+        self
     }
 
     /// Return the weather file with a new file extension.
@@ -47,7 +65,21 @@ impl WeatherFile {
     /// * `extension` is the new file extension.
     ///
     pub fn with_extension(&self, extension: &str) -> Self {
-        WeatherFile::new(self.path.with_extension(extension))
+        WeatherFile::new(self.path.with_extension(extension)).with_lock_timeout(self.lock_timeout)
+    }
+
+    /// Lock the weather directory that contains the file.
+    ///
+    /// # Argument
+    ///
+    /// * `mode` is the kind of lock.
+    ///
+    pub fn lock(&self, mode: LockMode) -> crate::Result<DirLock> {
+        let dir = match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        DirLock::acquire(dir, mode, self.lock_timeout)
     }
 
     /// Indicates if the file exists or does not.
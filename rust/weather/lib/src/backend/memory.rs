@@ -0,0 +1,400 @@
+//! The weather data implementation that keeps locations and histories in memory.
+//!
+//! Nothing is read from or written to a weather data directory, the weather data lasts as long as
+//! the backend. It is used for tests and short-lived sessions that need the weather data `API`
+//! without creating files. Selecting locations and searching history descriptions behave the same
+//! as the archive implementation. The history client query usage is also kept in memory and the US
+//! Cities cannot be searched.
+
+use super::filesys::{filter_locations, validate_location, words};
+use crate::{
+    backend::{Backend, Config, LocationFilters},
+    history_client::UsageLedger,
+    entities::{
+        DailyHistories, DateRange, DateRanges, History, HistoryDates, HistorySummaries, Location, State, CityFilter,
+    },
+};
+use chrono::NaiveDate;
+use std::{
+    collections::{btree_map::Entry, BTreeMap, HashMap},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+/// Create a memory backend specific error message.
+macro_rules! error {
+    ($($arg:tt)*) => {
+        crate::Error::from(format!("MemoryBackend {}", format!($($arg)*)))
+    }
+}
+
+/// Create an error from the memory backend specific error message.
+macro_rules! err {
+    ($($arg:tt)*) => {
+        Err(error!($($arg)*))
+    };
+}
+
+/// The reason US Cities cannot be searched.
+const US_CITIES_UNAVAILABLE: &str = "US Cities are not available when weather data is kept in memory.";
+
+/// Creates the in memory data API for weather data.
+///
+/// # Arguments
+///
+/// * `config` contains the weather data configuration.
+///
+pub fn create_memory_backend(config: Config) -> crate::Result<Box<dyn Backend>> {
+    log::debug!("MemoryBackend");
+    Ok(Box::new(MemoryBackend {
+        config,
+        weather_data: Mutex::new(WeatherData::default()),
+        usage_ledger: UsageLedger::in_memory(),
+    }))
+}
+
+/// The locations and their histories.
+#[derive(Debug, Default)]
+struct WeatherData {
+    /// The locations in name order.
+    locations: Vec<Location>,
+    /// The location histories by alias, in date order.
+    histories: HashMap<String, BTreeMap<NaiveDate, History>>,
+}
+impl WeatherData {
+    /// Get the histories of a location.
+    ///
+    /// # Arguments
+    ///
+    /// * `alias` is the location alias.
+    ///
+    fn histories(&self, alias: &str) -> impl Iterator<Item = &History> {
+        self.histories.get(alias).into_iter().flat_map(|histories| histories.values())
+    }
+}
+
+/// The in memory implementation of a [Backend].
+struct MemoryBackend {
+    /// The weather data configuration.
+    config: Config,
+    /// The locations and their histories.
+    weather_data: Mutex<WeatherData>,
+    /// The history client query costs, there is no directory to keep them in.
+    usage_ledger: UsageLedger,
+}
+impl MemoryBackend {
+    /// Get the weather data. A panic while the weather data was being changed does not make it
+    /// unusable because changes are made after they have been validated.
+    fn weather_data(&self) -> MutexGuard<'_, WeatherData> {
+        self.weather_data.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+impl Backend for MemoryBackend {
+    /// Get the backend configuration.
+    ///
+    fn get_config(&self) -> &Config {
+        &self.config
+    }
+
+    fn get_usage_ledger(&self) -> UsageLedger {
+        self.usage_ledger.clone()
+    }
+
+    /// Add weather data history for a location. Histories for dates that already have history
+    /// are not added.
+    ///
+    /// # Arguments
+    ///
+    /// * `daily_histories` has the location and histories to add.
+    ///
+    fn add_daily_histories(&self, daily_histories: DailyHistories) -> crate::Result<usize> {
+        crate::log_elapsed_time!(trace, "add_daily_histories");
+        let alias = &daily_histories.location.alias;
+        let mut weather_data = self.weather_data();
+        if !weather_data.locations.iter().any(|location| &location.alias == alias) {
+            err!("location '{}' was not found.", alias)?;
+        }
+        let histories = weather_data.histories.entry(alias.clone()).or_default();
+        let mut additions = 0;
+        for history in daily_histories.histories {
+            if let Entry::Vacant(entry) = histories.entry(history.date) {
+                entry.insert(History { alias: alias.clone(), ..history });
+                additions += 1;
+            }
+        }
+        Ok(additions)
+    }
+
+    /// Returns the daily weather data history for a location.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` identifies what location should be used.
+    /// * `history_range` specifies the date range that should be used.
+    ///
+    fn get_daily_histories(&self, filters: LocationFilters, history_range: DateRange) -> crate::Result<DailyHistories> {
+        let mut locations = self.get_locations(filters)?;
+        let location = match locations.len() {
+            1 => locations.pop().unwrap(),
+            0 => err!("a location was not found.")?,
+            _ => err!("Multiple locations were found.")?,
+        };
+        self.get_location_histories(location, history_range)
+    }
+
+    /// Get daily weather history for a location selected by its alias.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` is the location whose history is returned.
+    /// * `history_range` covers the history dates returned.
+    ///
+    fn get_location_histories(&self, location: Location, history_range: DateRange) -> crate::Result<DailyHistories> {
+        crate::log_elapsed_time!(trace, "get_location_histories");
+        let histories = self
+            .weather_data()
+            .histories(&location.alias)
+            .filter(|history| history_range.covers(&history.date))
+            .cloned()
+            .collect();
+        Ok(DailyHistories { location, histories })
+    }
+
+    /// Get the weather history dates for locations.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` identifies the locations.
+    ///
+    fn get_history_dates(&self, filters: LocationFilters) -> crate::Result<Vec<HistoryDates>> {
+        let locations = self.get_locations(filters)?;
+        crate::log_elapsed_time!(trace, "get_history_dates");
+        let weather_data = self.weather_data();
+        let history_dates = locations
+            .into_iter()
+            .map(|location| {
+                let dates = weather_data.histories(&location.alias).map(|history| history.date).collect();
+                let history_dates = DateRanges::new(&location.alias, dates).date_ranges;
+                HistoryDates { location, history_dates }
+            })
+            .collect();
+        Ok(history_dates)
+    }
+
+    /// Get the summary metrics of a locations weather data. There is no backing store so only the
+    /// history count is available.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` identifies the locations that should be used.
+    ///
+    fn get_history_summaries(&self, filters: LocationFilters) -> crate::Result<Vec<HistorySummaries>> {
+        let locations = self.get_locations(filters)?;
+        crate::log_elapsed_time!(trace, "get_history_summaries");
+        let weather_data = self.weather_data();
+        let history_summaries = locations
+            .into_iter()
+            .map(|location| {
+                let count = weather_data.histories(&location.alias).count();
+                HistorySummaries { location, count, overall_size: None, raw_size: None, store_size: None }
+            })
+            .collect();
+        Ok(history_summaries)
+    }
+
+    /// Search the weather history descriptions of locations. A description matches when it contains
+    /// all the words in the query, ignoring case.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` identifies the locations that should be used.
+    /// * `query` contains the words a history description must have.
+    /// * `history_range` restricts the history dates searched, if `None` all history is searched.
+    ///
+    fn search_histories(
+        &self,
+        filters: LocationFilters,
+        query: &str,
+        history_range: Option<DateRange>,
+    ) -> crate::Result<Vec<DailyHistories>> {
+        let locations = self.get_locations(filters)?;
+        crate::log_elapsed_time!(trace, "search_histories");
+        let query_words = words(query);
+        if query_words.is_empty() {
+            err!("the search query does not contain any words.")?;
+        }
+        let is_match = |history: &&History| {
+            let in_range = history_range.as_ref().is_none_or(|history_range| history_range.covers(&history.date));
+            let description_words = history.description.as_deref().map_or(vec![], words);
+            in_range && query_words.iter().all(|word| description_words.contains(word))
+        };
+        let weather_data = self.weather_data();
+        let mut search_histories = vec![];
+        for location in locations {
+            let histories: Vec<History> = weather_data.histories(&location.alias).filter(is_match).cloned().collect();
+            if !histories.is_empty() {
+                search_histories.push(DailyHistories { location, histories });
+            }
+        }
+        Ok(search_histories)
+    }
+
+    /// Get the metadata for weather locations.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` identifies the locations of interest.
+    ///
+    fn get_locations(&self, filters: LocationFilters) -> crate::Result<Vec<Location>> {
+        crate::log_elapsed_time!(trace, "get_locations");
+        Ok(filter_locations(&self.weather_data().locations, filters).collect())
+    }
+
+    /// Add a new weather location.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` is the location that will be added.
+    ///
+    fn add_location(&self, location: Location) -> crate::Result<()> {
+        crate::log_elapsed_time!(trace, "add_location");
+        let location = validate_location(location)?;
+        let mut weather_data = self.weather_data();
+        if let Some(existing) = weather_data.locations.iter().find(|existing| existing.alias == location.alias) {
+            err!("{} already uses the '{}' alias name", existing.name, existing.alias)?;
+        }
+        weather_data.locations.push(location);
+        weather_data.locations.sort_by(|lhs, rhs| lhs.name.cmp(&rhs.name));
+        Ok(())
+    }
+
+    /// The US Cities database is kept in the weather data directory so there are no cities to
+    /// search.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` identifies which cities are being searched for (default is all).
+    ///
+    fn search_locations(&self, _filter: CityFilter) -> crate::Result<Vec<Location>> {
+        err!("{}", US_CITIES_UNAVAILABLE)
+    }
+
+    /// The US Cities database is kept in the weather data directory so there are no states.
+    ///
+    fn get_states(&self) -> crate::Result<Vec<State>> {
+        err!("{}", US_CITIES_UNAVAILABLE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{location_filter, location_filters};
+    use std::path::PathBuf;
+
+    use crate::backend::testlib::location;
+
+    fn history(date: NaiveDate, description: &str) -> History {
+        History { date, description: Some(description.to_string()), ..Default::default() }
+    }
+
+    #[test]
+    fn memory_backend() {
+        let backend = create_memory_backend(Config::try_from("").unwrap()).unwrap();
+        backend.add_location(location("Tigard", "tigard")).unwrap();
+        backend.add_location(location("Portland", "pdx")).unwrap();
+        assert!(backend.add_location(location("Beaverton", "tigard")).is_err());
+        let locations = backend.get_locations(location_filters![]).unwrap();
+        assert_eq!(locations.iter().map(|location| location.alias.as_str()).collect::<Vec<_>>(), ["pdx", "tigard"]);
+        let locations = backend.get_locations(location_filters![location_filter!(name = "tig*")]).unwrap();
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].alias, "tigard");
+
+        let date = NaiveDate::from_ymd_opt(2024, 7, 1).unwrap();
+        let histories = vec![
+            history(date, "Clear conditions throughout the day."),
+            history(date + chrono::Days::new(1), "Partly cloudy with afternoon rain."),
+            history(date + chrono::Days::new(3), "Clear conditions throughout the day."),
+        ];
+        let daily_histories = DailyHistories { location: location("Tigard", "tigard"), histories };
+        assert_eq!(backend.add_daily_histories(daily_histories).unwrap(), 3);
+        let histories = vec![history(date, "Duplicate date."), history(date + chrono::Days::new(2), "Rain.")];
+        let daily_histories = DailyHistories { location: location("Tigard", "tigard"), histories };
+        assert_eq!(backend.add_daily_histories(daily_histories).unwrap(), 1);
+        let daily_histories = DailyHistories { location: location("Salem", "salem"), histories: vec![] };
+        assert!(backend.add_daily_histories(daily_histories).is_err());
+
+        let range = DateRange::new(date + chrono::Days::new(1), date + chrono::Days::new(2));
+        let daily_histories =
+            backend.get_daily_histories(location_filters![location_filter!(name = "tigard")], range).unwrap();
+        assert_eq!(daily_histories.histories.len(), 2);
+        assert_eq!(daily_histories.histories[0].alias, "tigard");
+        assert_eq!(daily_histories.histories[0].date, date + chrono::Days::new(1));
+
+        let history_dates = backend.get_history_dates(location_filters![]).unwrap();
+        assert!(history_dates[0].history_dates.is_empty());
+        assert_eq!(history_dates[1].history_dates, vec![DateRange::new(date, date + chrono::Days::new(3))]);
+        let summaries = backend.get_history_summaries(location_filters![]).unwrap();
+        assert_eq!(summaries.iter().map(|summary| summary.count).collect::<Vec<_>>(), [0, 4]);
+
+        let search_histories = backend.search_histories(location_filters![], "CLEAR day", None).unwrap();
+        assert_eq!(search_histories.len(), 1);
+        assert_eq!(search_histories[0].histories.len(), 2);
+        let range = Some(DateRange::new(date + chrono::Days::new(1), date + chrono::Days::new(3)));
+        let search_histories = backend.search_histories(location_filters![], "clear", range).unwrap();
+        assert_eq!(search_histories[0].histories.len(), 1);
+        assert!(backend.search_histories(location_filters![], " ", None).is_err());
+        assert!(backend.search_locations(CityFilter::default()).is_err());
+        assert!(backend.get_states().is_err());
+    }
+
+    #[test]
+    fn history_client() {
+        use crate::backend::{testlib, MEMORY_DIRNAME};
+        use std::{
+            io::{Read, Write},
+            net::TcpListener,
+            path::Path,
+        };
+
+        // serve a single timeline response
+        let body = include_str!("../../resources/tests/responses/timeline/alias/20240301-20240315.json");
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![];
+            let mut buffer = [0u8; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let count = stream.read(&mut buffer).unwrap();
+                if count == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..count]);
+            }
+            let header = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len());
+            stream.write_all(header.as_bytes()).unwrap();
+            stream.write_all(body.as_bytes()).unwrap();
+        });
+
+        let fixture = testlib::TestFixture::create();
+        let config_file = PathBuf::from(&fixture).join("weather.toml");
+        let config = format!(
+            "[weather-data]\ndirectory = \"{}\"\n[visual-crossing]\nendpoint = \"{}\"\napi-key = \"key\"\n",
+            MEMORY_DIRNAME, endpoint
+        );
+        std::fs::write(&config_file, config).unwrap();
+        let weather_data = crate::create_weather_data(Some(config_file), None, None, false).unwrap();
+        let history_client = weather_data.get_history_client().unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let range = DateRange::new(date, date + chrono::Days::new(14));
+        let handle = history_client.execute(&location("Tigard", "tigard"), &range).unwrap();
+        assert_eq!(history_client.get(handle).unwrap().histories.len(), 15);
+        server.join().unwrap();
+
+        // the usage is kept with the weather data and nothing is written to a directory
+        let query_usage = weather_data.get_query_usage().unwrap();
+        assert_eq!((query_usage.requests, query_usage.cost), (1, 15));
+        assert!(!Path::new(MEMORY_DIRNAME).exists());
+        assert!(!PathBuf::from(&fixture).join(MEMORY_DIRNAME).exists());
+    }
+}
@@ -0,0 +1,417 @@
+//! The weather data implementation that forwards requests to a weather data server.
+//!
+//! Requests are sent as `HTTP` and the server responds with `JSON` documents. Location filters are
+//! applied to the server locations here, using the same rules as the archive implementation, and the
+//! server is asked for the locations by alias. Adding locations and histories requires the server
+//! token from the weather data configuration.
+//!
+//! History clients are not routed through the server. They run locally without a weather data
+//! directory so responses are not cached and the query usage is kept in memory.
+
+use super::filesys::filter_locations;
+use crate::{
+    backend::{Backend, Config, LocationFilters},
+    history_client::UsageLedger,
+    entities::{DailyHistories, DateRange, HistoryDates, HistorySummaries, Location, State, CityFilter},
+};
+use reqwest::{
+    blocking::{Client, RequestBuilder},
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    Proxy,
+    Url,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::time::Duration;
+
+/// Create a remote backend specific error message.
+macro_rules! error {
+    ($($arg:tt)*) => {
+        crate::Error::from(format!("RemoteBackend {}", format!($($arg)*)))
+    }
+}
+
+/// Create an error from the remote backend specific error message.
+macro_rules! err {
+    ($($arg:tt)*) => {
+        Err(error!($($arg)*))
+    };
+}
+
+/// Creates the weather data API that uses a weather data server.
+///
+/// # Arguments
+///
+/// * `config` contains the weather data configuration.
+///
+pub fn create_remote_backend(config: Config) -> crate::Result<Box<dyn Backend>> {
+    let url = match config.weather_data.url.as_deref().map(Url::parse) {
+        Some(Ok(url)) if matches!(url.scheme(), "http" | "https") && !url.cannot_be_a_base() => url,
+        Some(Ok(url)) => err!("'{}' is not an HTTP URL.", url)?,
+        Some(Err(error)) => err!("the weather data URL is not valid ({}).", error)?,
+        None => err!("the weather data URL has not been configured.")?,
+    };
+    log::debug!("RemoteBackend {}", url);
+    let token = config.weather_data.token()?;
+    let http = &config.http;
+    let mut builder = Client::builder()
+        .connect_timeout(Duration::from_secs(http.connect_timeout))
+        .timeout(Duration::from_secs(http.timeout))
+        .user_agent(&http.user_agent);
+    if let Some(proxy_url) = &http.proxy {
+        match Proxy::all(proxy_url) {
+            Ok(proxy) => builder = builder.proxy(proxy),
+            Err(error) => err!("error with HTTP proxy '{}' ({}).", proxy_url, error)?,
+        }
+    }
+    let client = match builder.build() {
+        Ok(client) => client,
+        Err(error) => err!("error creating the HTTP client ({}).", error)?,
+    };
+    Ok(Box::new(RemoteBackend { config, client, url, token, usage_ledger: UsageLedger::in_memory() }))
+}
+
+/// The error document returned by the server.
+#[derive(Debug, Deserialize)]
+struct ErrorReply {
+    /// Describes the error.
+    error: String,
+}
+
+/// The response to adding daily histories.
+#[derive(Debug, Deserialize)]
+struct AddedReply {
+    /// The number of histories that were added.
+    added: usize,
+}
+
+/// The weather data server implementation of a [Backend].
+struct RemoteBackend {
+    /// The weather data configuration.
+    config: Config,
+    /// The `HTTP` client used to call the server.
+    client: Client,
+    /// The weather data server base URL.
+    url: Url,
+    /// The token that authorizes writes.
+    token: Option<String>,
+    /// The history client query costs, the weather data directory is not used.
+    usage_ledger: UsageLedger,
+}
+impl RemoteBackend {
+    /// Get the URL of a server endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` is the endpoint path relative to the server URL.
+    /// * `params` are the query parameters.
+    ///
+    fn endpoint(&self, path: &str, params: &[(&str, String)]) -> Url {
+        let mut url = self.url.clone();
+        // the server URL is a base so it always has path segments
+        url.path_segments_mut().unwrap().pop_if_empty().extend(path.split('/'));
+        if !params.is_empty() {
+            url.query_pairs_mut().extend_pairs(params);
+        }
+        url
+    }
+
+    /// Get a document from the server.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` is the endpoint path relative to the server URL.
+    /// * `params` are the query parameters.
+    ///
+    fn get<T: DeserializeOwned>(&self, path: &str, params: &[(&str, String)]) -> crate::Result<T> {
+        self.send(self.client.get(self.endpoint(path, params)))
+    }
+
+    /// Send a document to the server.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` is the endpoint path relative to the server URL.
+    /// * `document` is the request body.
+    ///
+    fn post<T: DeserializeOwned>(&self, path: &str, document: &impl Serialize) -> crate::Result<T> {
+        let body = match serde_json::to_vec(document) {
+            Ok(body) => body,
+            Err(error) => err!("could not create the {} request ({}).", path, error)?,
+        };
+        let mut request = self.client.post(self.endpoint(path, &[])).header(CONTENT_TYPE, "application/json");
+        if let Some(token) = &self.token {
+            request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+        }
+        self.send(request.body(body))
+    }
+
+    /// Send a request to the server and get the response document.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` is what will be sent to the server.
+    ///
+    fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> crate::Result<T> {
+        let response = match request.send() {
+            Ok(response) => response,
+            Err(error) => err!("could not reach {} ({}).", self.url, error)?,
+        };
+        let status = response.status();
+        let path = response.url().path().to_string();
+        let body = match response.bytes() {
+            Ok(body) => body,
+            Err(error) => err!("{} response could not be read ({}).", path, error)?,
+        };
+        if !status.is_success() {
+            match serde_json::from_slice::<ErrorReply>(&body) {
+                Ok(reply) => err!("{} failed, {} ({}).", path, reply.error, status.as_u16())?,
+                Err(_) => err!("{} failed ({}).", path, status)?,
+            }
+        }
+        match serde_json::from_slice(&body) {
+            Ok(document) => Ok(document),
+            Err(error) => err!("{} response is not valid ({}).", path, error),
+        }
+    }
+
+    /// Get the query parameters that select locations by alias.
+    ///
+    /// # Arguments
+    ///
+    /// * `locations` are the locations that will be selected.
+    ///
+    fn alias_params(locations: &[Location]) -> Vec<(&'static str, String)> {
+        locations.iter().map(|location| ("name", location.alias.clone())).collect()
+    }
+
+    /// Keep the results for the selected locations. The server selects locations by name as well as
+    /// alias so a location named like the alias of another would otherwise be included.
+    ///
+    /// # Arguments
+    ///
+    /// * `locations` are the selected locations.
+    /// * `results` are the server results.
+    /// * `location` gets the location of a result.
+    ///
+    fn retain<T>(locations: &[Location], mut results: Vec<T>, location: fn(&T) -> &Location) -> Vec<T> {
+        results.retain(|result| locations.iter().any(|selected| selected.alias == location(result).alias));
+        results
+    }
+}
+impl Backend for RemoteBackend {
+    /// Get the backend configuration.
+    ///
+    fn get_config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Get the ledger of history client query costs, which is kept in memory.
+    ///
+    fn get_usage_ledger(&self) -> UsageLedger {
+        self.usage_ledger.clone()
+    }
+
+    /// Add weather data history for a location.
+    ///
+    /// # Arguments
+    ///
+    /// * `daily_histories` has the location and histories to add.
+    ///
+    fn add_daily_histories(&self, daily_histories: DailyHistories) -> crate::Result<usize> {
+        crate::log_elapsed_time!(trace, "add_daily_histories");
+        let reply: AddedReply = self.post("history", &daily_histories)?;
+        Ok(reply.added)
+    }
+
+    /// Returns the daily weather data history for a location.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` identifies what location should be used.
+    /// * `history_range` specifies the date range that should be used.
+    ///
+    fn get_daily_histories(&self, filters: LocationFilters, history_range: DateRange) -> crate::Result<DailyHistories> {
+        let mut locations = self.get_locations(filters)?;
+        let location = match locations.len() {
+            1 => locations.pop().unwrap(),
+            0 => err!("a location was not found.")?,
+            _ => err!("Multiple locations were found.")?,
+        };
+        self.get_location_histories(location, history_range)
+    }
+
+    /// Get daily weather history for a location selected by its alias.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` is the location whose history is returned.
+    /// * `history_range` covers the history dates returned.
+    ///
+    fn get_location_histories(&self, location: Location, history_range: DateRange) -> crate::Result<DailyHistories> {
+        crate::log_elapsed_time!(trace, "get_location_histories");
+        let params = [
+            ("alias", location.alias),
+            ("from", history_range.start.to_string()),
+            ("thru", history_range.end.to_string()),
+        ];
+        self.get("history/daily", &params)
+    }
+
+    /// Get the weather history dates for locations.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` identifies the locations.
+    ///
+    fn get_history_dates(&self, filters: LocationFilters) -> crate::Result<Vec<HistoryDates>> {
+        let locations = self.get_locations(filters)?;
+        if locations.is_empty() {
+            return Ok(vec![]);
+        }
+        crate::log_elapsed_time!(trace, "get_history_dates");
+        let history_dates = self.get("history/dates", &Self::alias_params(&locations))?;
+        Ok(Self::retain(&locations, history_dates, |history_dates: &HistoryDates| &history_dates.location))
+    }
+
+    /// Get the summary metrics of a locations weather data.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` identifies the locations that should be used.
+    ///
+    fn get_history_summaries(&self, filters: LocationFilters) -> crate::Result<Vec<HistorySummaries>> {
+        let locations = self.get_locations(filters)?;
+        if locations.is_empty() {
+            return Ok(vec![]);
+        }
+        crate::log_elapsed_time!(trace, "get_history_summaries");
+        let history_summaries = self.get("history/summary", &Self::alias_params(&locations))?;
+        Ok(Self::retain(&locations, history_summaries, |summaries: &HistorySummaries| &summaries.location))
+    }
+
+    /// Search the weather history descriptions of locations.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` identifies the locations that should be used.
+    /// * `query` contains the words a history description must have.
+    /// * `history_range` restricts the history dates searched, if `None` all history is searched.
+    ///
+    fn search_histories(
+        &self,
+        filters: LocationFilters,
+        query: &str,
+        history_range: Option<DateRange>,
+    ) -> crate::Result<Vec<DailyHistories>> {
+        let locations = self.get_locations(filters)?;
+        if locations.is_empty() {
+            return Ok(vec![]);
+        }
+        crate::log_elapsed_time!(trace, "search_histories");
+        let mut params = Self::alias_params(&locations);
+        params.push(("query", query.to_string()));
+        if let Some(history_range) = history_range {
+            params.push(("from", history_range.start.to_string()));
+            params.push(("thru", history_range.end.to_string()));
+        }
+        let search_histories = self.get("history/search", &params)?;
+        Ok(Self::retain(&locations, search_histories, |daily_histories: &DailyHistories| &daily_histories.location))
+    }
+
+    /// Get the metadata for weather locations.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` identifies the locations of interest.
+    ///
+    fn get_locations(&self, filters: LocationFilters) -> crate::Result<Vec<Location>> {
+        crate::log_elapsed_time!(trace, "get_locations");
+        let locations: Vec<Location> = self.get("locations", &[])?;
+        Ok(filter_locations(&locations, filters).collect())
+    }
+
+    /// Add a new weather location.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` is the location that will be added.
+    ///
+    fn add_location(&self, location: Location) -> crate::Result<()> {
+        crate::log_elapsed_time!(trace, "add_location");
+        let _: serde_json::Value = self.post("locations", &location)?;
+        Ok(())
+    }
+
+    /// Search the US Cities on the server.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` identifies which cities are being searched for.
+    ///
+    fn search_locations(&self, filter: CityFilter) -> crate::Result<Vec<Location>> {
+        crate::log_elapsed_time!(trace, "search_locations");
+        let mut params = vec![("limit", filter.limit.to_string())];
+        params.extend(filter.name.map(|name| ("name", name)));
+        params.extend(filter.state.map(|state| ("state", state)));
+        params.extend(filter.zip_code.map(|zip_code| ("zip", zip_code)));
+        self.get("cities", &params)
+    }
+
+    /// Get the US City states from the server.
+    ///
+    fn get_states(&self) -> crate::Result<Vec<State>> {
+        crate::log_elapsed_time!(trace, "get_states");
+        self.get("states", &[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn remote_backend(url: &str) -> crate::Result<Box<dyn Backend>> {
+        let mut config = Config::try_from("").unwrap();
+        config.weather_data.url = Some(url.to_string());
+        create_remote_backend(config)
+    }
+
+    #[test]
+    fn endpoint() {
+        let mut config = Config::try_from("").unwrap();
+        config.weather_data.url = Some("http://localhost:8080/weather".to_string());
+        let url = Url::parse("http://localhost:8080/weather").unwrap();
+        let usage_ledger = UsageLedger::in_memory();
+        let testcase = RemoteBackend { config, client: Client::new(), url, token: None, usage_ledger };
+        let params = [("name", "tigard".to_string()), ("query", "light rain".to_string())];
+        let endpoint = testcase.endpoint("history/search", &params);
+        assert_eq!(endpoint.as_str(), "http://localhost:8080/weather/history/search?name=tigard&query=light+rain");
+        assert_eq!(testcase.endpoint("states", &[]).as_str(), "http://localhost:8080/weather/states");
+        assert!(remote_backend("http://localhost:8080/").is_ok());
+        assert!(remote_backend("ftp://localhost/").is_err());
+        assert!(remote_backend("not a url").is_err());
+    }
+
+    #[test]
+    fn history_client() {
+        use crate::{backend::testlib, history_client::create_history_client};
+        let fixture = testlib::TestFixture::create();
+        let mut config = Config::try_from("[visual-crossing]\napi-key = \"key\"").unwrap();
+        config.weather_data.directory = PathBuf::from(&fixture).display().to_string();
+        config.weather_data.url = Some("http://localhost:8080/".to_string());
+        let backend = create_remote_backend(config).unwrap();
+
+        // the query usage is kept in memory instead of the weather data directory
+        let today = UsageLedger::today();
+        backend.get_usage_ledger().reserve(today, 5, None).unwrap();
+        assert_eq!(backend.get_usage_ledger().usage(today).unwrap().cost, 5);
+        assert!(!PathBuf::from(&fixture).join(UsageLedger::FILENAME).exists());
+        let history_client = create_history_client(backend.get_config(), backend.get_usage_ledger()).unwrap();
+        assert!(format!("{:?}", history_client).contains("response_cache: None"));
+
+        let mut config = Config::try_from("").unwrap();
+        config.weather_data.url = Some("http://localhost:8080/".to_string());
+        config.visual_crossing.replay = true;
+        assert!(create_history_client(&config, UsageLedger::in_memory()).is_err());
+    }
+}
@@ -0,0 +1,41 @@
+//! The weather data configuration API and data beans.
+
+pub use crate::backend::{
+    config_settings, find_config_file, starter_config, user_config_path, validate_config, ENV_CONFIG, ENV_TOKEN,
+};
+use std::path::PathBuf;
+
+/// Where a configuration setting came from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigSource {
+    /// The setting is the default value.
+    Default,
+    /// The setting is from the configuration file.
+    File,
+    /// The setting is from the named configuration profile.
+    Profile(String),
+    /// The setting is from the named environment variable.
+    Environment(String),
+}
+
+/// A configuration setting.
+#[derive(Debug, Clone)]
+pub struct ConfigSetting {
+    /// The setting name (`table.key`).
+    pub name: String,
+    /// The setting value as `TOML`.
+    pub value: String,
+    /// Where the setting came from.
+    pub source: ConfigSource,
+}
+
+/// The effective weather data configuration.
+#[derive(Debug)]
+pub struct ConfigSettings {
+    /// The configuration file that was loaded.
+    pub path: Option<PathBuf>,
+    /// The configuration profile that was selected.
+    pub profile: Option<String>,
+    /// The configuration settings.
+    pub settings: Vec<ConfigSetting>,
+}
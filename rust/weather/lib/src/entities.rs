@@ -1,9 +1,10 @@
 //! Structures used by the weather data `API`s.
 
 use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
 
 /// A locations daily weather history.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct DailyHistories {
     /// The location metadata.
     pub location: Location,
@@ -12,7 +13,7 @@ pub struct DailyHistories {
 }
 
 /// A locations history dates.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct HistoryDates {
     /// The location metadata.
     pub location: Location,
@@ -20,7 +21,7 @@ pub struct HistoryDates {
     pub history_dates: Vec<DateRange>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 /// A locations history summary.
 pub struct HistorySummaries {
     pub location: Location,
@@ -35,7 +36,7 @@ pub struct HistorySummaries {
 }
 
 /// The data that comprises a location.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Location {
     /// The name of the city.
     pub city: String,
@@ -56,7 +57,7 @@ pub struct Location {
 }
 
 /// The data that identifies selection of a location or locations.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct LocationFilter {
     /// A location can be searched by the city name.
     pub city: Option<String>,
@@ -137,6 +138,7 @@ macro_rules! location_filter {
 /// The collection of location filters. Originally this was defined as a type but having
 /// a concrete class helps a bit with the Python library.
 ///
+#[derive(Clone, Debug)]
 pub struct LocationFilters(
     /// The collection of location filters.
     Vec<LocationFilter>,
@@ -214,9 +216,10 @@ pub struct HistorySummary {
 }
 
 /// The weather history data.
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct History {
     /// The location alias name.
+    #[serde(default)]
     pub alias: String,
     /// The history date.
     pub date: NaiveDate,
@@ -305,7 +308,7 @@ impl DateRanges {
 }
 
 /// A container for a range of dates.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct DateRange {
     /// The starting date of the range.
     pub start: NaiveDate,
@@ -411,6 +414,7 @@ impl Default for CityFilter {
 }
 
 /// The US City state names.
+#[derive(Debug, Serialize, Deserialize)]
 pub struct State {
     /// The states full name.
     pub name: String,
@@ -419,6 +423,19 @@ pub struct State {
     pub state_id: String,
 }
 
+/// The history client requests made on a day and what they cost.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryUsage {
+    /// The day the requests were made.
+    pub date: NaiveDate,
+    /// The number of history requests made.
+    pub requests: usize,
+    /// The records charged for the requests.
+    pub cost: u64,
+    /// The maximum records that can be charged in a day, if there is one.
+    pub budget: Option<u64>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
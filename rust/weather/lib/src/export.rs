@@ -0,0 +1,425 @@
+//! Writers that export weather history for use by other tools.
+//!
+//! Histories can be exported as newline delimited `JSON` (one history per line) or as `Apache
+//! Parquet`. Each row carries the location metadata along with the history so files from different
+//! locations can be combined. `Parquet` columns are typed, dates use the `DATE` logical type and the
+//! sunrise and sunset times are local timestamps. A `Parquet` file has one row group per location.
+
+use crate::entities::{History, Location};
+use chrono::NaiveDate;
+use parquet::{
+    basic::Compression,
+    data_type::{ByteArray, ByteArrayType, DataType, DoubleType, Int32Type, Int64Type},
+    file::{
+        properties::WriterProperties,
+        writer::{SerializedFileWriter, SerializedRowGroupWriter},
+    },
+    schema::parser::parse_message_type,
+};
+use serde_json::json;
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Create an export specific error message.
+macro_rules! error {
+    ($path:expr, $($arg:tt)*) => {
+        crate::Error::from(format!("Export ({}): {}", $path.display(), format!($($arg)*)))
+    };
+}
+
+/// The file formats histories can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    /// Newline delimited `JSON`.
+    Ndjson,
+    /// Apache `Parquet`.
+    Parquet,
+}
+impl ExportFormat {
+    /// The filename extension used by the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+impl std::fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportFormat::Ndjson => write!(f, "NDJSON"),
+            ExportFormat::Parquet => write!(f, "Parquet"),
+        }
+    }
+}
+
+/// What was exported.
+#[derive(Debug, Default)]
+pub struct ExportDetails {
+    /// The files that were written.
+    pub files: Vec<PathBuf>,
+    /// The number of locations exported.
+    pub locations: usize,
+    /// The number of histories exported.
+    pub histories: usize,
+}
+
+/// The `Parquet` schema of exported histories.
+const PARQUET_SCHEMA: &str = r#"
+    message history {
+        REQUIRED BYTE_ARRAY alias (UTF8);
+        REQUIRED BYTE_ARRAY name (UTF8);
+        REQUIRED BYTE_ARRAY city (UTF8);
+        REQUIRED BYTE_ARRAY state_id (UTF8);
+        REQUIRED BYTE_ARRAY state (UTF8);
+        OPTIONAL DOUBLE latitude;
+        OPTIONAL DOUBLE longitude;
+        REQUIRED BYTE_ARRAY tz (UTF8);
+        REQUIRED INT32 date (DATE);
+        OPTIONAL DOUBLE temperature_high;
+        OPTIONAL DOUBLE temperature_low;
+        OPTIONAL DOUBLE temperature_mean;
+        OPTIONAL DOUBLE dew_point;
+        OPTIONAL DOUBLE humidity;
+        OPTIONAL DOUBLE precipitation_chance;
+        OPTIONAL BYTE_ARRAY precipitation_type (UTF8);
+        OPTIONAL DOUBLE precipitation_amount;
+        OPTIONAL DOUBLE wind_speed;
+        OPTIONAL DOUBLE wind_gust;
+        OPTIONAL INT64 wind_direction;
+        OPTIONAL DOUBLE cloud_cover;
+        OPTIONAL DOUBLE pressure;
+        OPTIONAL DOUBLE uv_index;
+        OPTIONAL INT64 sunrise (TIMESTAMP(MILLIS,false));
+        OPTIONAL INT64 sunset (TIMESTAMP(MILLIS,false));
+        OPTIONAL DOUBLE moon_phase;
+        OPTIONAL DOUBLE visibility;
+        OPTIONAL BYTE_ARRAY description (UTF8);
+    }
+"#;
+
+/// The file format specific writer.
+enum Writer {
+    /// The newline delimited `JSON` writer.
+    Ndjson(BufWriter<File>),
+    /// The `Parquet` writer.
+    Parquet(SerializedFileWriter<File>),
+}
+
+/// Writes location histories to an export file.
+pub(crate) struct ExportWriter {
+    /// The export file path.
+    path: PathBuf,
+    /// The format specific writer.
+    writer: Writer,
+}
+impl ExportWriter {
+    /// Create the export file, an existing file is replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` is the export file path.
+    /// * `format` is the export file format.
+    ///
+    pub fn create(path: &Path, format: ExportFormat) -> crate::Result<Self> {
+        let file = File::create(path).map_err(|error| error!(path, "could not create the file ({}).", error))?;
+        let writer = match format {
+            ExportFormat::Ndjson => Writer::Ndjson(BufWriter::new(file)),
+            ExportFormat::Parquet => {
+                let schema = parse_message_type(PARQUET_SCHEMA).map_err(|error| error!(path, "{}", error))?;
+                let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+                match SerializedFileWriter::new(file, Arc::new(schema), Arc::new(properties)) {
+                    Ok(writer) => Writer::Parquet(writer),
+                    Err(error) => Err(error!(path, "could not create the Parquet writer ({}).", error))?,
+                }
+            }
+        };
+        Ok(Self { path: path.to_path_buf(), writer })
+    }
+    /// Write the histories of a location.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` is the location metadata written with each history.
+    /// * `histories` are the location histories.
+    ///
+    pub fn write(&mut self, location: &Location, histories: &[History]) -> crate::Result<()> {
+        let path = &self.path;
+        match &mut self.writer {
+            Writer::Ndjson(writer) => {
+                for history in histories {
+                    let row = json!({
+                        "alias": location.alias,
+                        "name": location.name,
+                        "city": location.city,
+                        "state_id": location.state_id,
+                        "state": location.state,
+                        "latitude": coordinate(&location.latitude),
+                        "longitude": coordinate(&location.longitude),
+                        "tz": location.tz,
+                        "date": history.date,
+                        "temperature_high": history.temperature_high,
+                        "temperature_low": history.temperature_low,
+                        "temperature_mean": history.temperature_mean,
+                        "dew_point": history.dew_point,
+                        "humidity": history.humidity,
+                        "precipitation_chance": history.precipitation_chance,
+                        "precipitation_type": history.precipitation_type,
+                        "precipitation_amount": history.precipitation_amount,
+                        "wind_speed": history.wind_speed,
+                        "wind_gust": history.wind_gust,
+                        "wind_direction": history.wind_direction,
+                        "cloud_cover": history.cloud_cover,
+                        "pressure": history.pressure,
+                        "uv_index": history.uv_index,
+                        "sunrise": history.sunrise,
+                        "sunset": history.sunset,
+                        "moon_phase": history.moon_phase,
+                        "visibility": history.visibility,
+                        "description": history.description,
+                    });
+                    if let Err(error) = writeln!(writer, "{}", row) {
+                        Err(error!(path, "could not write the {} histories ({}).", location.alias, error))?;
+                    }
+                }
+                Ok(())
+            }
+            Writer::Parquet(writer) => match writer.next_row_group() {
+                Ok(mut row_group) => {
+                    write_row_group(&mut row_group, location, histories)
+                        .and_then(|_| row_group.close().map(|_| ()))
+                        .map_err(|error| error!(path, "could not write the {} histories ({}).", location.alias, error))
+                }
+                Err(error) => Err(error!(path, "could not create the {} row group ({}).", location.alias, error)),
+            },
+        }
+    }
+    /// Finish writing the export file, removing it if it cannot be finished.
+    pub fn finish(self) -> crate::Result<()> {
+        let path = &self.path;
+        let result = match self.writer {
+            Writer::Ndjson(mut writer) => writer.flush().map_err(|error| error.to_string()),
+            Writer::Parquet(writer) => writer.close().map(|_| ()).map_err(|error| error.to_string()),
+        };
+        result.map_err(|error| {
+            if let Err(error) = fs::remove_file(path) {
+                log::warn!("{}", error!(path, "could not remove the partial export ({}).", error));
+            }
+            error!(path, "could not finish the file ({}).", error)
+        })
+    }
+    /// Remove a partially written export file.
+    pub fn discard(self) {
+        let path = self.path;
+        drop(self.writer);
+        if let Err(error) = fs::remove_file(&path) {
+            log::warn!("{}", error!(path, "could not remove the partial export ({}).", error));
+        }
+    }
+}
+
+/// Convert a location coordinate into a number.
+///
+/// # Arguments
+///
+/// * `coordinate` is the latitude or longitude text.
+///
+fn coordinate(coordinate: &str) -> Option<f64> {
+    coordinate.trim().parse().ok()
+}
+
+/// Write the location histories as a `Parquet` row group.
+///
+/// # Arguments
+///
+/// * `row_group` is the row group writer.
+/// * `location` is the location metadata written with each history.
+/// * `histories` are the location histories.
+///
+fn write_row_group(
+    row_group: &mut SerializedRowGroupWriter<File>,
+    location: &Location,
+    histories: &[History],
+) -> parquet::errors::Result<()> {
+    let text = |value: &str| histories.iter().map(|_| Some(ByteArray::from(value))).collect::<Vec<_>>();
+    let optional_text = |value: fn(&History) -> &Option<String>| {
+        histories.iter().map(|history| value(history).as_deref().map(ByteArray::from)).collect::<Vec<_>>()
+    };
+    let number = |value: Option<f64>| histories.iter().map(|_| value).collect::<Vec<_>>();
+    let double = |value: fn(&History) -> Option<f64>| histories.iter().map(value).collect::<Vec<_>>();
+    let timestamp = |value: fn(&History) -> Option<chrono::NaiveDateTime>| {
+        histories.iter().map(|history| value(history).map(|ts| ts.and_utc().timestamp_millis())).collect::<Vec<_>>()
+    };
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    let dates = histories.iter().map(|history| Some((history.date - epoch).num_days() as i32)).collect();
+
+    // the columns must be written in schema order
+    write_column::<ByteArrayType>(row_group, text(&location.alias))?;
+    write_column::<ByteArrayType>(row_group, text(&location.name))?;
+    write_column::<ByteArrayType>(row_group, text(&location.city))?;
+    write_column::<ByteArrayType>(row_group, text(&location.state_id))?;
+    write_column::<ByteArrayType>(row_group, text(&location.state))?;
+    write_column::<DoubleType>(row_group, number(coordinate(&location.latitude)))?;
+    write_column::<DoubleType>(row_group, number(coordinate(&location.longitude)))?;
+    write_column::<ByteArrayType>(row_group, text(&location.tz))?;
+    write_column::<Int32Type>(row_group, dates)?;
+    write_column::<DoubleType>(row_group, double(|history| history.temperature_high))?;
+    write_column::<DoubleType>(row_group, double(|history| history.temperature_low))?;
+    write_column::<DoubleType>(row_group, double(|history| history.temperature_mean))?;
+    write_column::<DoubleType>(row_group, double(|history| history.dew_point))?;
+    write_column::<DoubleType>(row_group, double(|history| history.humidity))?;
+    write_column::<DoubleType>(row_group, double(|history| history.precipitation_chance))?;
+    write_column::<ByteArrayType>(row_group, optional_text(|history| &history.precipitation_type))?;
+    write_column::<DoubleType>(row_group, double(|history| history.precipitation_amount))?;
+    write_column::<DoubleType>(row_group, double(|history| history.wind_speed))?;
+    write_column::<DoubleType>(row_group, double(|history| history.wind_gust))?;
+    write_column::<Int64Type>(row_group, histories.iter().map(|history| history.wind_direction).collect())?;
+    write_column::<DoubleType>(row_group, double(|history| history.cloud_cover))?;
+    write_column::<DoubleType>(row_group, double(|history| history.pressure))?;
+    write_column::<DoubleType>(row_group, double(|history| history.uv_index))?;
+    write_column::<Int64Type>(row_group, timestamp(|history| history.sunrise))?;
+    write_column::<Int64Type>(row_group, timestamp(|history| history.sunset))?;
+    write_column::<DoubleType>(row_group, double(|history| history.moon_phase))?;
+    write_column::<DoubleType>(row_group, double(|history| history.visibility))?;
+    write_column::<ByteArrayType>(row_group, optional_text(|history| &history.description))?;
+    Ok(())
+}
+
+/// Write the next column of a `Parquet` row group. Missing values are written as nulls.
+///
+/// # Arguments
+///
+/// * `row_group` is the row group writer.
+/// * `values` are the column values, one for each row.
+///
+fn write_column<T: DataType>(
+    row_group: &mut SerializedRowGroupWriter<File>,
+    values: Vec<Option<T::T>>,
+) -> parquet::errors::Result<()> {
+    let mut column = match row_group.next_column()? {
+        Some(column) => column,
+        None => Err(parquet::errors::ParquetError::General("the schema is missing a column".to_string()))?,
+    };
+    let writer = column.typed::<T>();
+    let definition_levels: Vec<i16> = values.iter().map(|value| value.is_some() as i16).collect();
+    let values: Vec<T::T> = values.into_iter().flatten().collect();
+    match writer.get_descriptor().max_def_level() {
+        0 => writer.write_batch(&values, None, None)?,
+        _ => writer.write_batch(&values, Some(&definition_levels), None)?,
+    };
+    column.close()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::testlib;
+    use parquet::{
+        file::reader::{FileReader, SerializedFileReader},
+        record::Field,
+    };
+    use std::io::{BufRead, BufReader};
+    use crate::{entities::DailyHistories, location_filters, weather_data::create_weather_data};
+
+    #[test]
+    fn export() {
+        let fixture = testlib::TestFixture::create();
+        let location = testlib::location("Tigard", "tigard");
+        let date = NaiveDate::from_ymd_opt(2024, 7, 4).unwrap();
+        let histories = vec![
+            History {
+                alias: "tigard".to_string(),
+                date,
+                temperature_high: Some(88.5),
+                wind_direction: Some(270),
+                sunrise: date.and_hms_opt(5, 36, 0),
+                description: Some("Clear conditions throughout the day.".to_string()),
+                ..Default::default()
+            },
+            History { alias: "tigard".to_string(), date: date.succ_opt().unwrap(), ..Default::default() },
+        ];
+
+        let path = PathBuf::from(&fixture).join("tigard.ndjson");
+        let mut writer = ExportWriter::create(&path, ExportFormat::Ndjson).unwrap();
+        writer.write(&location, &histories).unwrap();
+        writer.finish().unwrap();
+        let lines: Vec<String> = BufReader::new(File::open(&path).unwrap()).lines().map(|line| line.unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        let row: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(row["alias"], "tigard");
+        assert_eq!(row["latitude"], 45.4312);
+        assert_eq!(row["date"], "2024-07-04");
+        assert_eq!(row["temperature_high"], 88.5);
+        assert_eq!(row["wind_direction"], 270);
+        assert_eq!(row["sunrise"], "2024-07-04T05:36:00");
+        let row: serde_json::Value = serde_json::from_str(&lines[1]).unwrap();
+        assert!(row["temperature_high"].is_null());
+
+        let path = PathBuf::from(&fixture).join("tigard.parquet");
+        let mut writer = ExportWriter::create(&path, ExportFormat::Parquet).unwrap();
+        writer.write(&location, &histories).unwrap();
+        writer.write(&location, &histories[1..]).unwrap();
+        writer.finish().unwrap();
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        let rows: Vec<_> = reader.get_row_iter(None).unwrap().map(|row| row.unwrap()).collect();
+        assert_eq!(rows.len(), 3);
+        let fields: Vec<(String, Field)> =
+            rows[0].get_column_iter().map(|(name, field)| (name.clone(), field.clone())).collect();
+        let field = |name: &str| fields.iter().find(|(column, _)| column == name).map(|(_, field)| field).unwrap();
+        assert_eq!(field("alias"), &Field::Str("tigard".to_string()));
+        assert_eq!(field("longitude"), &Field::Double(-122.7717));
+        let days = (date - NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()).num_days() as i32;
+        assert_eq!(field("date"), &Field::Date(days));
+        assert_eq!(field("temperature_high"), &Field::Double(88.5));
+        assert_eq!(field("temperature_low"), &Field::Null);
+        assert_eq!(field("wind_direction"), &Field::Long(270));
+        let sunrise = date.and_hms_opt(5, 36, 0).unwrap().and_utc().timestamp_millis();
+        assert_eq!(field("sunrise"), &Field::TimestampMillis(sunrise));
+    }
+
+    #[test]
+    fn export_histories() {
+        let fixture = testlib::TestFixture::create();
+        // use an explicit configuration file so the user configuration is not loaded
+        let config_file = PathBuf::from(&fixture).join("weather.toml");
+        fs::write(&config_file, format!("[weather-data]\ndirectory = {:?}\n", fixture.to_string())).unwrap();
+        let weather_data = create_weather_data(Some(config_file), None, None, true).unwrap();
+        let location = testlib::location("Tigard", "tigard");
+        weather_data.add_location(location.clone()).unwrap();
+        // the name matches the tigard alias
+        let portland = Location { name: "Tigard".to_string(), ..testlib::location("Portland", "portland") };
+        weather_data.add_location(portland).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 7, 4).unwrap();
+        let histories = (0..3)
+            .map(|day| History {
+                alias: location.alias.clone(),
+                date: date + chrono::Days::new(day),
+                ..Default::default()
+            })
+            .collect();
+        weather_data.add_histories(DailyHistories { location, histories }).unwrap();
+
+        // locations without history are skipped
+        let path = PathBuf::from(&fixture).join("export.ndjson");
+        let details = weather_data.export_histories(location_filters![], None, ExportFormat::Ndjson, &path, false);
+        let details = details.unwrap();
+        assert_eq!(details.files, vec![path.clone()]);
+        assert_eq!(details.locations, 1);
+        assert_eq!(details.histories, 3);
+        assert_eq!(BufReader::new(File::open(&path).unwrap()).lines().count(), 3);
+
+        let split = PathBuf::from(&fixture).join("split");
+        fs::create_dir(&split).unwrap();
+        let range = Some(crate::entities::DateRange::new(date, date));
+        let details = weather_data.export_histories(location_filters![], range, ExportFormat::Parquet, &split, true);
+        let details = details.unwrap();
+        assert_eq!(details.files, vec![split.join("tigard.parquet")]);
+        assert_eq!(details.histories, 1);
+        assert!(weather_data.export_histories(location_filters![], None, ExportFormat::Ndjson, &path, true).is_err());
+    }
+}
@@ -3,45 +3,199 @@
 use crate::{
     backend::Config,
     entities::{DailyHistories, DateRange, Location},
-    Result,
+    Error, Result,
 };
-use std::fmt::Debug;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt::Debug,
+};
+use replay_client::ReplayClient;
 use timeline_client::TimelineClient;
 
+mod history_batch;
+pub use history_batch::{HistoryBatch, HistoryChunk};
+
+mod replay_client;
+
+mod response_cache;
+
 mod rest_client;
 
 mod timeline_client;
 
-/// Creates a history client.
+mod usage_ledger;
+pub use usage_ledger::UsageLedger;
+
+/// Creates a history client. Weather history can only be replayed when weather data is kept in a
+/// directory.
 ///
 /// # Arguments
 ///
 /// - `config` is the weather data configuration.
+/// - `usage_ledger` records the cost of history requests.
 ///
-pub fn create_history_client(config: &Config) -> Result<Box<dyn HistoryClient>> {
-    // currently there is only 1 client so just create it.
-    match TimelineClient::new(config) {
+pub fn create_history_client(config: &Config, usage_ledger: UsageLedger) -> Result<Box<dyn HistoryClient>> {
+    if config.visual_crossing.replay {
+        if !config.weather_data.is_local() {
+            Err("Weather history can only be replayed from a weather data directory.")?;
+        }
+        log::info!("Weather history will be replayed from cached responses.");
+        return Ok(Box::new(ReplayClient::new(config)));
+    }
+    match TimelineClient::new(config, usage_ledger) {
         Ok(history_client) => Ok(Box::new(history_client)),
         Err(error) => Err(error),
     }
 }
 
-/// The internal API used to get location weather history.
+/// Identifies a weather history request started by a [HistoryClient].
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HistoryHandle(usize);
+impl From<usize> for HistoryHandle {
+    /// Create the handle from its identifier.
+    fn from(id: usize) -> Self {
+        Self(id)
+    }
+}
+impl From<HistoryHandle> for usize {
+    /// Get the identifier of the handle.
+    fn from(handle: HistoryHandle) -> Self {
+        handle.0
+    }
+}
+
+/// The internal API used to get location weather history. Several requests can be active at the same
+/// time, each one is identified by the [HistoryHandle] returned when it is executed.
 ///
 pub trait HistoryClient: Debug + Send {
-    /// Execute the request to get history for a location.
+    /// Execute the request to get history for a location. It is an error to execute a request when
+    /// there are no requests [available](Self::available()).
     ///
     /// # Arguments
     ///
     /// * `location` identifies what weather history to get.
     /// * `date_range` controls the weather history dates.
     ///
-    fn execute(&self, location: &Location, date_range: &DateRange) -> Result<()>;
-    /// Query if the request has finished or return an error if there is no active request. `Ok(true)`
+    fn execute(&self, location: &Location, date_range: &DateRange) -> Result<HistoryHandle>;
+    /// Query if the request has finished or return an error if the request is not active. `Ok(true)`
     /// guarantees the request response is available.
     ///
-    fn poll(&self) -> Result<bool>;
+    /// # Arguments
+    ///
+    /// * `handle` identifies the request.
+    ///
+    fn poll(&self, handle: &HistoryHandle) -> Result<bool>;
     /// Get the request result by blocking until it finishes.
     ///
-    fn get(&self) -> Result<DailyHistories>;
+    /// # Arguments
+    ///
+    /// * `handle` identifies the request.
+    ///
+    fn get(&self, handle: HistoryHandle) -> Result<DailyHistories>;
+    /// Cancel the request. The request is no longer active after it has been cancelled so its
+    /// result can never be retrieved.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` identifies the request.
+    ///
+    fn cancel(&self, handle: HistoryHandle) -> Result<()>;
+    /// Get the number of requests that can be executed before the concurrency limit is reached.
+    ///
+    fn available(&self) -> usize;
+}
+
+/// The requests a history client has in flight.
+///
+#[derive(Debug)]
+struct ActiveRequests<T> {
+    /// The active requests by handle identifier.
+    requests: RefCell<HashMap<usize, T>>,
+    /// The identifier of the next request.
+    next_id: Cell<usize>,
+    /// The maximum number of active requests.
+    limit: usize,
+}
+impl<T> ActiveRequests<T> {
+    /// Create a new instance of the active requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` is the maximum number of active requests.
+    ///
+    fn new(limit: usize) -> Self {
+        Self { requests: Default::default(), next_id: Cell::new(1), limit: limit.max(1) }
+    }
+    /// Get the number of requests that can be added.
+    ///
+    fn available(&self) -> usize {
+        self.limit.saturating_sub(self.requests.borrow().len())
+    }
+    /// Add a request if the concurrency limit has not been reached.
+    ///
+    /// # Arguments
+    ///
+    /// * `start_request` starts the request that will be added.
+    ///
+    fn add(&self, start_request: impl FnOnce() -> Result<T>) -> Result<HistoryHandle> {
+        if self.available() == 0 {
+            Err(Error::from(format!("There are already {} active requests.", self.limit)))?;
+        }
+        let request = start_request()?;
+        let id = self.next_id.replace(self.next_id.get() + 1);
+        self.requests.borrow_mut().insert(id, request);
+        Ok(HistoryHandle(id))
+    }
+    /// Examine an active request.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` identifies the request.
+    /// * `examine` is called with the request.
+    ///
+    fn with<R>(&self, handle: &HistoryHandle, examine: impl FnOnce(&T) -> R) -> Result<R> {
+        match self.requests.borrow().get(&handle.0) {
+            Some(request) => Ok(examine(request)),
+            None => Err(Error::from(format!("Request {} is not active.", handle.0))),
+        }
+    }
+    /// Remove an active request.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` identifies the request.
+    ///
+    fn remove(&self, handle: HistoryHandle) -> Result<T> {
+        match self.requests.borrow_mut().remove(&handle.0) {
+            Some(request) => Ok(request),
+            None => Err(Error::from(format!("Request {} is not active.", handle.0))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_requests() {
+        let testcase = ActiveRequests::new(2);
+        assert_eq!(testcase.available(), 2);
+        let first = testcase.add(|| Ok("first")).unwrap();
+        let second = testcase.add(|| Ok("second")).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(testcase.available(), 0);
+        assert!(testcase.add(|| Ok("third")).is_err());
+        assert!(testcase.add(|| -> Result<&str> { panic!("request should not start") }).is_err());
+        assert_eq!(testcase.with(&second, |request| request.len()).unwrap(), 6);
+        assert_eq!(testcase.remove(first).unwrap(), "first");
+        assert!(testcase.remove(first).is_err());
+        assert!(testcase.with(&first, |_| ()).is_err());
+        assert_eq!(testcase.available(), 1);
+        let third = testcase.add(|| Err(Error::from("failed")));
+        assert!(third.is_err());
+        assert_eq!(testcase.available(), 1);
+    }
 }
@@ -0,0 +1,152 @@
+//! Support for getting weather history for many locations and long date ranges.
+//!
+//! A batch is built from the history dates of locations. Dates that already have weather
+//! history are skipped so a batch that was interrupted can be created again and it will pick up
+//! where it left off. The dates that are missing are split into chunks the history client can
+//! reasonably request in one call.
+
+use crate::entities::{DateRange, HistoryDates, Location};
+use std::collections::VecDeque;
+
+/// The weather history request for a location.
+#[derive(Debug)]
+pub struct HistoryChunk {
+    /// The location weather history will be requested for.
+    pub location: Location,
+    /// The weather history dates that will be requested.
+    pub date_range: DateRange,
+}
+
+/// The collection of weather history requests that need to be made.
+#[derive(Debug)]
+pub struct HistoryBatch {
+    /// The weather history requests that have not been returned.
+    chunks: VecDeque<HistoryChunk>,
+    /// The total number of weather history requests in the batch.
+    total: usize,
+}
+impl HistoryBatch {
+    /// The default number of days of weather history in a chunk.
+    pub const DEFAULT_CHUNK_DAYS: usize = 31;
+
+    /// Create the weather history requests for locations.
+    ///
+    /// # Arguments
+    ///
+    /// * `history_dates` has the locations and their existing history dates.
+    /// * `date_range` covers the weather history that should be available.
+    /// * `chunk_days` is the maximum number of days in a weather history request.
+    ///
+    pub fn new(history_dates: Vec<HistoryDates>, date_range: &DateRange, chunk_days: usize) -> Self {
+        let chunk_days = chunk_days.max(1);
+        let mut chunks = VecDeque::new();
+        for HistoryDates { location, history_dates } in history_dates {
+            // the count of days and range of dates currently without history
+            let mut missing: Option<(usize, DateRange)> = None;
+            for date in date_range {
+                let exists = history_dates.iter().any(|history_range| history_range.covers(&date));
+                missing = match (missing, exists) {
+                    (None, true) => None,
+                    (None, false) => Some((1, DateRange::new(date, date))),
+                    (Some((_, range)), true) => {
+                        chunks.push_back(HistoryChunk { location: location.clone(), date_range: range });
+                        None
+                    }
+                    (Some((days, range)), false) if days == chunk_days => {
+                        chunks.push_back(HistoryChunk { location: location.clone(), date_range: range });
+                        Some((1, DateRange::new(date, date)))
+                    }
+                    (Some((days, range)), false) => Some((days + 1, DateRange::new(range.start, date))),
+                };
+            }
+            if let Some((_, range)) = missing {
+                chunks.push_back(HistoryChunk { location, date_range: range });
+            }
+        }
+        let total = chunks.len();
+        Self { chunks, total }
+    }
+
+    /// Get the total number of weather history requests in the batch.
+    ///
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Get the number of weather history requests that have been taken from the batch.
+    ///
+    pub fn completed(&self) -> usize {
+        self.total - self.chunks.len()
+    }
+
+    /// Returns `true` if there are no weather history requests remaining.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+}
+impl Iterator for HistoryBatch {
+    type Item = HistoryChunk;
+    /// Get the next weather history request.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.chunks.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toolslib::date_time::get_date;
+    use crate::backend::testlib::location;
+
+    #[test]
+    fn chunks() {
+        let date_range = DateRange::new(get_date(2024, 1, 1), get_date(2024, 3, 31));
+        let history_dates = vec![HistoryDates { location: location("City", "one"), history_dates: vec![] }];
+        let mut testcase = HistoryBatch::new(history_dates, &date_range, 31);
+        assert_eq!(testcase.total(), 3);
+        assert_eq!(testcase.completed(), 0);
+        let chunk = testcase.next().unwrap();
+        assert_eq!(chunk.location.alias, "one");
+        assert_eq!(chunk.date_range, DateRange::new(get_date(2024, 1, 1), get_date(2024, 1, 31)));
+        let chunk = testcase.next().unwrap();
+        assert_eq!(chunk.date_range, DateRange::new(get_date(2024, 2, 1), get_date(2024, 3, 2)));
+        let chunk = testcase.next().unwrap();
+        assert_eq!(chunk.date_range, DateRange::new(get_date(2024, 3, 3), get_date(2024, 3, 31)));
+        assert!(testcase.is_empty());
+        assert_eq!(testcase.completed(), 3);
+        assert!(testcase.next().is_none());
+    }
+
+    #[test]
+    fn skip_existing() {
+        let date_range = DateRange::new(get_date(2024, 1, 1), get_date(2024, 1, 31));
+        let history_dates = vec![
+            HistoryDates {
+                location: location("City", "one"),
+                history_dates: vec![
+                    DateRange::new(get_date(2023, 12, 1), get_date(2024, 1, 5)),
+                    DateRange::new(get_date(2024, 1, 10), get_date(2024, 1, 20)),
+                ],
+            },
+            HistoryDates {
+                location: location("City", "two"),
+                history_dates: vec![DateRange::new(get_date(2024, 1, 1), get_date(2024, 1, 31))],
+            },
+            HistoryDates { location: location("City", "three"), history_dates: vec![] },
+        ];
+        let testcase = HistoryBatch::new(history_dates, &date_range, 10).collect::<Vec<_>>();
+        assert_eq!(testcase.len(), 7);
+        assert_eq!(testcase[0].location.alias, "one");
+        assert_eq!(testcase[0].date_range, DateRange::new(get_date(2024, 1, 6), get_date(2024, 1, 9)));
+        assert_eq!(testcase[1].location.alias, "one");
+        assert_eq!(testcase[1].date_range, DateRange::new(get_date(2024, 1, 21), get_date(2024, 1, 30)));
+        assert_eq!(testcase[2].location.alias, "one");
+        assert_eq!(testcase[2].date_range, DateRange::new(get_date(2024, 1, 31), get_date(2024, 1, 31)));
+        assert!(testcase[3..].iter().all(|chunk| chunk.location.alias == "three"));
+        assert_eq!(testcase[3].date_range, DateRange::new(get_date(2024, 1, 1), get_date(2024, 1, 10)));
+        assert_eq!(testcase[4].date_range, DateRange::new(get_date(2024, 1, 11), get_date(2024, 1, 20)));
+        assert_eq!(testcase[5].date_range, DateRange::new(get_date(2024, 1, 21), get_date(2024, 1, 30)));
+        assert_eq!(testcase[6].date_range, DateRange::new(get_date(2024, 1, 31), get_date(2024, 1, 31)));
+    }
+}
@@ -0,0 +1,164 @@
+//! A history client that replays cached timeline responses instead of calling Visual Crossing.
+
+use super::{
+    response_cache::ResponseCache,
+    timeline_client::{map_body, PROVIDER},
+    ActiveRequests, HistoryClient, HistoryHandle,
+};
+use crate::{
+    backend::Config,
+    entities::{DailyHistories, DateRange, Location},
+    Error, Result,
+};
+use std::path::PathBuf;
+
+/// The history client that serves weather history from cached responses. No network access is
+/// required.
+///
+#[derive(Debug)]
+pub struct ReplayClient {
+    /// The cached responses.
+    response_cache: ResponseCache,
+    /// The location and dates of the active requests.
+    active_requests: ActiveRequests<(Location, DateRange)>,
+}
+impl ReplayClient {
+    /// Create a new instance of the replay client. The number of active requests is limited the same
+    /// as the timeline client so replays behave like live requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` is the weather data configuration.
+    ///
+    pub fn new(config: &Config) -> Self {
+        Self::with_directory(&config.weather_data.directory, config.visual_crossing.concurrency)
+    }
+    /// Create a new instance of the replay client using cached responses in a directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` is the directory containing the cached responses.
+    /// * `concurrency` is the number of requests that can be active at the same time.
+    ///
+    fn with_directory(directory: impl Into<PathBuf>, concurrency: usize) -> Self {
+        Self {
+            response_cache: ResponseCache::new(directory, PROVIDER),
+            active_requests: ActiveRequests::new(concurrency),
+        }
+    }
+}
+impl HistoryClient for ReplayClient {
+    /// Prepare the request to get history for a location.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` is whose history will be returned.
+    /// * `date_range` is the history dates to return.
+    ///
+    fn execute(&self, location: &Location, date_range: &DateRange) -> Result<HistoryHandle> {
+        self.active_requests.add(|| Ok((location.clone(), date_range.clone())))
+    }
+    /// Cached responses are always available if the request is active.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` identifies the request.
+    ///
+    fn poll(&self, handle: &HistoryHandle) -> Result<bool> {
+        self.active_requests.with(handle, |_| true)
+    }
+    /// Get the history from the cached response. Histories outside the request dates are dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` identifies the request.
+    ///
+    fn get(&self, handle: HistoryHandle) -> Result<DailyHistories> {
+        let (location, date_range) = self.active_requests.remove(handle)?;
+        match self.response_cache.find(&location.alias, &date_range)? {
+            None => {
+                let (from, thru) = date_range.as_iso8601();
+                Err(Error::from(format!("There is no cached response for {} ({} thru {}).", location.name, from, thru)))
+            }
+            Some(cached_response) => {
+                let mut daily_histories = map_body(location, cached_response.body()?)?;
+                daily_histories.histories.retain(|history| date_range.covers(&history.date));
+                Ok(daily_histories)
+            }
+        }
+    }
+    /// Cancel the request.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` identifies the request.
+    ///
+    fn cancel(&self, handle: HistoryHandle) -> Result<()> {
+        self.active_requests.remove(handle)?;
+        Ok(())
+    }
+    /// Get the number of requests that can be executed before the concurrency limit is reached.
+    ///
+    fn available(&self) -> usize {
+        self.active_requests.available()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toolslib::date_time::get_date;
+
+    use crate::backend::testlib::{location, test_resources as test_responses};
+
+    #[test]
+    fn captured_responses() {
+        // every captured document should map into daily histories
+        let response_cache = ResponseCache::new(test_responses(), PROVIDER);
+        let responses = response_cache.responses().unwrap();
+        assert!(!responses.is_empty());
+        for response in responses {
+            let alias = response.path.parent().unwrap().file_name().unwrap().to_str().unwrap();
+            let daily_histories = map_body(location("City", alias), response.body().unwrap()).unwrap();
+            let histories = &daily_histories.histories;
+            assert!(!histories.is_empty(), "{}", response.path.display());
+            assert!(histories.iter().all(|history| response.date_range.covers(&history.date)));
+        }
+    }
+
+    #[test]
+    fn concurrency() {
+        let config = Config::try_from("[visual-crossing]\nreplay = true\nconcurrency = 2\n").unwrap();
+        assert_eq!(ReplayClient::new(&config).available(), 2);
+    }
+
+    #[test]
+    fn replay() {
+        let testcase = ReplayClient::with_directory(test_responses(), 4);
+        assert!(testcase.poll(&HistoryHandle::from(1)).is_err());
+        let location = location("City", "alias");
+        let date_range = DateRange::new(get_date(2024, 3, 1), get_date(2024, 3, 15));
+        let all = testcase.execute(&location, &date_range).unwrap();
+        // a range covered by the cached response
+        let date_range = DateRange::new(get_date(2024, 3, 5), get_date(2024, 3, 7));
+        let some = testcase.execute(&location, &date_range).unwrap();
+        // nothing is cached for these dates
+        let date_range = DateRange::new(get_date(2024, 2, 25), get_date(2024, 3, 5));
+        let none = testcase.execute(&location, &date_range).unwrap();
+        assert_eq!(testcase.available(), 1);
+        assert!(testcase.poll(&all).unwrap());
+        assert!(testcase.poll(&some).unwrap());
+        let daily_histories = testcase.get(some).unwrap();
+        assert_eq!(daily_histories.histories.len(), 3);
+        assert_eq!(daily_histories.histories[0].date, get_date(2024, 3, 5));
+        assert!(testcase.poll(&some).is_err());
+        assert!(testcase.get(none).is_err());
+        let cancelled = testcase.execute(&location, &date_range).unwrap();
+        testcase.cancel(cancelled).unwrap();
+        assert!(testcase.poll(&cancelled).is_err());
+        assert!(testcase.get(cancelled).is_err());
+        assert!(testcase.cancel(cancelled).is_err());
+        assert_eq!(testcase.get(all).unwrap().histories.len(), 15);
+        assert_eq!(testcase.available(), 4);
+    }
+}
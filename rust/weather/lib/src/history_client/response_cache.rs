@@ -0,0 +1,228 @@
+//! The on-disk cache of raw history client responses.
+//!
+//! Responses are kept in the weather data directory using the layout
+//! `responses/<provider>/<alias>/<YYYYMMDD>-<YYYYMMDD>.json`. The dates are the range of history that
+//! was requested.
+
+use crate::{entities::DateRange, Error, Result};
+use chrono::NaiveDate;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Create a response cache specific error message.
+macro_rules! error {
+    ($($arg:tt)*) => {
+        Error::from(format!("Response cache {}", format!($($arg)*)))
+    }
+}
+
+/// Create an error from the response cache specific error message.
+macro_rules! err {
+    ($($arg:tt)*) => {
+        Err(error!($($arg)*))
+    };
+}
+
+/// The format of dates in a cached response filename.
+const DATE_FORMAT: &str = "%Y%m%d";
+
+/// The extension of cached response files.
+const EXTENSION: &str = "json";
+
+/// A cached response file.
+#[derive(Debug)]
+pub struct CachedResponse {
+    /// The history dates that were requested.
+    pub date_range: DateRange,
+    /// The path of the cached response.
+    pub path: PathBuf,
+}
+impl CachedResponse {
+    /// Read the raw response body.
+    ///
+    pub fn body(&self) -> Result<Vec<u8>> {
+        match fs::read(&self.path) {
+            Ok(body) => Ok(body),
+            Err(error) => err!("could not read '{}' ({}).", self.path.display(), error),
+        }
+    }
+}
+
+/// The cache of responses for a history client provider.
+#[derive(Debug)]
+pub struct ResponseCache(
+    /// The provider directory of cached responses.
+    PathBuf,
+);
+impl ResponseCache {
+    /// The name of the directory within weather data that contains the cached responses.
+    pub const DIRNAME: &str = "responses";
+
+    /// Create a new instance of the response cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` is the weather data directory.
+    /// * `provider` identifies the history client responses.
+    ///
+    pub fn new(directory: impl Into<PathBuf>, provider: &str) -> Self {
+        Self(directory.into().join(Self::DIRNAME).join(provider))
+    }
+
+    /// Save the raw response body of a history request.
+    ///
+    /// # Arguments
+    ///
+    /// * `alias` is the location alias name.
+    /// * `date_range` is the history dates that were requested.
+    /// * `body` is the raw response body.
+    ///
+    pub fn store(&self, alias: &str, date_range: &DateRange, body: &[u8]) -> Result<()> {
+        let location_dir = self.0.join(alias);
+        if let Err(error) = fs::create_dir_all(&location_dir) {
+            err!("could not create '{}' ({}).", location_dir.display(), error)?;
+        }
+        let path = location_dir.join(filename(date_range));
+        // write to a temporary file first so a partial response is never in the cache
+        let update_path = path.with_extension("upd");
+        if let Err(error) = fs::write(&update_path, body) {
+            err!("could not write '{}' ({}).", update_path.display(), error)?;
+        }
+        match fs::rename(&update_path, &path) {
+            Ok(_) => Ok(()),
+            Err(error) => err!("could not rename '{}' ({}).", update_path.display(), error),
+        }
+    }
+
+    /// Find the cached response for a history request. If there is not an exact match a response
+    /// that covers the history dates will be returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `alias` is the location alias name.
+    /// * `date_range` is the history dates being requested.
+    ///
+    pub fn find(&self, alias: &str, date_range: &DateRange) -> Result<Option<CachedResponse>> {
+        let mut responses = self.location_responses(alias)?;
+        let exact = responses.iter().position(|response| &response.date_range == date_range);
+        let position = exact.or_else(|| {
+            responses.iter().position(|response| {
+                response.date_range.covers(&date_range.start) && response.date_range.covers(&date_range.end)
+            })
+        });
+        Ok(position.map(|index| responses.swap_remove(index)))
+    }
+
+    /// Get all the cached responses.
+    ///
+    #[cfg(test)]
+    pub fn responses(&self) -> Result<Vec<CachedResponse>> {
+        let mut responses = vec![];
+        for alias in read_dir(&self.0)?.into_iter().filter(|path| path.is_dir()) {
+            if let Some(alias) = alias.file_name().and_then(|name| name.to_str()) {
+                responses.extend(self.location_responses(alias)?);
+            }
+        }
+        Ok(responses)
+    }
+
+    /// Get the cached responses for a location.
+    ///
+    /// # Arguments
+    ///
+    /// * `alias` is the location alias name.
+    ///
+    fn location_responses(&self, alias: &str) -> Result<Vec<CachedResponse>> {
+        let mut responses: Vec<CachedResponse> = read_dir(&self.0.join(alias))?
+            .into_iter()
+            .filter_map(|path| {
+                let date_range = date_range(&path)?;
+                Some(CachedResponse { date_range, path })
+            })
+            .collect();
+        responses.sort_by_key(|response| response.date_range.start);
+        Ok(responses)
+    }
+}
+
+/// Get the cached response filename for a range of dates.
+///
+/// # Arguments
+///
+/// * `date_range` is the history dates that were requested.
+///
+fn filename(date_range: &DateRange) -> String {
+    format!("{}-{}.{}", date_range.start.format(DATE_FORMAT), date_range.end.format(DATE_FORMAT), EXTENSION)
+}
+
+/// Get the range of dates from a cached response filename.
+///
+/// # Arguments
+///
+/// * `path` is the cached response path.
+///
+fn date_range(path: &Path) -> Option<DateRange> {
+    if path.extension()? != EXTENSION {
+        return None;
+    }
+    let (start, end) = path.file_stem()?.to_str()?.split_once('-')?;
+    let start = NaiveDate::parse_from_str(start, DATE_FORMAT).ok()?;
+    let end = NaiveDate::parse_from_str(end, DATE_FORMAT).ok()?;
+    Some(DateRange::new(start, end))
+}
+
+/// Get the paths in a directory. A directory that does not exist is treated as empty.
+///
+/// # Arguments
+///
+/// * `directory` is the directory that will be read.
+///
+fn read_dir(directory: &Path) -> Result<Vec<PathBuf>> {
+    if !directory.exists() {
+        return Ok(vec![]);
+    }
+    match fs::read_dir(directory) {
+        Ok(entries) => Ok(entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect()),
+        Err(error) => err!("could not read '{}' ({}).", directory.display(), error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::testlib;
+    use toolslib::date_time::get_date;
+
+    #[test]
+    fn filenames() {
+        let testcase = DateRange::new(get_date(2024, 3, 1), get_date(2024, 3, 15));
+        let filename = filename(&testcase);
+        assert_eq!(filename, "20240301-20240315.json");
+        assert_eq!(date_range(Path::new(&filename)), Some(testcase));
+        assert!(date_range(Path::new("20240301-20240315.upd")).is_none());
+        assert!(date_range(Path::new("20240301.json")).is_none());
+        assert!(date_range(Path::new("2024-03-01-2024-03-15.json")).is_none());
+    }
+
+    #[test]
+    fn store_find() {
+        let fixture = testlib::TestFixture::create();
+        let testcase = ResponseCache::new(PathBuf::from(&fixture), "provider");
+        let march = DateRange::new(get_date(2024, 3, 1), get_date(2024, 3, 31));
+        assert!(testcase.find("alias", &march).unwrap().is_none());
+        testcase.store("alias", &march, b"march").unwrap();
+        testcase.store("alias", &DateRange::new(get_date(2024, 3, 10), get_date(2024, 3, 10)), b"day").unwrap();
+        testcase.store("other", &march, b"other").unwrap();
+        let response = testcase.find("alias", &march).unwrap().unwrap();
+        assert_eq!(response.body().unwrap(), b"march");
+        let day = DateRange::new(get_date(2024, 3, 10), get_date(2024, 3, 10));
+        assert_eq!(testcase.find("alias", &day).unwrap().unwrap().body().unwrap(), b"day");
+        let covered = DateRange::new(get_date(2024, 3, 5), get_date(2024, 3, 6));
+        assert_eq!(testcase.find("alias", &covered).unwrap().unwrap().body().unwrap(), b"march");
+        let uncovered = DateRange::new(get_date(2024, 3, 30), get_date(2024, 4, 1));
+        assert!(testcase.find("alias", &uncovered).unwrap().is_none());
+        assert_eq!(testcase.responses().unwrap().len(), 3);
+    }
+}
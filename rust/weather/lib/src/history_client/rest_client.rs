@@ -1,14 +1,22 @@
 //! The HTTP client that calls the Visual Crossing endpoint.
+use crate::{backend::Config, Error};
+use chrono::{DateTime, Utc};
 use reqwest::{
     // use the blocking API since the rest client is async.
     blocking::{Client, Request, RequestBuilder},
+    header::{HeaderMap, RETRY_AFTER},
+    Proxy,
     StatusCode,
     Url,
 };
 use std::{
     cell::RefCell,
-    sync::Arc,
-    thread::{spawn, JoinHandle},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{sleep, spawn, JoinHandle},
+    time::{Duration, Instant},
 };
 
 /// The result of a request made by the Rest client.
@@ -21,10 +29,16 @@ pub enum RestClientResult {
     ClientPanic(String),
     /// The error if there is a problem executing the Rest call.
     ExecuteError(String),
+    /// The error if a connection to the endpoint could not be made, the request was never sent.
+    ConnectError(String),
     /// The error if there is a problem receiving the response body.
     ResponseError(String),
     /// The HTTP status code returned from the endpoint.
     HttpStatusCode(u16),
+    /// The endpoint is rate limiting requests, the optional value is the seconds to wait before trying again.
+    RateLimited(Option<u64>),
+    /// The request was cancelled before it finished.
+    Cancelled,
 }
 
 #[derive(Debug)]
@@ -33,12 +47,31 @@ pub enum RestClientResult {
 pub struct RestClientHandle {
     /// The underlying thread join handle and result.
     client_handle: RefCell<Option<JoinHandle<RestClientResult>>>,
+    /// Set when the request has been cancelled, it is shared with the request thread.
+    cancelled: Arc<AtomicBool>,
 }
 impl RestClientHandle {
     /// Create a new instance of the client handle.
     ///
-    fn new(client_handle: JoinHandle<RestClientResult>) -> Self {
-        Self { client_handle: RefCell::new(Some(client_handle)) }
+    /// # Arguments
+    ///
+    /// - `client_handle` is the request thread join handle.
+    /// - `cancelled` is the flag the request thread checks to see if it should stop.
+    ///
+    fn new(client_handle: JoinHandle<RestClientResult>, cancelled: Arc<AtomicBool>) -> Self {
+        Self { client_handle: RefCell::new(Some(client_handle)), cancelled }
+    }
+    /// Cancel the request. The request thread is not waited on, it will stop at the next opportunity
+    /// and any response it receives is dropped.
+    ///
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.client_handle.take();
+    }
+    /// Check if the request has been cancelled.
+    ///
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
     }
     /// Check if the client has finished. If `true` is returned the next call to [get](Self::get()) will not block.
     ///
@@ -51,13 +84,17 @@ impl RestClientHandle {
     /// Get the result of the client request. This will block until the underlying thread exits.
     ///
     pub fn get(&self) -> RestClientResult {
-        if self.client_handle.borrow().is_none() {
+        if self.is_cancelled() {
+            RestClientResult::Cancelled
+        } else if self.client_handle.borrow().is_none() {
             log::error!("Rest client result already consumed.");
             RestClientResult::Body(vec![])
         } else {
             let client_result = self.client_handle.take().unwrap().join();
             if client_result.is_err() {
                 RestClientResult::ClientPanic(format!("{:?}", client_result.err()))
+            } else if self.is_cancelled() {
+                RestClientResult::Cancelled
             } else {
                 client_result.unwrap()
             }
@@ -65,18 +102,108 @@ impl RestClientHandle {
     }
 }
 
+/// Controls how often and how long to wait before a failed request is sent again.
+///
+#[derive(Debug, Clone, Default)]
+pub struct RetryPolicy {
+    /// The number of times a request will be retried.
+    retries: u32,
+    /// The time to wait before the first retry.
+    backoff: Duration,
+    /// The maximum time to wait before a retry.
+    max_backoff: Duration,
+    /// The longest wait the endpoint can ask for before a retry.
+    timeout: Duration,
+}
+impl RetryPolicy {
+    /// Create a new instance of the retry policy.
+    ///
+    /// # Arguments
+    ///
+    /// - `retries` is the number of times a request will be retried.
+    /// - `backoff` is the milliseconds to wait before the first retry, it doubles for each retry after that.
+    /// - `max_backoff` is the maximum milliseconds to wait before a retry.
+    ///
+    pub fn new(retries: u32, backoff: u64, max_backoff: u64) -> Self {
+        let (backoff, max_backoff) = (Duration::from_millis(backoff), Duration::from_millis(max_backoff));
+        Self { retries, backoff, max_backoff, timeout: Duration::ZERO }
+    }
+    /// Set the longest wait the endpoint can ask for before a retry. A longer wait is not retried.
+    ///
+    /// # Arguments
+    ///
+    /// - `timeout` is the seconds a request is allowed to take.
+    ///
+    pub fn with_timeout(mut self, timeout: u64) -> Self {
+        self.timeout = Duration::from_secs(timeout);
+        self
+    }
+    /// Get the time to wait before a retry.
+    ///
+    /// # Arguments
+    ///
+    /// - `attempt` is the number of retries that have already been made.
+    ///
+    fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt);
+        self.backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
 /// The asynchronous Rest client.
 ///
 #[derive(Debug, Default)]
 pub struct RestClient(
     /// The client is shared between threads so use a thread-safe reference.
     Arc<Client>,
+    /// The policy used to retry requests.
+    RetryPolicy,
 );
+impl TryFrom<&Config> for RestClient {
+    type Error = Error;
+    /// Create the Rest client using the HTTP configuration.
+    ///
+    /// # Arguments
+    ///
+    /// - `config` is the weather data configuration.
+    ///
+    fn try_from(config: &Config) -> std::result::Result<Self, Self::Error> {
+        let http = &config.http;
+        let mut builder = Client::builder()
+            .connect_timeout(Duration::from_secs(http.connect_timeout))
+            .timeout(Duration::from_secs(http.timeout))
+            .user_agent(&http.user_agent);
+        if let Some(proxy_url) = &http.proxy {
+            match Proxy::all(proxy_url) {
+                Ok(proxy) => builder = builder.proxy(proxy),
+                Err(error) => Err(Error::from(format!("Error with HTTP proxy '{}' ({})", proxy_url, error)))?,
+            }
+        }
+        match builder.build() {
+            Ok(client) => {
+                let retry_policy =
+                    RetryPolicy::new(http.retries, http.backoff, http.max_backoff).with_timeout(http.timeout);
+                Ok(RestClient::new(client).with_retry_policy(retry_policy))
+            }
+            Err(error) => Err(Error::from(format!("Error creating history client ({})", error))),
+        }
+    }
+}
 impl RestClient {
     /// Create the asynchronous Rest client.
     ///
     pub fn new(client: Client) -> Self {
-        Self(Arc::new(client))
+        Self(Arc::new(client), RetryPolicy::default())
+    }
+    /// Set the policy used to retry requests.
+    ///
+    /// # Arguments
+    ///
+    /// - `retry_policy` controls how failed requests are retried.
+    ///
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.1 = retry_policy;
+        self
     }
     /// Get the base URL of the Rest client endpoint.
     ///
@@ -91,57 +218,290 @@ impl RestClient {
     ///
     pub fn execute(&self, request: Request) -> RestClientHandle {
         let client = self.0.clone();
-        let client_handle = spawn(move || match client.execute(request) {
-            Err(err) => RestClientResult::ExecuteError(err.to_string()),
-            Ok(response) => match response.status() {
-                StatusCode::OK => match response.bytes() {
-                    Ok(bytes) => RestClientResult::Body(bytes.into()),
-                    Err(err) => RestClientResult::ResponseError(err.to_string()),
-                },
-                status_code => RestClientResult::HttpStatusCode(status_code.as_u16()),
+        let retry_policy = self.1.clone();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let client_handle = {
+            let cancelled = cancelled.clone();
+            spawn(move || execute_with_retry(&client, request, &retry_policy, &cancelled))
+        };
+        RestClientHandle::new(client_handle, cancelled)
+    }
+}
+
+/// The outcome of sending a request to the endpoint.
+///
+enum Attempt {
+    /// The request finished and should not be tried again.
+    Done(RestClientResult),
+    /// The request failed but can be tried again, optionally after the time the endpoint asked for.
+    Retry(RestClientResult, Option<Duration>),
+}
+
+/// Send the request to the endpoint retrying it if the failure is transient. The request stops
+/// before it is sent or retried if it has been cancelled.
+///
+/// # Arguments
+///
+/// - `client` is the HTTP client.
+/// - `request` is what will be sent to the endpoint.
+/// - `retry_policy` controls how failed requests are retried.
+/// - `cancelled` is set when the request should stop.
+///
+fn execute_with_retry(
+    client: &Client,
+    mut request: Request,
+    retry_policy: &RetryPolicy,
+    cancelled: &AtomicBool,
+) -> RestClientResult {
+    let mut attempt = 0;
+    loop {
+        if cancelled.load(Ordering::Relaxed) {
+            return RestClientResult::Cancelled;
+        }
+        let retry_request = match attempt < retry_policy.retries {
+            true => request.try_clone(),
+            false => None,
+        };
+        match (send(client, request), retry_request) {
+            (Attempt::Retry(result, retry_after), Some(retry_request)) => {
+                // the endpoint wait is honored up to the request timeout, the caller is told when to try again
+                let delay = match retry_after {
+                    Some(delay) if delay > retry_policy.timeout => {
+                        log::warn!("Request will not be retried, the wait of {}s is too long.", delay.as_secs());
+                        return RestClientResult::RateLimited(Some(delay.as_secs()));
+                    }
+                    Some(delay) => delay,
+                    None => retry_policy.delay(attempt),
+                };
+                log::warn!("Retrying request in {}ms ({:?}).", delay.as_millis(), result);
+                if !wait(delay, cancelled) {
+                    return RestClientResult::Cancelled;
+                }
+                attempt += 1;
+                request = retry_request;
+            }
+            (Attempt::Retry(result, _), None) | (Attempt::Done(result), _) => return result,
+        }
+    }
+}
+
+/// Wait before retrying a request returning `false` if the request was cancelled while waiting.
+///
+/// # Arguments
+///
+/// - `delay` is how long to wait.
+/// - `cancelled` is set when the request should stop.
+///
+fn wait(delay: Duration, cancelled: &AtomicBool) -> bool {
+    let pause = Duration::from_millis(50);
+    let until = Instant::now() + delay;
+    loop {
+        if cancelled.load(Ordering::Relaxed) {
+            return false;
+        }
+        let now = Instant::now();
+        if now >= until {
+            return true;
+        }
+        sleep(pause.min(until - now));
+    }
+}
+
+/// Send the request to the endpoint.
+///
+/// # Arguments
+///
+/// - `client` is the HTTP client.
+/// - `request` is what will be sent to the endpoint.
+///
+fn send(client: &Client, request: Request) -> Attempt {
+    match client.execute(request) {
+        Err(err) => {
+            let result = match err.is_connect() || err.is_builder() {
+                true => RestClientResult::ConnectError(err.to_string()),
+                false => RestClientResult::ExecuteError(err.to_string()),
+            };
+            match err.is_timeout() || err.is_connect() {
+                true => Attempt::Retry(result, None),
+                false => Attempt::Done(result),
+            }
+        }
+        Ok(response) => match response.status() {
+            StatusCode::OK => match response.bytes() {
+                Ok(bytes) => Attempt::Done(RestClientResult::Body(bytes.into())),
+                Err(err) => Attempt::Done(RestClientResult::ResponseError(err.to_string())),
             },
-        });
-        // let client_handle = spawn(move || {
-        //     thread::sleep(Duration::new(10, 0));
-        //     RestClientResult::ExecuteError("Nothing to do".to_string())
-        // });
-        RestClientHandle::new(client_handle)
+            StatusCode::TOO_MANY_REQUESTS => {
+                let retry_after = retry_after(response.headers());
+                Attempt::Retry(RestClientResult::RateLimited(retry_after.map(|delay| delay.as_secs())), retry_after)
+            }
+            status_code @ (StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT) => {
+                let retry_after = retry_after(response.headers());
+                Attempt::Retry(RestClientResult::HttpStatusCode(status_code.as_u16()), retry_after)
+            }
+            status_code => Attempt::Done(RestClientResult::HttpStatusCode(status_code.as_u16())),
+        },
     }
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-//     use serde_json::Value;
-//     use std::time::Duration;
-//
-//     #[test]
-//     fn client() {
-//         let rest_client = RestClient::new(Client::default());
-//         let mut stopwatch = toolslib::stopwatch::StopWatch::new();
-//         let url = Url::parse("https://postman-echo.com/get/with/more/path").unwrap();
-//         let request = rest_client.get(url).build().unwrap();
-//         let client_handle = rest_client.execute(request);
-//         stopwatch.start();
-//         let mut wait_count = 0usize;
-//         while wait_count < 500 {
-//             if client_handle.is_finished() {
-//                 stopwatch.stop();
-//                 break;
-//             }
-//             std::thread::sleep(Duration::from_millis(10));
-//             wait_count += 1;
-//         }
-//         assert!(wait_count < 500, "timeout");
-//         eprintln!("wait count {}, elapsed {}ms", wait_count, stopwatch.millis());
-//         let result = client_handle.get();
-//         match result {
-//             RestClientResult::Body(body) => {
-//                 let document = serde_json::from_slice::<Value>(&body[..]).unwrap();
-//                 eprintln!("{}", serde_json::to_string_pretty(&document).unwrap())
-//             }
-//             result => assert!(false, "{:?}", result),
-//         }
-//         eprintln!("{:?}", client_handle.get());
-//     }
-// }
+/// Get the time the endpoint asked to wait before sending another request. The `Retry-After` header
+/// can either be the number of seconds or an HTTP date.
+///
+/// # Arguments
+///
+/// - `headers` are the response headers.
+///
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => {
+            let date = DateTime::parse_from_rfc2822(value).ok()?;
+            let seconds = (date.with_timezone(&Utc) - Utc::now()).num_seconds().max(0);
+            Some(Duration::from_secs(seconds as u64))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+
+    /// Serve the canned responses to requests, one response per connection.
+    fn serve(responses: Vec<&'static str>) -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = vec![];
+                let mut buffer = [0u8; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let count = stream.read(&mut buffer).unwrap();
+                    if count == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buffer[..count]);
+                }
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        url
+    }
+
+    const UNAVAILABLE: &str = "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const RATE_LIMITED: &str = "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const WAIT_1S: &str = "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 1\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const WAIT_1H: &str = "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 3600\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\nbody";
+
+    fn rest_client(retries: u32) -> RestClient {
+        let client = Client::builder().no_proxy().build().unwrap();
+        RestClient::new(client).with_retry_policy(RetryPolicy::new(retries, 10, 100).with_timeout(5))
+    }
+
+    #[test]
+    fn retry() {
+        let url = serve(vec![UNAVAILABLE, RATE_LIMITED, OK]);
+        let rest_client = rest_client(2);
+        let request = rest_client.get(url).build().unwrap();
+        match rest_client.execute(request).get() {
+            RestClientResult::Body(body) => assert_eq!(body, b"body"),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn long_retry_after() {
+        // the endpoint wait is honored even though it is longer than the maximum backoff
+        let url = serve(vec![WAIT_1S, OK]);
+        let client = rest_client(1);
+        let request = client.get(url).build().unwrap();
+        match client.execute(request).get() {
+            RestClientResult::Body(body) => assert_eq!(body, b"body"),
+            result => panic!("{:?}", result),
+        }
+        // a wait longer than the request timeout is returned to the caller
+        let url = serve(vec![WAIT_1H]);
+        let client = rest_client(1);
+        let request = client.get(url).build().unwrap();
+        match client.execute(request).get() {
+            RestClientResult::RateLimited(retry_after) => assert_eq!(retry_after, Some(3600)),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn connect_error() {
+        // nothing is listening on the port once the listener is dropped
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        drop(listener);
+        let client = rest_client(0);
+        let request = client.get(url).build().unwrap();
+        match client.execute(request).get() {
+            RestClientResult::ConnectError(_) => (),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn rate_limited() {
+        let url = serve(vec![RATE_LIMITED, RATE_LIMITED]);
+        let rest_client = rest_client(1);
+        let request = rest_client.get(url).build().unwrap();
+        match rest_client.execute(request).get() {
+            RestClientResult::RateLimited(retry_after) => assert_eq!(retry_after, Some(0)),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn cancel() {
+        // whatever the request thread is doing a cancelled request never returns its result
+        let url = serve(vec![UNAVAILABLE]);
+        let rest_client = rest_client(1);
+        let request = rest_client.get(url).build().unwrap();
+        let testcase = rest_client.execute(request);
+        assert!(!testcase.is_cancelled());
+        testcase.cancel();
+        assert!(testcase.is_cancelled());
+        assert!(testcase.is_finished());
+        assert!(matches!(testcase.get(), RestClientResult::Cancelled));
+        let cancelled = AtomicBool::new(true);
+        assert!(!wait(Duration::from_secs(60), &cancelled));
+        let cancelled = AtomicBool::new(false);
+        assert!(wait(Duration::from_millis(1), &cancelled));
+    }
+
+    #[test]
+    fn delay() {
+        let testcase = RetryPolicy::new(5, 100, 500);
+        assert_eq!(testcase.delay(0), Duration::from_millis(100));
+        assert_eq!(testcase.delay(1), Duration::from_millis(200));
+        assert_eq!(testcase.delay(2), Duration::from_millis(400));
+        assert_eq!(testcase.delay(3), Duration::from_millis(500));
+        assert_eq!(testcase.delay(40), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn retry_after_header() {
+        let mut headers = HeaderMap::new();
+        assert!(retry_after(&headers).is_none());
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"));
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+        let date = (Utc::now() + chrono::Duration::seconds(300)).to_rfc2822();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&date).unwrap());
+        let seconds = retry_after(&headers).unwrap().as_secs();
+        assert!(seconds > 290 && seconds <= 300, "{seconds}");
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert!(retry_after(&headers).is_none());
+    }
+}
@@ -1,38 +1,55 @@
 //! The Visual Crossing weather data services client.
-use super::{rest_client::{RestClient, RestClientHandle, RestClientResult}, HistoryClient};
+use super::{
+    response_cache::ResponseCache,
+    rest_client::{RestClient, RestClientHandle, RestClientResult},
+    usage_ledger::UsageLedger,
+    ActiveRequests, HistoryClient, HistoryHandle,
+};
 use crate::{
     backend::Config,
     prelude::{DailyHistories, DateRange, History, Location},
     Error, Result
 };
-use chrono::DateTime;
+use chrono::{DateTime, NaiveDate};
 use reqwest::{
     // use the blocking API since the rest client is async.
-    blocking::{Client, Request},
+    blocking::Request,
     StatusCode,
     Url,
 };
 use serde::Deserialize;
 
-pub use timeline_client::TimelineClient;
+/// The provider name used for cached timeline responses.
+pub const PROVIDER: &str = "timeline";
+
+/// The timeline element that has the history date.
+const DATETIME: &str = "datetime";
+
+pub use timeline_client::{map_body, TimelineClient};
+use timeline_response::query_cost;
 mod timeline_client {
     //! The Visual Crossing timeline API client.
 
     use super::*;
-    use std::cell::RefCell;
     use std::fmt::Formatter;
 
     #[derive(Debug)]
-    /// The current timeline client request location and client handle.
+    /// A timeline client request location and client handle.
     struct ActiveRequest {
         /// The location associated with the request.
         location: Location,
+        /// The history dates associated with the request.
+        date_range: DateRange,
+        /// The records the request is expected to be charged.
+        estimated_cost: u64,
+        /// The day the estimated cost was added to the usage ledger.
+        usage_date: NaiveDate,
         /// The Rest client handle.
         client_handle: RestClientHandle,
     }
 
-    /// The Visual Crossing timeline API Rest client. The client can only run 1 request at a time. A
-    /// debug assertion is thrown if [execute](HistoryClient::execute()) is called with a request pending.
+    /// The Visual Crossing timeline API Rest client. The number of requests that can run at the same
+    /// time is limited by the `concurrency` configuration setting.
     ///
     pub struct TimelineClient {
         /// The Rest async request runner.
@@ -41,15 +58,27 @@ mod timeline_client {
         url: Url,
         /// The Visual Crossing API key.
         api_key: String,
-        /// The currently active request.
-        active_request: RefCell<Option<ActiveRequest>>,
+        /// The comma separated timeline elements requested for each day.
+        elements: String,
+        /// The currently active requests.
+        active_requests: ActiveRequests<ActiveRequest>,
+        /// The optional cache of raw responses.
+        response_cache: Option<ResponseCache>,
+        /// The ledger of request costs.
+        usage_ledger: UsageLedger,
+        /// The optional maximum number of records that can be charged in a day.
+        daily_budget: Option<u64>,
     }
     impl std::fmt::Debug for TimelineClient {
         /// Show all the attributes except the API client and API key.
         fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
             f.debug_struct("TimelineClient")
                 .field("url", &self.url)
-                .field("active_request", &self.active_request)
+                .field("elements", &self.elements)
+                .field("active_requests", &self.active_requests)
+                .field("response_cache", &self.response_cache)
+                .field("usage_ledger", &self.usage_ledger)
+                .field("daily_budget", &self.daily_budget)
                 .finish()
         }
     }
@@ -59,8 +88,9 @@ mod timeline_client {
         /// # Arguments
         ///
         /// * `config` is the weather data configuration.
+        /// * `usage_ledger` records the cost of requests.
         ///
-        pub fn new(config: &Config) -> Result<Self> {
+        pub fn new(config: &Config, usage_ledger: UsageLedger) -> Result<Self> {
             let endpoint = if config.visual_crossing.endpoint.ends_with("/") {
                 config.visual_crossing.endpoint.clone()
             } else {
@@ -71,15 +101,34 @@ mod timeline_client {
                     let reason = format!("Error parsing URL='{}' ({})", endpoint, err);
                     Err(Error::from(reason))
                 }
-                Ok(url) => match Client::builder().build() {
-                    Err(error) => Err(Error::from(format!("Error creating history client ({})", error))),
-                    Ok(client) => Ok(Self {
-                        rest_client: RestClient::new(client),
-                        url,
-                        api_key: config.visual_crossing.api_key.clone(),
-                        active_request: Default::default(),
-                    }),
-                },
+                Ok(url) => Ok(Self {
+                    rest_client: RestClient::try_from(config)?,
+                    url,
+                    api_key: config.visual_crossing.api_key.resolve()?,
+                    elements: elements(&config.visual_crossing.elements),
+                    active_requests: ActiveRequests::new(config.visual_crossing.concurrency),
+                    // responses can only be cached when weather data is kept in a directory
+                    response_cache: match config.visual_crossing.cache && config.weather_data.is_local() {
+                        true => Some(ResponseCache::new(&config.weather_data.directory, PROVIDER)),
+                        false => None,
+                    },
+                    usage_ledger,
+                    daily_budget: config.visual_crossing.daily_budget,
+                }),
+            }
+        }
+        /// Replace the estimated cost of a request in the usage ledger. A ledger problem should not lose
+        /// the history that was paid for so it is logged.
+        ///
+        /// # Arguments
+        ///
+        /// * `active_request` is the request that finished.
+        /// * `cost` is the records charged for the request.
+        ///
+        fn reconcile(&self, active_request: &ActiveRequest, cost: u64) {
+            let ActiveRequest { usage_date, estimated_cost, .. } = active_request;
+            if let Err(error) = self.usage_ledger.reconcile(*usage_date, *estimated_cost, cost) {
+                log::warn!("{}", error);
             }
         }
         /// Creates the Visual Crossing timeline URL to query weather history.
@@ -109,6 +158,7 @@ mod timeline_client {
                     let builder = self.rest_client.get(url).query(&[
                         ("unitGroup", "us"),
                         ("include", "days"),
+                        ("elements", &self.elements),
                         ("key", &self.api_key),
                     ]);
                     // build the request
@@ -131,42 +181,103 @@ mod timeline_client {
         /// * `location` is whose history will be queried.
         /// * `date_range` is the history dates to query.
         ///
-        fn execute(&self, location: &Location, date_range: &DateRange) -> Result<()> {
-            let is_active_request = self.active_request.borrow().is_some();
-            match is_active_request {
-                true => Err(Error::from("A request already in active."))?,
-                false => {
-                    let request = self.create_request(location, date_range)?;
-                    let client_handle = self.rest_client.execute(request);
-                    self.active_request.borrow_mut().replace(ActiveRequest {
-                        location: location.clone(),
-                        client_handle,
-                    });
-                    Ok(())
-                }
-            }
+        fn execute(&self, location: &Location, date_range: &DateRange) -> Result<HistoryHandle> {
+            self.active_requests.add(|| {
+                // each day of history is charged as 1 record
+                let estimated_cost = date_range.iter().count() as u64;
+                let request = self.create_request(location, date_range)?;
+                // the estimate is charged before sending so other requests see it against the budget
+                let usage_date = UsageLedger::today();
+                self.usage_ledger.reserve(usage_date, estimated_cost, self.daily_budget)?;
+                Ok(ActiveRequest {
+                    location: location.clone(),
+                    date_range: date_range.clone(),
+                    estimated_cost,
+                    usage_date,
+                    client_handle: self.rest_client.execute(request),
+                })
+            })
         }
-        /// Query if the request has finished or return an error if there is no active request. `Ok(true)`
+        /// Query if the request has finished or return an error if the request is not active. `Ok(true)`
         /// guarantees the response is available.
         ///
-        fn poll(&self) -> Result<bool> {
-            match self.active_request.borrow().as_ref() {
-                Some(active_request) => Ok(active_request.client_handle.is_finished()),
-                None => Err(Error::from("There is no active request available.")),
-            }
+        /// # Arguments
+        ///
+        /// * `handle` identifies the request.
+        ///
+        fn poll(&self, handle: &HistoryHandle) -> Result<bool> {
+            self.active_requests.with(handle, |active_request| active_request.client_handle.is_finished())
         }
         /// Get the result by blocking until the request finishes.
         ///
-        fn get(&self) -> Result<DailyHistories> {
-            match self.active_request.borrow_mut().take() {
-                // None => ControlFlow::Break(Err(Error::from("There is no active request."))),
-                None => Err(Error::from("There is no active request.")),
-                Some(active_request) => match active_request.client_handle.get() {
-                    RestClientResult::Body(body) => map_body(active_request.location, body),
-                    client_result => map_client_error(&active_request.location, client_result),
-                },
+        /// # Arguments
+        ///
+        /// * `handle` identifies the request.
+        ///
+        fn get(&self, handle: HistoryHandle) -> Result<DailyHistories> {
+            let active_request = self.active_requests.remove(handle)?;
+            match active_request.client_handle.get() {
+                RestClientResult::Body(body) => {
+                    let cost = query_cost(&body).unwrap_or(active_request.estimated_cost);
+                    self.reconcile(&active_request, cost);
+                    if let Some(response_cache) = &self.response_cache {
+                        let ActiveRequest { location, date_range, .. } = &active_request;
+                        // a cache problem should not lose the history that was paid for
+                        if let Err(error) = response_cache.store(&location.alias, date_range, &body) {
+                            log::warn!("{}", error);
+                        }
+                    }
+                    map_body(active_request.location, body)
+                }
+                client_result => {
+                    // the provider does not charge requests it rejects or never received
+                    let not_charged = matches!(
+                        client_result,
+                        RestClientResult::HttpStatusCode(_)
+                            | RestClientResult::RateLimited(_)
+                            | RestClientResult::ConnectError(_)
+                    );
+                    if not_charged {
+                        self.reconcile(&active_request, 0);
+                    }
+                    map_client_error(&active_request.location, client_result)
+                }
             }
         }
+        /// Cancel the request. The response of a cancelled request is not cached and the estimated cost
+        /// stays in the usage ledger since the provider may have already charged for it.
+        ///
+        /// # Arguments
+        ///
+        /// * `handle` identifies the request.
+        ///
+        fn cancel(&self, handle: HistoryHandle) -> Result<()> {
+            let active_request = self.active_requests.remove(handle)?;
+            active_request.client_handle.cancel();
+            log::debug!("Request for {} cancelled.", active_request.location.name);
+            Ok(())
+        }
+        /// Get the number of requests that can be executed before the concurrency limit is reached.
+        ///
+        fn available(&self) -> usize {
+            self.active_requests.available()
+        }
+    }
+
+    /// Get the timeline elements query parameter. The date is always requested since weather history
+    /// cannot be created without it.
+    ///
+    /// # Arguments
+    ///
+    /// - `elements` are the configured timeline elements.
+    ///
+    fn elements(elements: &[String]) -> String {
+        let mut elements: Vec<&str> = elements.iter().map(|element| element.trim()).collect();
+        elements.retain(|element| !element.is_empty());
+        if !elements.contains(&DATETIME) {
+            elements.insert(0, DATETIME);
+        }
+        elements.join(",")
     }
 
     /// Convert the response body into the daily histories.
@@ -175,7 +286,7 @@ mod timeline_client {
     ///
     /// - `location` is the location associated with the response.
     /// - `body` is the raw `JSON` document.
-    fn map_body(location: Location, body: Vec<u8>) -> Result<DailyHistories> {
+    pub fn map_body(location: Location, body: Vec<u8>) -> Result<DailyHistories> {
         match serde_json::from_slice::<TimelineDays>(&body[..]) {
             Ok(timeline_days) => Ok(timeline_days.into_daily_histories(&location)),
             Err(err) => Err(Error::from(format!("Error with response body document ({})", err))),
@@ -194,12 +305,17 @@ mod timeline_client {
         let what_happened = match client_result {
             ClientPanic(msg) => format!("Add history for {} panicked ({})", location.name, msg),
             ExecuteError(msg) => format!("Add history for {} did not run ({}).", location.name, msg),
+            ConnectError(msg) => format!("Add history for {} could not connect ({}).", location.name, msg),
             ResponseError(msg) => format!("Add history for {} response error ({})", location.name, msg),
+            Cancelled => format!("Add history for {} was cancelled.", location.name),
+            RateLimited(retry_after) => match retry_after {
+                Some(seconds) => format!("Too many requests, try again in {} seconds.", seconds),
+                None => "Too many requests today.".to_string(),
+            },
             HttpStatusCode(code) => {
                 let status_code = StatusCode::from_u16(code).unwrap();
                 debug_assert!(status_code != StatusCode::OK, "HTTP status is Ok\n{:#?}", location);
                 match status_code {
-                    StatusCode::TOO_MANY_REQUESTS => "Too many requests today.".to_string(),
                     StatusCode::UNAUTHORIZED => "API key was not accepted.".to_string(),
                     StatusCode::NOT_FOUND => format!(
                         "History not found for '{}' ({}/{}).",
@@ -212,6 +328,39 @@ mod timeline_client {
         };
         Err(Error::from(what_happened))
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::backend::testlib;
+
+        #[test]
+        fn timeline_elements() {
+            let testcase = |elements: &[&str]| elements.iter().map(|e| e.to_string()).collect::<Vec<String>>();
+            assert_eq!(elements(&testcase(&["datetime", "tempmax", "tempmin"])), "datetime,tempmax,tempmin");
+            assert_eq!(elements(&testcase(&["tempmax", " tempmin "])), "datetime,tempmax,tempmin");
+            assert_eq!(elements(&testcase(&["tempmax", "datetime", ""])), "tempmax,datetime");
+            assert_eq!(elements(&[]), "datetime");
+        }
+
+        #[test]
+        fn not_connected() {
+            // a request that never reaches the provider is not charged
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let endpoint = format!("http://{}/", listener.local_addr().unwrap());
+            drop(listener);
+            let config =
+                format!("[visual-crossing]\nendpoint = \"{}\"\napi-key = \"key\"\n[http]\nretries = 0", endpoint);
+            let config = Config::try_from(config.as_str()).unwrap();
+            let usage_ledger = UsageLedger::in_memory();
+            let testcase = TimelineClient::new(&config, usage_ledger.clone()).unwrap();
+            let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+            let handle = testcase.execute(&testlib::location("Tigard", "tigard"), &DateRange::new(date, date)).unwrap();
+            assert!(testcase.get(handle).is_err());
+            let usage = usage_ledger.usage(UsageLedger::today()).unwrap();
+            assert_eq!((usage.requests, usage.cost), (1, 0));
+        }
+    }
 }
 
 use timeline_response::TimelineDays;
@@ -225,7 +374,7 @@ mod timeline_response {
     #[derive(Debug, Deserialize)]
     struct TimelineDay {
         /// The date associated with the history.
-        datetime: String,
+        datetime: Option<String>,
         /// The high temperature.
         tempmax: Option<f64>,
         /// The low temperature.
@@ -266,16 +415,24 @@ mod timeline_response {
         description: Option<String>,
     }
     impl TimelineDay {
-        /// Convert the visual crossing timeline day into [History].
+        /// Convert the visual crossing timeline day into [History]. `None` is returned if the day
+        /// does not have a valid date.
         ///
         /// # Arguments
         ///
         /// * `alias` is the location alias name.
         ///
-        fn into_history(self, alias: &str) -> History {
-            History {
+        fn into_history(self, alias: &str) -> Option<History> {
+            let date = match self.datetime.as_deref().map(toolslib::date_time::parse_date) {
+                Some(Ok(date)) => date,
+                _ => {
+                    log::warn!("{} timeline day dropped, the date is not valid ({:?}).", alias, self.datetime);
+                    return None;
+                }
+            };
+            Some(History {
                 alias: alias.to_string(),
-                date: toolslib::date_time::parse_date(&self.datetime).map_or(Default::default(), |d| d),
+                date,
                 temperature_high: self.tempmax,
                 temperature_low: self.tempmin,
                 temperature_mean: self.temp,
@@ -302,14 +459,33 @@ mod timeline_response {
                 moon_phase: self.moonphase,
                 visibility: self.visibility,
                 description: self.description,
-            }
+            })
         }
     }
 
+    /// The records Visual Crossing charged for the response.
+    #[allow(non_snake_case)]
+    #[derive(Debug, Deserialize)]
+    struct TimelineCost {
+        /// The query cost.
+        queryCost: Option<u64>,
+    }
+
+    /// Get the records charged for a response, if the response has it.
+    ///
+    /// # Arguments
+    ///
+    /// - `body` is the raw `JSON` document.
+    ///
+    pub fn query_cost(body: &[u8]) -> Option<u64> {
+        serde_json::from_slice::<TimelineCost>(body).ok()?.queryCost
+    }
+
     /// The fields of interest from the Visual Crossing response.
     #[derive(Debug, Deserialize)]
     pub struct TimelineDays {
         /// The weather history days corresponding to the request dates.
+        #[serde(default)]
         days: Vec<TimelineDay>,
     }
     impl TimelineDays {
@@ -325,7 +501,7 @@ mod timeline_response {
                 histories: self
                     .days
                     .into_iter()
-                    .map(|timeline_day| timeline_day.into_history(&location.alias))
+                    .filter_map(|timeline_day| timeline_day.into_history(&location.alias))
                     .collect(),
             }
         }
@@ -336,9 +512,50 @@ mod timeline_response {
         use super::*;
         use chrono::NaiveDate;
 
+        #[test]
+        fn cost() {
+            let response = include_str!("../../resources/tests/responses/timeline/alias/20240301-20240315.json");
+            assert_eq!(query_cost(response.as_bytes()), Some(15));
+            assert!(query_cost(br#"{"days": []}"#).is_none());
+            assert!(query_cost(b"not json").is_none());
+        }
+
+        #[test]
+        fn missing_elements() {
+            let location = Location {
+                city: "city".to_string(),
+                state_id: "ST".to_string(),
+                state: "state".to_string(),
+                name: "name".to_string(),
+                alias: "alias".to_string(),
+                longitude: "-111".to_string(),
+                latitude: "47".to_string(),
+                tz: "America/Denver".to_string(),
+            };
+            let response = r#"{"days": [
+                {"datetime": "2024-03-01", "tempmax": 43.4},
+                {"tempmax": 50.1},
+                {"datetime": "yesterday"},
+                {"datetime": "2024-03-03", "preciptype": null, "humidity": 80.0}
+            ]}"#;
+            let timeline_days = serde_json::from_slice::<TimelineDays>(response.as_bytes()).unwrap();
+            let histories = timeline_days.into_daily_histories(&location).histories;
+            assert_eq!(histories.len(), 2);
+            assert_eq!(histories[0].date, NaiveDate::from_ymd_opt(2024, 3, 1).unwrap());
+            assert_eq!(histories[0].temperature_high, Some(43.4));
+            assert!(histories[0].temperature_low.is_none());
+            assert!(histories[0].description.is_none());
+            assert_eq!(histories[1].date, NaiveDate::from_ymd_opt(2024, 3, 3).unwrap());
+            assert!(histories[1].precipitation_type.is_none());
+            assert_eq!(histories[1].humidity, Some(0.8));
+            // a response without days has no histories
+            let timeline_days = serde_json::from_slice::<TimelineDays>(br#"{"queryCost": 0}"#).unwrap();
+            assert!(timeline_days.into_daily_histories(&location).histories.is_empty());
+        }
+
         #[test]
         fn daily_histories() {
-            let response = include_str!("response.json");
+            let response = include_str!("../../resources/tests/responses/timeline/alias/20240301-20240315.json");
             let location = Location {
                 city: "city".to_string(),
                 state_id: "abrev_state".to_string(),
@@ -0,0 +1,278 @@
+//! The persistent ledger of history client query costs.
+//!
+//! The ledger is a `JSON` document in the weather data directory. It has the number of history
+//! requests and records charged for each day requests were made. Days are in `UTC` since that is
+//! when the provider resets the daily record count.
+//!
+//! The estimated cost of a request is added when the request is sent and corrected with the charged
+//! cost when the response arrives. Updates hold a lock on a file next to the ledger so programs
+//! sharing the weather data directory do not overwrite each other's usage.
+//!
+//! When weather data is not kept in a directory the ledger is kept in memory and only lasts as long
+//! as the weather data.
+
+use crate::{entities::QueryUsage, Error, Result};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// Create a usage ledger specific error message.
+macro_rules! error {
+    ($($arg:tt)*) => {
+        Error::from(format!("Usage ledger {}", format!($($arg)*)))
+    }
+}
+
+/// Create an error from the usage ledger specific error message.
+macro_rules! err {
+    ($($arg:tt)*) => {
+        Err(error!($($arg)*))
+    };
+}
+
+/// The requests and cost recorded for a day.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct DailyUsage {
+    /// The number of history requests.
+    requests: usize,
+    /// The records charged for the requests.
+    cost: u64,
+}
+
+/// Where the ledger is kept.
+#[derive(Debug, Clone)]
+enum Storage {
+    /// The ledger document path.
+    File(PathBuf),
+    /// The ledger shared by the history clients of in memory weather data.
+    Memory(Arc<Mutex<BTreeMap<NaiveDate, DailyUsage>>>),
+}
+
+/// The ledger of query costs by day.
+#[derive(Debug, Clone)]
+pub struct UsageLedger(Storage);
+impl UsageLedger {
+    /// The name of the ledger document within the weather data directory.
+    pub const FILENAME: &str = "query-usage.json";
+
+    /// The name of the file locked while the ledger is updated.
+    const LOCK_FILENAME: &str = "query-usage.lock";
+
+    /// Create a new instance of the usage ledger.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` is the weather data directory.
+    ///
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self(Storage::File(directory.into().join(Self::FILENAME)))
+    }
+
+    /// Create a new instance of the usage ledger that is kept in memory. Clones of the ledger share
+    /// the usage.
+    ///
+    pub fn in_memory() -> Self {
+        Self(Storage::Memory(Arc::default()))
+    }
+
+    /// Get the current day used by the ledger.
+    ///
+    pub fn today() -> NaiveDate {
+        Utc::now().date_naive()
+    }
+
+    /// Get the usage for a day.
+    ///
+    /// # Arguments
+    ///
+    /// * `date` is the day of interest.
+    ///
+    pub fn usage(&self, date: NaiveDate) -> Result<QueryUsage> {
+        let ledger = self.load()?;
+        let (requests, cost) = ledger.get(&date).map_or((0, 0), |usage| (usage.requests, usage.cost));
+        Ok(QueryUsage { date, requests, cost, budget: None })
+    }
+
+    /// Add a request and its estimated cost to the ledger before the request is sent, returning the
+    /// updated usage. An error is returned and nothing is added if the cost would exceed the budget.
+    ///
+    /// # Arguments
+    ///
+    /// * `date` is the day the request is made.
+    /// * `cost` is the records the request is expected to be charged.
+    /// * `budget` is the maximum records that can be charged in a day, if there is one.
+    ///
+    pub fn reserve(&self, date: NaiveDate, cost: u64, budget: Option<u64>) -> Result<QueryUsage> {
+        self.update(date, |daily_usage| {
+            if let Some(budget) = budget {
+                if daily_usage.cost + cost > budget {
+                    let reason = format!(
+                        "The request would exceed the daily budget of {} records ({} used).",
+                        budget, daily_usage.cost
+                    );
+                    return Err(Error::from(reason));
+                }
+            }
+            daily_usage.requests += 1;
+            daily_usage.cost += cost;
+            Ok(())
+        })
+    }
+
+    /// Replace the estimated cost of a request with the records it was charged, returning the updated
+    /// usage.
+    ///
+    /// # Arguments
+    ///
+    /// * `date` is the day the request was made.
+    /// * `estimated_cost` is the cost added when the request was reserved.
+    /// * `cost` is the records charged for the request.
+    ///
+    pub fn reconcile(&self, date: NaiveDate, estimated_cost: u64, cost: u64) -> Result<QueryUsage> {
+        self.update(date, |daily_usage| {
+            daily_usage.cost = daily_usage.cost.saturating_sub(estimated_cost) + cost;
+            Ok(())
+        })
+    }
+
+    /// Change the usage of a day while the ledger is locked.
+    ///
+    /// # Arguments
+    ///
+    /// * `date` is the day that will be changed.
+    /// * `change` is called with the usage of the day, nothing is saved if it returns an error.
+    ///
+    fn update(&self, date: NaiveDate, change: impl FnOnce(&mut DailyUsage) -> Result<()>) -> Result<QueryUsage> {
+        let apply = |ledger: &mut BTreeMap<NaiveDate, DailyUsage>| {
+            let daily_usage = ledger.entry(date).or_default();
+            change(daily_usage)?;
+            Ok(QueryUsage { date, requests: daily_usage.requests, cost: daily_usage.cost, budget: None })
+        };
+        match &self.0 {
+            Storage::File(path) => {
+                let _lock = Self::lock(path)?;
+                let mut ledger = self.load()?;
+                let usage = apply(&mut ledger)?;
+                Self::save(path, &ledger)?;
+                Ok(usage)
+            }
+            Storage::Memory(ledger) => apply(&mut ledger.lock().unwrap_or_else(PoisonError::into_inner)),
+        }
+    }
+
+    /// Lock the ledger, it is unlocked when the returned file is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` is the ledger document path.
+    ///
+    fn lock(path: &Path) -> Result<File> {
+        let path = path.with_file_name(Self::LOCK_FILENAME);
+        let file = match OpenOptions::new().write(true).create(true).truncate(false).open(&path) {
+            Ok(file) => file,
+            Err(error) => err!("could not open '{}' ({}).", path.display(), error)?,
+        };
+        match file.lock() {
+            Ok(_) => Ok(file),
+            Err(error) => err!("could not lock '{}' ({}).", path.display(), error),
+        }
+    }
+
+    /// Read the ledger document. A ledger that does not exist is treated as empty.
+    ///
+    fn load(&self) -> Result<BTreeMap<NaiveDate, DailyUsage>> {
+        let path = match &self.0 {
+            Storage::File(path) => path,
+            Storage::Memory(ledger) => return Ok(ledger.lock().unwrap_or_else(PoisonError::into_inner).clone()),
+        };
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        match fs::read(path) {
+            Ok(document) => match serde_json::from_slice(&document) {
+                Ok(ledger) => Ok(ledger),
+                Err(error) => err!("'{}' is not valid ({}).", path.display(), error),
+            },
+            Err(error) => err!("could not read '{}' ({}).", path.display(), error),
+        }
+    }
+
+    /// Write the ledger document.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` is the ledger document path.
+    /// * `ledger` is the usage by day.
+    ///
+    fn save(path: &Path, ledger: &BTreeMap<NaiveDate, DailyUsage>) -> Result<()> {
+        let document = match serde_json::to_vec_pretty(ledger) {
+            Ok(document) => document,
+            Err(error) => err!("could not be serialized ({}).", error)?,
+        };
+        // write to a temporary file first so the ledger is never partially written
+        let update_path = path.with_extension("upd");
+        if let Err(error) = fs::write(&update_path, document) {
+            err!("could not write '{}' ({}).", update_path.display(), error)?;
+        }
+        match fs::rename(&update_path, path) {
+            Ok(_) => Ok(()),
+            Err(error) => err!("could not rename '{}' ({}).", update_path.display(), error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::testlib;
+    use toolslib::date_time::get_date;
+
+    #[test]
+    fn ledger() {
+        let fixture = testlib::TestFixture::create();
+        let testcase = UsageLedger::new(PathBuf::from(&fixture));
+        let today = get_date(2024, 3, 1);
+        let tomorrow = get_date(2024, 3, 2);
+        assert_eq!(testcase.usage(today).unwrap(), QueryUsage { date: today, ..Default::default() });
+        testcase.reserve(today, 15, None).unwrap();
+        let usage = testcase.reserve(today, 10, Some(25)).unwrap();
+        assert_eq!(usage.requests, 2);
+        assert_eq!(usage.cost, 25);
+        assert!(testcase.reserve(today, 1, Some(25)).is_err());
+        assert_eq!(testcase.usage(today).unwrap().requests, 2);
+        // the charged cost replaces the estimate
+        assert_eq!(testcase.reconcile(today, 10, 4).unwrap().cost, 19);
+        testcase.reserve(tomorrow, 31, None).unwrap();
+        assert_eq!(testcase.usage(today).unwrap().cost, 19);
+        let usage = testcase.usage(tomorrow).unwrap();
+        assert_eq!((usage.requests, usage.cost), (1, 31));
+        // the ledger is persistent
+        let testcase = UsageLedger::new(PathBuf::from(&fixture));
+        assert_eq!(testcase.usage(today).unwrap().requests, 2);
+    }
+
+    #[test]
+    fn shared() {
+        // programs sharing the weather data directory do not lose each other's usage
+        let fixture = testlib::TestFixture::create();
+        let today = get_date(2024, 3, 1);
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let directory = PathBuf::from(&fixture);
+                std::thread::spawn(move || {
+                    let ledger = UsageLedger::new(directory);
+                    for _ in 0..10 {
+                        ledger.reserve(today, 1, None).unwrap();
+                    }
+                })
+            })
+            .collect();
+        threads.into_iter().for_each(|thread| thread.join().unwrap());
+        assert_eq!(UsageLedger::new(PathBuf::from(&fixture)).usage(today).unwrap().cost, 40);
+    }
+}
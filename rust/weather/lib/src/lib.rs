@@ -72,25 +72,41 @@ mod entities;
 
 mod history_client;
 
+mod export;
+
 mod admin;
 
+mod config;
+
 /// The public weather data API.
 pub mod prelude {
     pub use crate::{
+        backend::MEMORY_DIRNAME,
         entities::{
             DailyHistories, DateRange, DateRanges, History, HistoryDates, HistorySummaries, HistorySummary, Location,
-            LocationFilter, LocationFilters, State, CityFilter,
+            LocationFilter, LocationFilters, QueryUsage, State, CityFilter,
         },
-        history_client::HistoryClient,
+        export::{ExportDetails, ExportFormat},
+        history_client::{HistoryBatch, HistoryChunk, HistoryClient, HistoryHandle},
         location_filter, location_filters,
         weather_data::{create_weather_data, WeatherData},
     };
 }
 
+/// The public weather data configuration API.
+pub mod config_prelude {
+    pub use crate::config::{
+        config_settings, find_config_file, starter_config, user_config_path, validate_config, ConfigSetting,
+        ConfigSettings, ConfigSource, ENV_CONFIG, ENV_TOKEN,
+    };
+}
+
 /// The public administration weather data API.
 pub mod admin_prelude {
     pub use crate::admin::{
-        create_weather_admin, Components, DbDetails, FilesysDetails, LocationDetails, UsCityDetails, WeatherAdmin,
+        create_weather_admin, BackupDetails, Components, DbDetails, FilesysDetails, LocationDetails, Recovery,
+        RecoveryAction, RestoreDetails, SqlResults, SyncDetails, SyncLocation, SyncTarget, UsCityDetails,
+        VerifyDetails, VerifyProblem, WeatherAdmin,
     };
 }
 
@@ -3,20 +3,29 @@ use crate::{
     backend::{create, Backend},
     entities::{
         DailyHistories, DateRange, HistoryDates, HistorySummaries, Location, LocationFilter, LocationFilters,
-        State, CityFilter,
+        QueryUsage, State, CityFilter,
     },
-    history_client::HistoryClient,
+    export::{ExportDetails, ExportFormat, ExportWriter},
+    history_client::{HistoryBatch, HistoryClient, UsageLedger},
     location_filters, Result,
 };
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Creates the weather data `API` depending on the backend configuration.
 ///
 /// # Arguments
 ///
+/// * `config_file` is the weather data configuration filename.
+/// * `profile` is the name of the configuration profile to use.
 /// * `dirname` is the weather data directory name.
-pub fn create_weather_data(config_file: Option<PathBuf>, dirname: Option<PathBuf>, no_db: bool) -> Result<WeatherData> {
-    Ok(WeatherData(create(config_file, dirname, no_db)?))
+/// * `no_db` forces the filesys backend to be used.
+pub fn create_weather_data(
+    config_file: Option<PathBuf>,
+    profile: Option<String>,
+    dirname: Option<PathBuf>,
+    no_db: bool,
+) -> Result<WeatherData> {
+    Ok(WeatherData(create(config_file, profile, dirname, no_db)?))
 }
 
 /// The weather data `API`.
@@ -39,7 +48,36 @@ impl WeatherData {
     /// Get the client that retrieves weather history for a location.
     ///
     pub fn get_history_client(&self) -> Result<Box<dyn HistoryClient>> {
-        crate::history_client::create_history_client(self.0.get_config())
+        crate::history_client::create_history_client(self.0.get_config(), self.0.get_usage_ledger())
+    }
+
+    /// Get the history client requests made today and what they cost.
+    ///
+    pub fn get_query_usage(&self) -> Result<QueryUsage> {
+        let config = self.0.get_config();
+        let mut query_usage = self.0.get_usage_ledger().usage(UsageLedger::today())?;
+        query_usage.budget = config.visual_crossing.daily_budget;
+        Ok(query_usage)
+    }
+
+    /// Get the weather history requests needed to cover a range of dates for locations. Dates that
+    /// already have history will not be requested.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` identifies the locations.
+    /// * `history_range` covers the history dates that should be available.
+    /// * `chunk_days` is the maximum number of days in a single weather history request.
+    ///
+    pub fn get_history_batch(
+        &self,
+        filters: LocationFilters,
+        history_range: &DateRange,
+        chunk_days: usize,
+    ) -> Result<HistoryBatch> {
+        crate::log_elapsed_time!(info, "get_history_batch");
+        let history_dates = self.0.get_history_dates(filters)?;
+        Ok(HistoryBatch::new(history_dates, history_range, chunk_days))
     }
 
     /// Get daily weather history for a location.
@@ -56,6 +94,18 @@ impl WeatherData {
         self.0.get_daily_histories(location_filters![filter], history_range)
     }
 
+    /// Get daily weather history for a location selected by its alias.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` is the location whose history is returned.
+    /// * `history_range` covers the history dates returned.
+    ///
+    pub fn get_location_histories(&self, location: Location, history_range: DateRange) -> Result<DailyHistories> {
+        crate::log_elapsed_time!(info, "get_location_histories");
+        self.0.get_location_histories(location, history_range)
+    }
+
     /// Get the history dates for locations.
     ///
     /// # Arguments
@@ -78,6 +128,98 @@ impl WeatherData {
         self.0.get_history_summaries(filters)
     }
 
+    /// Search the weather history descriptions of locations, for example `thunderstorms` or
+    /// `"freezing fog"`. Only locations with matching histories are returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` identifies the locations that will be searched.
+    /// * `query` selects the history descriptions that match.
+    /// * `history_range` restricts the history dates searched, if `None` all history is searched.
+    ///
+    pub fn search_histories(
+        &self,
+        filters: LocationFilters,
+        query: &str,
+        history_range: Option<DateRange>,
+    ) -> Result<Vec<DailyHistories>> {
+        crate::log_elapsed_time!(info, "search_histories");
+        self.0.search_histories(filters, query, history_range)
+    }
+
+    /// Export location histories to `NDJSON` or `Parquet` files. Locations without histories are not
+    /// exported.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` identifies the locations.
+    /// * `history_range` restricts the history dates exported, `None` exports all histories.
+    /// * `format` is the export file format.
+    /// * `path` is the export file, or the directory location files are written to.
+    /// * `per_location` writes a file for each location named by its alias.
+    ///
+    pub fn export_histories(
+        &self,
+        filters: LocationFilters,
+        history_range: Option<DateRange>,
+        format: ExportFormat,
+        path: &Path,
+        per_location: bool,
+    ) -> Result<ExportDetails> {
+        crate::log_elapsed_time!(info, "export_histories");
+        if per_location && !path.is_dir() {
+            Err(format!("Export: {} is not a directory.", path.display()))?;
+        }
+        let mut details = ExportDetails::default();
+        let mut combined: Option<ExportWriter> = None;
+        for history_dates in self.0.get_history_dates(filters)? {
+            let dates = &history_dates.history_dates;
+            let range = match (&history_range, dates.first(), dates.last()) {
+                (Some(range), _, _) => range.clone(),
+                (None, Some(first), Some(last)) => DateRange::new(first.start, last.end),
+                _ => continue,
+            };
+            let daily_histories = self.0.get_location_histories(history_dates.location, range)?;
+            if daily_histories.histories.is_empty() {
+                continue;
+            }
+            let result = match per_location {
+                true => {
+                    let alias = &daily_histories.location.alias;
+                    let file = path.join(format!("{}.{}", alias, format.extension()));
+                    let mut writer = ExportWriter::create(&file, format)?;
+                    match writer.write(&daily_histories.location, &daily_histories.histories) {
+                        Ok(_) => writer.finish().map(|_| details.files.push(file)),
+                        Err(error) => {
+                            writer.discard();
+                            Err(error)
+                        }
+                    }
+                }
+                false => {
+                    if combined.is_none() {
+                        combined.replace(ExportWriter::create(path, format)?);
+                    }
+                    let writer = combined.as_mut().unwrap();
+                    writer.write(&daily_histories.location, &daily_histories.histories)
+                }
+            };
+            if let Err(error) = result {
+                if let Some(writer) = combined.take() {
+                    writer.discard();
+                }
+                return Err(error);
+            }
+            details.locations += 1;
+            details.histories += daily_histories.histories.len();
+        }
+        if let Some(writer) = combined {
+            writer.finish()?;
+            details.files.push(path.to_path_buf());
+        }
+        Ok(details)
+    }
+
     /// Get the weather location metadata.
     ///
     /// # Arguments
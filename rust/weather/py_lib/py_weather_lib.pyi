@@ -68,6 +68,12 @@ class PyWeatherConfig:
     @fs_only.setter
     def fs_only(self, bool) -> None: ...
 
+    @property
+    def profile(self) -> str | None: ...
+
+    @profile.setter
+    def profile(self, str) -> None: ...
+
     def __new__(
             cls,
             config_file: str | None = None,
@@ -76,15 +82,22 @@ class PyWeatherConfig:
             log_append=False,
             log_level=0,
             fs_only=False,
+            profile: str | None = None,
     ) -> PyWeatherConfig: ...
 
+    def validate(self) -> List[str]: ...
+
 
 class PyHistoryClient:
-    def execute(self, location: PyLocation, date_range: PyDateRange) -> None: ...
+    def execute(self, location: PyLocation, date_range: PyDateRange) -> int: ...
+
+    def poll(self, handle: int) -> bool: ...
+
+    def get(self, handle: int) -> PyDailyHistories: ...
 
-    def poll(self) -> bool: ...
+    def cancel(self, handle: int) -> None: ...
 
-    def get(self) -> PyDailyHistories: ...
+    def available(self) -> int: ...
 
 
 class PyLocation:
@@ -3,6 +3,7 @@
 use super::*;
 use chrono::prelude::{NaiveDate, NaiveDateTime};
 use std::path::PathBuf;
+use weather_lib::config_prelude::{find_config_file, validate_config};
 use weather_lib::prelude::{
     CityFilter, DailyHistories, DateRange, History, HistoryDates, HistorySummaries, Location, LocationFilter,
     LocationFilters, State
@@ -17,11 +18,12 @@ pub struct PyWeatherConfig {
     pub log_append: bool,
     pub log_level: usize,
     pub fs_only: bool,
+    pub profile: Option<String>,
 }
 #[pymethods]
 impl PyWeatherConfig {
     #[new]
-    #[pyo3(signature = (config_file=None, dirname=None, logfile=None, log_append=false, log_level=0, fs_only=false))]
+    #[pyo3(signature = (config_file=None, dirname=None, logfile=None, log_append=false, log_level=0, fs_only=false, profile=None))]
     fn new(
         config_file: Option<PathBuf>,
         dirname: Option<PathBuf>,
@@ -29,8 +31,23 @@ impl PyWeatherConfig {
         log_append: bool,
         log_level: usize,
         fs_only: bool,
+        profile: Option<String>,
     ) -> Self {
-        Self { config_file, dirname, logfile, log_append, log_level, fs_only }
+        Self { config_file, dirname, logfile, log_append, log_level, fs_only, profile }
+    }
+    /// Validate the configuration file. An error is raised if the file cannot be loaded otherwise the
+    /// problems found are returned. The weather data directory is checked in place of the configured
+    /// weather data if `dirname` is set.
+    fn validate(&self) -> PyResult<Vec<String>> {
+        let path = match find_config_file(self.config_file.clone()) {
+            Ok(Some(path)) => path,
+            Ok(None) => return Ok(vec![]),
+            Err(error) => return system_err!(error),
+        };
+        match validate_config(&path, self.dirname.as_deref()) {
+            Ok(problems) => Ok(problems),
+            Err(error) => system_err!(error),
+        }
     }
 }
 
@@ -2,7 +2,7 @@
 
 use super::*;
 use py_entities::*;
-use weather_lib::prelude::HistoryClient;
+use weather_lib::prelude::{HistoryClient, HistoryHandle};
 
 #[pyclass]
 pub struct PyHistoryClient {
@@ -17,34 +17,59 @@ impl PyHistoryClient {
 ///
 #[pymethods]
 impl PyHistoryClient {
-    /// Execute the request to get history for a location.
+    /// Execute the request to get history for a location returning the request handle.
     ///
     /// # Arguments
     ///
     /// * `location` identifies what weather history to get.
     /// * `date_range` controls the weather history dates.
     ///
-    fn execute(&self, location: PyLocation, date_range: PyDateRange) -> PyResult<()> {
+    fn execute(&self, location: PyLocation, date_range: PyDateRange) -> PyResult<usize> {
         match self.history_client.execute(&location.into(), &date_range.into()) {
             Err(error) => system_err!(error.to_string()),
-            Ok(_) => Ok(()),
+            Ok(handle) => Ok(handle.into()),
         }
     }
-    /// Query if the request has finished or return an error if there is no active request. `Ok(true)`
+    /// Query if the request has finished or return an error if the request is not active. `Ok(true)`
     /// guarantees the request response is available.
     ///
-    fn poll(&self) -> PyResult<bool> {
-        match self.history_client.poll() {
+    /// # Arguments
+    ///
+    /// * `handle` identifies the request.
+    ///
+    fn poll(&self, handle: usize) -> PyResult<bool> {
+        match self.history_client.poll(&HistoryHandle::from(handle)) {
             Err(error) => system_err!(error.to_string()),
             Ok(completed) => Ok(completed),
         }
     }
     /// Get the request result by blocking until it finishes.
     ///
-    fn get(&self) -> PyResult<PyDailyHistories> {
-        match self.history_client.get() {
+    /// # Arguments
+    ///
+    /// * `handle` identifies the request.
+    ///
+    fn get(&self, handle: usize) -> PyResult<PyDailyHistories> {
+        match self.history_client.get(HistoryHandle::from(handle)) {
             Err(error) => system_err!(error.to_string()),
             Ok(daily_histories) => Ok(daily_histories.into()),
         }
     }
+    /// Cancel the request.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` identifies the request.
+    ///
+    fn cancel(&self, handle: usize) -> PyResult<()> {
+        match self.history_client.cancel(HistoryHandle::from(handle)) {
+            Err(error) => system_err!(error.to_string()),
+            Ok(_) => Ok(()),
+        }
+    }
+    /// Get the number of requests that can be executed before the concurrency limit is reached.
+    ///
+    fn available(&self) -> usize {
+        self.history_client.available()
+    }
 }
@@ -64,7 +64,7 @@ pub fn create(init: PyWeatherConfig) -> PyResult<PyWeatherData> {
             Err(error) => system_err!(error)?,
         }
     }
-    match weather_lib::create_weather_data(init.config_file, init.dirname, init.fs_only) {
+    match weather_lib::create_weather_data(init.config_file, init.profile, init.dirname, init.fs_only) {
         Ok(weather_data) => {
             log::debug!("created weather data");
             Ok(PyWeatherData(weather_data))
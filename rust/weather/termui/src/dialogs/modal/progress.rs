@@ -1,6 +1,7 @@
 //! The TUI progress dialog.
 
 use super::*;
+use controls::{cancel_button, ButtonBar};
 use std::{cell::RefCell, time::{Duration, SystemTime}};
 
 /// A progress indicator that bounces back and forth.
@@ -61,6 +62,8 @@ pub struct ProgressDialog {
     frame: DialogFrame,
     /// The description of what's being tracked.
     description: String,
+    /// The optional status of the progress being made.
+    status: Option<String>,
     /// The size of the dialog.
     size: Size,
     /// The next render time.
@@ -69,6 +72,8 @@ pub struct ProgressDialog {
     render_duration: u64,
     /// The progress indicator,
     progress_indicator: RefCell<CylonEye>,
+    /// The optional button used to cancel what is being tracked.
+    buttons: Option<ButtonBar>,
     /// The button dialog style catalog type. This will always be [CatalogType::ProgressDialog].
     pub catalog_type: CatalogType,
 }
@@ -87,16 +92,52 @@ impl ProgressDialog {
         Self {
             frame: DialogFrame::default().with_border(),
             description: description.to_string(),
+            status: None,
             size: Size { width, height: 6 },
             next_render: RefCell::new(SystemTime::now()),
             render_duration: 20,
             progress_indicator: RefCell::new(cylon_eye),
+            buttons: None,
             catalog_type: CatalogType::ProgressDialog,
         }
     }
-    /// Consume a key pressed event always returning [Continue](DialogResult::Continue).
+    /// A builder method that adds a `Cancel` button to the dialog. Selecting the button or pressing
+    /// `Esc` will return [Cancel](DialogResult::Cancel) from [key_pressed](Self::key_pressed()).
     ///
-    pub fn key_pressed(&mut self, _key_event: KeyEvent) -> ControlFlow<DialogResult> {
+    pub fn with_cancel(mut self) -> Self {
+        let buttons = ButtonBar::new(vec![cancel_button().with_active()]).with_auto_select(true);
+        // the buttons are drawn below a separator row
+        self.size.width = cmp::max(self.size.width, buttons.size().width + 4);
+        self.size.height += buttons.size().height + 1;
+        self.buttons.replace(buttons);
+        self
+    }
+    /// Set the status shown between the description and progress indicator.
+    ///
+    /// # Arguments
+    ///
+    /// - `status` describes the progress that has been made.
+    ///
+    pub fn set_status(&mut self, status: impl ToString) {
+        let status = status.to_string();
+        self.size.width = cmp::max(self.size.width, status.len() as u16 + 4);
+        self.status.replace(status);
+    }
+    /// Consume a key pressed event. [Cancel](DialogResult::Cancel) is returned if the dialog has a
+    /// `Cancel` button and it was selected, otherwise [Continue](DialogResult::Continue) is returned.
+    ///
+    /// # Arguments
+    ///
+    /// - `key_event` is guaranteed to be a [key pressed](KeyEventKind::Press) event.
+    ///
+    pub fn key_pressed(&mut self, key_event: KeyEvent) -> ControlFlow<DialogResult> {
+        log_key_pressed!("ProgressDialog");
+        if let Some(buttons) = self.buttons.as_mut() {
+            if let ControlFlow::Break(ControlResult::Selected(_)) = buttons.key_pressed(key_event) {
+                break_event!(DialogResult::Cancel)?;
+            }
+            self.frame.key_pressed(key_event)?;
+        }
         break_event!(DialogResult::Continue)
     }
     /// Draw the progress dialog centered on the terminal screen. The cursor screen position will always be `None`.
@@ -127,14 +168,27 @@ impl ProgressDialog {
             .alignment(Alignment::Left)
             .wrap(Wrap { trim: true })
             .render(description_area, buffer);
+        // show the status if there is one
+        if let Some(status) = &self.status {
+            let status_area = inner_rect(content_area, (0, 1), (0, 2));
+            Paragraph::new(Line::raw(status))
+                .style(styles.get(StyleId::Text))
+                .alignment(Alignment::Left)
+                .render(status_area, buffer);
+        }
         // show the indicator
         let progress_indicator = self.progress_indicator.borrow_mut().next();
-        let indicator_line = inner_rect(content_area, (0, -1), (0, 0));
+        let indicator_line = inner_rect(content_area, (0, 2), (0, 3));
         let indicator_area = center_rect!(indicator_line, [self.progress_indicator.borrow().width as u16, 1]);
         Paragraph::new(Line::raw(progress_indicator))
             .style(styles.get(StyleId::Highlight))
             .alignment(Alignment::Left)
             .render(indicator_area, buffer);
+        // show the cancel button if there is one
+        if let Some(buttons) = &self.buttons {
+            let buttons_area = inner_rect(content_area, (0, -1), (0, 0));
+            buttons.render(buttons_area, buffer, styles);
+        }
         None
     }
 }